@@ -0,0 +1,23 @@
+//! Chip independent code shared by the HALs of the RP family
+//!
+//! This only holds the logic which does not touch the hardware, so that it can be shared by the
+//! HALs of chips with different PACs. It is an implementation detail of these HALs, and is
+//! re-exported or wrapped by them where needed:
+//!
+//! * [`clocks`] and [`uart`]: the divider calculations of the clock generators and the UARTs.
+//! * [`pll`]: the PLL configuration, and the search of a configuration for a frequency.
+//! * [`dma`]: the traits of the DMA sources and destinations, implemented by the peripheral
+//!   drivers of each HAL.
+//!
+//! The type-level helpers, the peripheral typestates, the clock tree and the PIO traits stay in
+//! the HAL of each chip. They are sealed with a trait private to the HAL, which is also
+//! implemented for the PAC types and therefore cannot live in a shared crate, or they are built
+//! directly on the PAC register blocks.
+
+#![warn(missing_docs)]
+#![no_std]
+
+pub mod clocks;
+pub mod dma;
+pub mod pll;
+pub mod uart;
@@ -0,0 +1,125 @@
+//! PLL configuration shared by the PLLs of the RP family
+
+use core::{
+    cmp::Reverse,
+    ops::{Range, RangeInclusive},
+};
+
+use fugit::{HertzU32, RateExtU32};
+
+/// Range of the Voltage Controlled Oscillator frequency.
+pub const VCO_FREQ_RANGE: RangeInclusive<HertzU32> = HertzU32::MHz(400)..=HertzU32::MHz(1_600);
+/// Range of both post dividers.
+pub const POSTDIV_RANGE: Range<u8> = 1..7;
+/// Range of the feedback divider.
+pub const FBDIV_RANGE: Range<u16> = 16..320;
+/// Lowest reference frequency, after the reference divider.
+pub const MIN_REF_FREQ: HertzU32 = HertzU32::MHz(5);
+
+/// Parameters for a PLL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PLLConfig {
+    /// Voltage Controlled Oscillator frequency.
+    pub vco_freq: HertzU32,
+
+    /// Reference divider
+    pub refdiv: u8,
+
+    /// Post Divider 1
+    pub post_div1: u8,
+
+    /// Post Divider 2
+    pub post_div2: u8,
+}
+
+impl PLLConfig {
+    /// Find the configuration giving the output frequency closest to `target`, from a reference
+    /// of `xosc_frequency`.
+    ///
+    /// Among the configurations with the smallest error, the one with the highest VCO frequency
+    /// is picked, as it has the lowest jitter, then the one with the largest first post divider.
+    /// Returns the configuration and the frequency it achieves, or `None` if no configuration is
+    /// valid for `xosc_frequency`.
+    ///
+    /// ```
+    /// use fugit::HertzU32;
+    /// use rp_hal_common::pll::PLLConfig;
+    /// let (config, freq) = PLLConfig::from_freq(HertzU32::MHz(12), HertzU32::MHz(200)).unwrap();
+    /// assert_eq!(freq, HertzU32::MHz(200));
+    /// ```
+    pub fn from_freq(xosc_frequency: HertzU32, target: HertzU32) -> Option<(PLLConfig, HertzU32)> {
+        let xosc_hz = xosc_frequency.to_Hz();
+        let target_hz = target.to_Hz();
+        // (error, vco, post_div1, config)
+        let mut best: Option<(u32, u32, u8, PLLConfig)> = None;
+        let max_refdiv = (xosc_hz / MIN_REF_FREQ.to_Hz()).min(u32::from(u8::MAX));
+        for refdiv in (1..=max_refdiv).filter(|refdiv| xosc_hz % refdiv == 0) {
+            let ref_hz = xosc_hz / refdiv;
+            for fbdiv in FBDIV_RANGE {
+                let vco_hz = match ref_hz.checked_mul(u32::from(fbdiv)) {
+                    Some(vco_hz) if VCO_FREQ_RANGE.contains(&HertzU32::from_raw(vco_hz)) => vco_hz,
+                    _ => continue,
+                };
+                // The reference divided by the feedback divider is checked by the HALs when
+                // applying the configuration.
+                if ref_hz >= vco_hz / 16 {
+                    continue;
+                }
+                for post_div1 in POSTDIV_RANGE {
+                    for post_div2 in 1..=post_div1 {
+                        let freq = vco_hz / (u32::from(post_div1) * u32::from(post_div2));
+                        let error = freq.abs_diff(target_hz);
+                        let better = best.map_or(true, |(e, v, p, _)| {
+                            (Reverse(error), vco_hz, post_div1) > (Reverse(e), v, p)
+                        });
+                        if better {
+                            let config = PLLConfig {
+                                vco_freq: vco_hz.Hz(),
+                                refdiv: refdiv as u8,
+                                post_div1,
+                                post_div2,
+                            };
+                            best = Some((error, vco_hz, post_div1, config));
+                        }
+                    }
+                }
+            }
+        }
+        let (_, vco_hz, _, config) = best?;
+        let freq = vco_hz / (u32::from(config.post_div1) * u32::from(config.post_div2));
+        Some((config, freq.Hz()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_freq() {
+        let (config, freq) = PLLConfig::from_freq(HertzU32::MHz(12), HertzU32::MHz(125)).unwrap();
+        assert_eq!(
+            config,
+            PLLConfig {
+                vco_freq: HertzU32::MHz(1500),
+                refdiv: 1,
+                post_div1: 6,
+                post_div2: 2,
+            }
+        );
+        assert_eq!(freq, HertzU32::MHz(125));
+
+        let (config, freq) = PLLConfig::from_freq(HertzU32::MHz(12), HertzU32::MHz(133)).unwrap();
+        assert_eq!(freq, HertzU32::MHz(133));
+        assert_eq!(config.vco_freq, HertzU32::MHz(1596));
+
+        let (_, freq) = PLLConfig::from_freq(HertzU32::MHz(12), HertzU32::MHz(1)).unwrap();
+        assert_eq!(freq, HertzU32::Hz(402_000_000 / 36));
+
+        assert_eq!(
+            PLLConfig::from_freq(HertzU32::MHz(1), HertzU32::MHz(125)),
+            None
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! UART helpers shared by the PL011 based UARTs
+
+/// Calculate the integer and fractional parts of the PL011 (PrimeCell UART) baud rate divider.
+///
+/// Returns `None` if `wanted_baudrate` is zero, or `frequency` is too high to be computed with.
+/// Baud rates out of range are clamped to the closest divider. Code inspired from the C SDK.
+pub fn calculate_baudrate_dividers(wanted_baudrate: u32, frequency: u32) -> Option<(u16, u16)> {
+    // See Chapter 4, Section 2 §7.1 from the RP2040 datasheet for an explanation of how baudrate
+    // is calculated
+    let baudrate_div = frequency
+        .checked_mul(8)
+        .and_then(|r| r.checked_div(wanted_baudrate))?;
+
+    Some(match (baudrate_div >> 7, ((baudrate_div & 0x7F) + 1) / 2) {
+        (0, _) => (1, 0),
+
+        (int_part, _) if int_part >= 65535 => (65535, 0),
+
+        (int_part, frac_part) => (int_part as u16, frac_part as u16),
+    })
+}
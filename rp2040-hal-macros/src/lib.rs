@@ -57,3 +57,52 @@ fn insert_after_static(stmts: impl IntoIterator<Item = Stmt>, insert: Stmt) -> V
 
     stmts
 }
+
+#[proc_macro_attribute]
+pub fn ram_function(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    // The linker ignores the section of generic functions, as they are only instantiated at
+    // their call site.
+    if !f.sig.generics.params.is_empty() {
+        return parse::Error::new(
+            Span::call_site(),
+            "`#[ram_function]` cannot be used on generic functions",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if f.sig.asyncness.is_some() {
+        return parse::Error::new(
+            Span::call_site(),
+            "`#[ram_function]` cannot be used on async functions",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if f.attrs.iter().any(|attr| attr.path().is_ident("inline")) {
+        return parse::Error::new(
+            Span::call_site(),
+            "`#[ram_function]` cannot be combined with `#[inline]`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // `.data` is copied from flash to RAM by cortex-m-rt during startup. Inlining would put
+    // the code back in the caller, which is likely to be in flash.
+    quote!(
+        #[inline(never)]
+        #[cfg_attr(target_arch = "arm", link_section = ".data.ram_func")]
+        #f
+    )
+    .into()
+}
@@ -19,7 +19,8 @@
 //! let pin_adc_counts: u16 = adc.read(&mut adc_pin_0).unwrap();
 //! ```
 //!
-//! Capture ADC reading from temperature sensor. Note that this needs conversion to be a real-world temperature.
+//! Capture ADC reading from temperature sensor. Note that this needs conversion to be a real-world temperature,
+//! e.g. with [`Adc::read_temperature_celsius`].
 //! ```no_run
 //! use embedded_hal::adc::OneShot;
 //! use rp2040_hal::{adc::Adc, gpio::Pins, pac, Sio};
@@ -119,8 +120,10 @@
 use core::convert::Infallible;
 use core::marker::PhantomData;
 use embedded_hal::adc::{Channel, OneShot};
+use fugit::HertzU32;
 
 use crate::{
+    clocks::{AdcClock, Clock},
     dma,
     gpio::{
         bank0::{Gpio26, Gpio27, Gpio28, Gpio29},
@@ -130,6 +133,11 @@ use crate::{
     resets::SubsystemReset,
 };
 
+#[cfg(feature = "async")]
+mod asynch;
+#[cfg(feature = "async")]
+pub use asynch::on_interrupt;
+
 const TEMPERATURE_SENSOR_CHANNEL: u8 = 4;
 
 /// The pin was invalid for the requested operation
@@ -137,6 +145,11 @@ const TEMPERATURE_SENSOR_CHANNEL: u8 = 4;
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InvalidPinError;
 
+/// A conversion failed, and its result is undefined or noisy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConversionError;
+
 /// A pin locked in use with the ADC.
 pub struct AdcPin<P>
 where
@@ -202,8 +215,42 @@ where
 }
 
 /// Internal temperature sensor type
+///
+/// Besides reading the raw ADC counts, the readings can be converted to degrees Celsius with
+/// [`Adc::read_temperature_celsius`] or [`TempSense::to_celsius`], using the formula from
+/// section 4.9.5 of the RP2040 datasheet:
+///
+/// ```text
+///  T = 27 - (V - 0.706) / 0.001721
+/// ```
+///
+/// The sensor is not very accurate, so for better results the conversion can be calibrated
+/// with [`TempSense::set_reference_voltage`] and [`TempSense::set_offset`].
 pub struct TempSense {
-    __private: (),
+    vref: f32,
+    offset: f32,
+}
+
+impl TempSense {
+    /// Set the ADC reference voltage used to convert readings, in volts (3.3V by default)
+    ///
+    /// This is the voltage of the ADC_AVDD pin, which is usually the 3.3V supply.
+    pub fn set_reference_voltage(&mut self, vref: f32) {
+        self.vref = vref;
+    }
+
+    /// Set a calibration offset in degrees Celsius, which is added to the converted temperatures
+    ///
+    /// It can be determined by comparing a reading with a reference thermometer.
+    pub fn set_offset(&mut self, offset: f32) {
+        self.offset = offset;
+    }
+
+    /// Convert a reading from the sensor (in ADC counts) to degrees Celsius
+    pub fn to_celsius(&self, counts: u16) -> f32 {
+        let voltage = f32::from(counts) * self.vref / 4096.0;
+        27.0 - (voltage - 0.706) / 0.001721 + self.offset
+    }
 }
 
 impl Channel<Adc> for TempSense {
@@ -253,6 +300,43 @@ impl Adc {
         self.device.result.read().result().bits()
     }
 
+    /// Read a channel, checking that the conversion succeeded
+    ///
+    /// Unlike [`OneShot::read`], this returns [`ConversionError`] if the conversion encountered
+    /// an error, instead of its undefined result.
+    pub fn read_checked<PIN: Channel<Adc, ID = u8>>(
+        &mut self,
+        _pin: &mut PIN,
+    ) -> Result<u16, ConversionError> {
+        let result = self.inner_read(PIN::channel());
+        if self.last_conversion_failed() {
+            Err(ConversionError)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Returns `true` if the most recent conversion encountered an error
+    pub fn last_conversion_failed(&self) -> bool {
+        self.device.cs.read().err().bit_is_set()
+    }
+
+    /// Check if any conversion encountered an error since the last call
+    ///
+    /// This covers conversions in free-running mode as well, so it can be used to check
+    /// samples captured by DMA, after the fact.
+    ///
+    /// This function also clears the `err_sticky` bit if it was set.
+    pub fn had_conversion_error(&mut self) -> bool {
+        let err = self.device.cs.read().err_sticky().bit_is_set();
+        if err {
+            self.device
+                .cs
+                .modify(|_, w| w.err_sticky().clear_bit_by_one());
+        }
+        err
+    }
+
     /// Enable temperature sensor, returns a channel to use.
     ///
     /// This can only be done once before calling [`Adc::disable_temp_sensor()`]. If the sensor has already
@@ -276,7 +360,17 @@ impl Adc {
             // if bit was already set, this is a nop
             w.ts_en().set_bit()
         });
-        disabled.then_some(TempSense { __private: () })
+        disabled.then_some(TempSense {
+            vref: 3.3,
+            offset: 0.0,
+        })
+    }
+
+    /// Read the temperature sensor, and convert the reading to degrees Celsius
+    ///
+    /// See [`TempSense`] for the conversion and how to calibrate it.
+    pub fn read_temperature_celsius(&mut self, sensor: &mut TempSense) -> f32 {
+        sensor.to_celsius(self.inner_read(TEMPERATURE_SENSOR_CHANNEL))
     }
 
     /// Disable temperature sensor, consumes channel
@@ -299,7 +393,7 @@ impl Adc {
         }
     }
 
-    fn inner_read(&mut self, chan: u8) -> u16 {
+    pub(crate) fn inner_read(&mut self, chan: u8) -> u16 {
         while !self.device.cs.read().ready().bit_is_set() {
             cortex_m::asm::nop();
         }
@@ -409,6 +503,31 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
         self
     }
 
+    /// Set the sample rate, computing the clock divider from the frequency of `adc_clock`
+    ///
+    /// The divider is rounded to the nearest 1/256 of a cycle, see [`AdcFifoBuilder::clock_divider`]
+    /// for the details. Rates above `adc_clock / 96` (500ksps with the usual 48MHz) are limited by
+    /// the conversion time, and sample as fast as possible. Rates below `adc_clock / 65536`
+    /// (732.41Hz) use the largest possible divider.
+    ///
+    /// ```no_run
+    /// use fugit::RateExtU32;
+    /// use rp2040_hal::{adc::Adc, clocks::ClocksManager, pac};
+    /// let mut peripherals = pac::Peripherals::take().unwrap();
+    /// let clocks: ClocksManager = todo!();
+    /// let mut adc = Adc::new(peripherals.ADC, &mut peripherals.RESETS);
+    /// let mut temperature_sensor = adc.take_temp_sensor().unwrap();
+    /// let fifo = adc
+    ///     .build_fifo()
+    ///     .sample_rate(&clocks.adc_clock, 8.kHz())
+    ///     .set_channel(&mut temperature_sensor)
+    ///     .start();
+    /// ```
+    pub fn sample_rate(self, adc_clock: &AdcClock, rate: HertzU32) -> Self {
+        let (int, frac) = sample_rate_divider(adc_clock.freq(), rate);
+        self.clock_divider(int, frac)
+    }
+
     /// Select ADC input channel to sample from
     ///
     /// If round-robin mode is used, this will only affect the first sample.
@@ -429,6 +548,8 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
     ///
     /// **NOTE:** *The order in which the channels are specified has no effect!
     /// Channels are always sampled in increasing order, by their channel number (Channel 0, Channel 1, ...).*
+    ///
+    /// Use [`AdcFifo::drain`] to read the interleaved samples together with their channel.
     pub fn round_robin<T: Into<RoundRobin>>(self, selected_channels: T) -> Self {
         let RoundRobin(bits) = selected_channels.into();
         self.adc
@@ -438,9 +559,13 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
         self
     }
 
-    /// Enable the FIFO interrupt ([`ADC_IRQ_FIFO`](pac::Interrupt::ADC_IRQ_FIFO))
+    /// Enable the FIFO interrupt ([`ADC_IRQ_FIFO`](crate::pac::Interrupt::ADC_IRQ_FIFO))
     ///
     /// It will be triggered whenever there are at least `threshold` samples waiting in the FIFO.
+    /// The FIFO holds 4 samples, so `threshold` should be between 1 and 4.
+    ///
+    /// The interrupt can't be cleared explicitly: it stays pending until enough samples were read
+    /// for the FIFO level to drop below the threshold, so the interrupt handler must read them.
     pub fn enable_interrupt(self, threshold: u8) -> Self {
         self.adc.device.inte.modify(|_, w| w.fifo().set_bit());
         self.adc
@@ -464,6 +589,20 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
         }
     }
 
+    /// Include the conversion error bit in the samples written to the FIFO.
+    ///
+    /// Bit 15 of a sample is set if its conversion failed, which is mostly useful for DMA
+    /// transfers, as the samples can't be checked as they are captured. Samples with this bit set
+    /// should be discarded, as their value is meaningless. When reading the FIFO directly, use
+    /// [`AdcFifo::read_checked`] to check the bit.
+    ///
+    /// The bit stays in place with [`AdcFifoBuilder::shift_8bit`], so it is only transferred
+    /// to `u16` buffers.
+    pub fn enable_error_bit(self) -> Self {
+        self.adc.device.fcs.modify(|_, w| w.err().set_bit());
+        self
+    }
+
     /// Enable DMA for the FIFO.
     ///
     /// This must be called to be able to transfer data from the ADC using a DMA transfer.
@@ -489,10 +628,7 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
     pub fn start(self) -> AdcFifo<'a, Word> {
         self.adc.device.fcs.modify(|_, w| w.en().set_bit());
         self.adc.device.cs.modify(|_, w| w.start_many().set_bit());
-        AdcFifo {
-            adc: self.adc,
-            marker: PhantomData,
-        }
+        AdcFifo::new(self.adc)
     }
 
     /// Enable ADC FIFO, but do not start conversion yet
@@ -502,10 +638,7 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
     /// Use [`AdcFifo::resume`] to start conversion.
     pub fn prepare(self) -> AdcFifo<'a, Word> {
         self.adc.device.fcs.modify(|_, w| w.en().set_bit());
-        AdcFifo {
-            adc: self.adc,
-            marker: PhantomData,
-        }
+        AdcFifo::new(self.adc)
     }
 }
 
@@ -515,10 +648,21 @@ impl<'a, Word> AdcFifoBuilder<'a, Word> {
 ///
 pub struct AdcFifo<'a, Word> {
     adc: &'a mut Adc,
+    /// The channel of the next sample in the FIFO.
+    channel: u8,
     marker: PhantomData<Word>,
 }
 
 impl<'a, Word> AdcFifo<'a, Word> {
+    fn new(adc: &'a mut Adc) -> Self {
+        let channel = adc.device.cs.read().ainsel().bits();
+        Self {
+            adc,
+            channel,
+            marker: PhantomData,
+        }
+    }
+
     #[allow(clippy::len_without_is_empty)]
     /// Returns the number of elements currently in the fifo
     pub fn len(&mut self) -> u8 {
@@ -657,11 +801,19 @@ impl<'a, Word> AdcFifo<'a, Word> {
         while self.len() > 0 {
             self.read_from_fifo();
         }
-        // disable fifo, reset threshold to 0 and disable DMA
-        self.adc
-            .device
-            .fcs
-            .modify(|_, w| unsafe { w.en().clear_bit().thresh().bits(0).dreq_en().clear_bit() });
+        // disable fifo, reset threshold to 0, disable DMA, shift and error bit
+        self.adc.device.fcs.modify(|_, w| unsafe {
+            w.en()
+                .clear_bit()
+                .thresh()
+                .bits(0)
+                .dreq_en()
+                .clear_bit()
+                .shift()
+                .clear_bit()
+                .err()
+                .clear_bit()
+        });
         // reset clock divider
         self.adc
             .device
@@ -670,6 +822,37 @@ impl<'a, Word> AdcFifo<'a, Word> {
         self.adc
     }
 
+    /// Enable the FIFO interrupt, after it was disabled with [`AdcFifo::disable_interrupt`]
+    ///
+    /// The threshold set by [`AdcFifoBuilder::enable_interrupt`] or [`AdcFifo::set_threshold`] is kept.
+    pub fn enable_interrupt(&mut self) {
+        self.adc.device.inte.modify(|_, w| w.fifo().set_bit());
+    }
+
+    /// Disable the FIFO interrupt
+    ///
+    /// Samples are still captured to the FIFO while the interrupt is disabled.
+    pub fn disable_interrupt(&mut self) {
+        self.adc.device.inte.modify(|_, w| w.fifo().clear_bit());
+    }
+
+    /// Set the number of samples in the FIFO from which the interrupt and DMA requests are triggered
+    ///
+    /// See [`AdcFifoBuilder::enable_interrupt`]. DMA transfers require a threshold of 1.
+    pub fn set_threshold(&mut self, threshold: u8) {
+        self.adc
+            .device
+            .fcs
+            .modify(|_, w| unsafe { w.thresh().bits(threshold) });
+    }
+
+    /// Returns `true` if the FIFO interrupt is enabled and pending
+    ///
+    /// It stays pending until the FIFO level drops below the threshold.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.adc.device.ints.read().fifo().bit_is_set()
+    }
+
     /// Block until a ADC_IRQ_FIFO interrupt occurs
     ///
     /// Interrupts must be enabled ([`AdcFifoBuilder::enable_interrupt`]), or else this methods blocks forever.
@@ -678,7 +861,58 @@ impl<'a, Word> AdcFifo<'a, Word> {
     }
 
     fn read_from_fifo(&mut self) -> u16 {
-        self.adc.device.fifo.read().val().bits()
+        self.read_sample().0
+    }
+
+    /// Read a sample and its error bit from the FIFO.
+    fn read_sample(&mut self) -> (u16, bool) {
+        let rrobin = self.adc.device.cs.read().rrobin().bits();
+        self.channel = next_round_robin_channel(self.channel, rrobin);
+        let sample = self.adc.device.fifo.read();
+        (sample.val().bits(), sample.err().bit_is_set())
+    }
+
+    /// Check if any conversion encountered an error since the last call
+    ///
+    /// See [`Adc::had_conversion_error`].
+    pub fn had_conversion_error(&mut self) -> bool {
+        self.adc.had_conversion_error()
+    }
+
+    /// Returns the channel the next sample read from the FIFO was captured from
+    ///
+    /// In round-robin mode, the channels are sampled in increasing order, starting with
+    /// the one selected by [`AdcFifoBuilder::set_channel`]. The channel is tracked by counting
+    /// the samples read from the FIFO, so it is only correct as long as no samples were lost
+    /// to a FIFO overrun (see [`AdcFifo::is_over`]) or read by a DMA transfer.
+    pub fn next_channel(&mut self) -> u8 {
+        self.channel
+    }
+
+    /// Returns an iterator over the samples currently in the FIFO, with their channels
+    ///
+    /// The iterator yields `(channel, sample)` pairs until the FIFO is empty. This is
+    /// convenient in round-robin mode, where the samples of the channels are interleaved:
+    ///
+    /// ```ignore
+    /// let mut fifo = adc.build_fifo()
+    ///     .set_channel(&mut adc_pin_0)
+    ///     .round_robin((&mut adc_pin_0, &mut temperature_sensor))
+    ///     .start();
+    ///
+    /// loop {
+    ///     for (channel, sample) in fifo.drain() {
+    ///         match channel {
+    ///             0 => handle_pin_0(sample),
+    ///             _ => handle_temperature(sample),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// See [`AdcFifo::next_channel`] for the limitations of the channel tracking.
+    pub fn drain(&mut self) -> Drain<'_, 'a, Word> {
+        Drain { fifo: self }
     }
 
     /// Returns a read-target for initiating DMA transfers
@@ -695,6 +929,16 @@ impl<'a> AdcFifo<'a, u16> {
     pub fn read(&mut self) -> u16 {
         self.read_from_fifo()
     }
+
+    /// Read a single value from the fifo, checking that its conversion succeeded
+    ///
+    /// Errors are only detected if the FIFO was built with [`AdcFifoBuilder::enable_error_bit`].
+    pub fn read_checked(&mut self) -> Result<u16, ConversionError> {
+        match self.read_sample() {
+            (_, true) => Err(ConversionError),
+            (value, false) => Ok(value),
+        }
+    }
 }
 
 impl<'a> AdcFifo<'a, u8> {
@@ -704,6 +948,66 @@ impl<'a> AdcFifo<'a, u8> {
     pub fn read(&mut self) -> u8 {
         self.read_from_fifo() as u8
     }
+
+    /// Read a single value from the fifo, checking that its conversion succeeded
+    ///
+    /// Errors are only detected if the FIFO was built with [`AdcFifoBuilder::enable_error_bit`].
+    pub fn read_checked(&mut self) -> Result<u8, ConversionError> {
+        match self.read_sample() {
+            (_, true) => Err(ConversionError),
+            (value, false) => Ok(value as u8),
+        }
+    }
+}
+
+/// Iterator over the samples in an [`AdcFifo`], returned by [`AdcFifo::drain`]
+pub struct Drain<'f, 'a, Word> {
+    fifo: &'f mut AdcFifo<'a, Word>,
+}
+
+impl<'f, 'a> Iterator for Drain<'f, 'a, u16> {
+    type Item = (u8, u16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fifo.len() == 0 {
+            return None;
+        }
+        let channel = self.fifo.next_channel();
+        Some((channel, self.fifo.read()))
+    }
+}
+
+impl<'f, 'a> Iterator for Drain<'f, 'a, u8> {
+    type Item = (u8, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.fifo.len() == 0 {
+            return None;
+        }
+        let channel = self.fifo.next_channel();
+        Some((channel, self.fifo.read()))
+    }
+}
+
+/// The `(int, frac)` divider values for sampling at `rate` with an ADC clock of `adc_freq`.
+fn sample_rate_divider(adc_freq: HertzU32, rate: HertzU32) -> (u16, u8) {
+    const CYCLES_PER_CONVERSION: u64 = 96;
+    let rate = u64::from(rate.to_Hz().max(1));
+    // cycles per sample, in units of 1/256 cycle
+    let period = (u64::from(adc_freq.to_Hz()) * 256 + rate / 2) / rate;
+    if period < CYCLES_PER_CONVERSION * 256 {
+        return (0, 0);
+    }
+    let div = (period - 256).min(0xff_ffff);
+    ((div >> 8) as u16, div as u8)
+}
+
+/// The channel sampled after `channel` in round-robin mode, with the channels in `rrobin`.
+fn next_round_robin_channel(channel: u8, rrobin: u8) -> u8 {
+    (1..=TEMPERATURE_SENSOR_CHANNEL + 1)
+        .map(|i| (channel + i) % (TEMPERATURE_SENSOR_CHANNEL + 1))
+        .find(|c| rrobin & (1 << c) != 0)
+        .unwrap_or(channel)
 }
 
 /// Represents a [`dma::ReadTarget`] for the [`AdcFifo`]
@@ -736,6 +1040,7 @@ impl<Word> dma::EndlessReadTarget for DmaReadTarget<Word> {}
 /// Internal struct representing values for the `CS.RROBIN` register.
 ///
 /// See [`AdcFifoBuilder::round_robin`], for usage example.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RoundRobin(u8);
 
 impl<PIN: Channel<Adc, ID = u8>> From<PIN> for RoundRobin {
@@ -795,3 +1100,48 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin_order() {
+        // channels 0, 2 and 4
+        let rrobin = 0b10101;
+        assert_eq!(next_round_robin_channel(0, rrobin), 2);
+        assert_eq!(next_round_robin_channel(2, rrobin), 4);
+        assert_eq!(next_round_robin_channel(4, rrobin), 0);
+        // the first channel does not need to be part of the mask
+        assert_eq!(next_round_robin_channel(1, rrobin), 2);
+        assert_eq!(next_round_robin_channel(3, 0), 3);
+    }
+
+    #[test]
+    fn temperature_conversion() {
+        let mut sensor = TempSense {
+            vref: 3.3,
+            offset: 0.0,
+        };
+        // 0.706V at 27°C
+        assert!((sensor.to_celsius(876) - 27.0).abs() < 0.5);
+        // about 1.721mV, or 2.1 counts per °C
+        assert!(sensor.to_celsius(855) > 36.0);
+        sensor.set_offset(-2.0);
+        assert!((sensor.to_celsius(876) - 25.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn sample_rate() {
+        use fugit::RateExtU32;
+        // the examples from the `clock_divider` documentation
+        assert_eq!(sample_rate_divider(48.MHz(), 1.kHz()), (47999, 0));
+        assert_eq!(sample_rate_divider(48.MHz(), 1024.Hz()), (46874, 0));
+        assert_eq!(sample_rate_divider(48.MHz(), 1337.Hz()), (35900, 70));
+        assert_eq!(sample_rate_divider(48.MHz(), 4096.Hz()), (11717, 192));
+        assert_eq!(sample_rate_divider(48.MHz(), 96.kHz()), (499, 0));
+        assert_eq!(sample_rate_divider(48.MHz(), 500.kHz()), (95, 0));
+        assert_eq!(sample_rate_divider(48.MHz(), 1.MHz()), (0, 0));
+        assert_eq!(sample_rate_divider(48.MHz(), 1.Hz()), (0xffff, 0xff));
+    }
+}
@@ -0,0 +1,75 @@
+//! Analog-Digital Converter (ADC) - Async Code
+//!
+//! The ADC only raises an interrupt from its FIFO, so the async methods wait for the FIFO to
+//! reach its threshold. See [`async_utils`](crate::async_utils).
+
+use embedded_hal::adc::Channel;
+
+use super::{Adc, AdcFifo};
+use crate::{
+    async_utils::{wait_for, WakerSlot},
+    pac::ADC,
+};
+
+static WAKER: WakerSlot = WakerSlot::new();
+
+/// Wake the task waiting for the ADC.
+///
+/// Call this from the `ADC_IRQ_FIFO` interrupt handler. It disables the FIFO interrupt, so
+/// [`AdcFifo::enable_interrupt`] should not be used at the same time.
+pub fn on_interrupt() {
+    // Safety: only the interrupt enable is modified, from the interrupt handler which cannot be
+    // preempted by the task modifying it.
+    let adc = unsafe { &*ADC::ptr() };
+    if adc.ints.read().fifo().bit_is_set() {
+        adc.inte.modify(|_, w| w.fifo().clear_bit());
+        WAKER.wake();
+    }
+}
+
+/// Wait until the FIFO level reaches its threshold.
+async fn wait_for_fifo(adc: &ADC) {
+    wait_for(
+        &WAKER,
+        || adc.intr.read().fifo().bit_is_set().then_some(()),
+        || critical_section::with(|_| adc.inte.modify(|_, w| w.fifo().set_bit())),
+    )
+    .await
+}
+
+impl Adc {
+    /// Read a channel, waiting for the conversion without blocking
+    ///
+    /// The sample is passed through the FIFO, which is enabled for the duration of the
+    /// conversion, so this cannot be used while free-running mode is set up.
+    pub async fn read_async<PIN: Channel<Adc, ID = u8>>(&mut self, _pin: &mut PIN) -> u16 {
+        while !self.device.cs.read().ready().bit_is_set() {}
+
+        self.device
+            .fcs
+            .write(|w| unsafe { w.en().set_bit().thresh().bits(1) });
+        while self.device.fcs.read().level().bits() > 0 {
+            self.device.fifo.read();
+        }
+
+        self.device
+            .cs
+            .modify(|_, w| unsafe { w.ainsel().bits(PIN::channel()).start_once().set_bit() });
+        wait_for_fifo(&self.device).await;
+        let result = self.device.fifo.read().val().bits();
+
+        self.device.fcs.write(|w| unsafe { w.bits(0) });
+        result
+    }
+}
+
+impl<'a, Word> AdcFifo<'a, Word> {
+    /// Wait until the FIFO holds as many samples as its threshold, without blocking
+    ///
+    /// This is the async version of [`AdcFifo::wait_for_interrupt`]. The threshold is set by
+    /// [`AdcFifoBuilder::enable_interrupt`](super::AdcFifoBuilder::enable_interrupt) or
+    /// [`AdcFifo::set_threshold`]. With a threshold of 0, this returns immediately.
+    pub async fn wait_for_samples(&mut self) {
+        wait_for_fifo(&self.adc.device).await
+    }
+}
@@ -0,0 +1,107 @@
+//! Shared infrastructure for the async drivers
+//!
+//! An async driver waits for a hardware event by registering the waker of its task in a
+//! [`WakerSlot`], enabling the interrupt of the event, and returning `Pending`. The interrupt
+//! handler of the peripheral disables the interrupt and wakes the task, which then checks for
+//! the event again.
+//!
+//! The HAL does not define interrupt handlers itself. The application calls the handler
+//! functions of the drivers it uses from its own handlers, on the core running the async
+//! tasks, and unmasks the interrupts in the NVIC:
+//!
+//! | Driver | Interrupt | Handler function |
+//! |--------|-----------|------------------|
+//! | GPIO `wait_for_*` | `IO_IRQ_BANK0`, `IO_IRQ_QSPI` | [`gpio::on_interrupt`](crate::gpio::on_interrupt) |
+//! | GPIO `embedded-hal-async` `Wait` | `IO_IRQ_BANK0`, `IO_IRQ_QSPI` | [`gpio::on_interrupt`](crate::gpio::on_interrupt) |
+//! | Timer alarm `wait`, `delay` and `embedded-hal-async` `DelayNs` | `TIMER_IRQ_0` to `TIMER_IRQ_3` | `AlarmN::on_interrupt`, e.g. [`Alarm0::on_interrupt`](crate::timer::Alarm0::on_interrupt) |
+//! | UART `embedded-io-async` traits | `UART0_IRQ`, `UART1_IRQ` | [`uart::on_interrupt`](crate::uart::on_interrupt) |
+//! | I2C controller `embedded-hal-async` `I2c` | `I2C0_IRQ`, `I2C1_IRQ` | [`i2c::on_interrupt`](crate::i2c::on_interrupt) |
+//! | SPI `embedded-hal-async` `SpiBus` | `SPI0_IRQ`, `SPI1_IRQ` | [`spi::on_interrupt`](crate::spi::on_interrupt) |
+//! | ADC `read_async` and FIFO `wait_for_samples` | `ADC_IRQ_FIFO` | [`adc::on_interrupt`](crate::adc::on_interrupt) |
+//! | DMA transfer `wait_async` | `DMA_IRQ_0` | [`dma::on_interrupt`](crate::dma::on_interrupt) |
+//!
+//! ```ignore
+//! use rp2040_hal::pac::interrupt;
+//!
+//! #[interrupt]
+//! fn IO_IRQ_BANK0() {
+//!     rp2040_hal::gpio::on_interrupt();
+//! }
+//! ```
+
+use core::{
+    cell::RefCell,
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+use critical_section::Mutex;
+
+/// Storage for the waker of a task waiting for an interrupt.
+pub struct WakerSlot {
+    waker: Mutex<RefCell<Option<Waker>>>,
+}
+
+impl WakerSlot {
+    /// An empty slot.
+    pub const fn new() -> Self {
+        Self {
+            waker: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Register the waker to be woken by the next call to [`wake`](Self::wake).
+    ///
+    /// This replaces the waker registered before, if any.
+    pub fn register(&self, waker: &Waker) {
+        critical_section::with(|cs| {
+            let mut slot = self.waker.borrow_ref_mut(cs);
+            match slot.as_ref() {
+                Some(registered) if registered.will_wake(waker) => {}
+                _ => *slot = Some(waker.clone()),
+            }
+        });
+    }
+
+    /// Wake the registered waker, if any.
+    pub fn wake(&self) {
+        if let Some(waker) = critical_section::with(|cs| self.waker.borrow_ref_mut(cs).take()) {
+            waker.wake();
+        }
+    }
+
+    /// Whether a waker is registered.
+    pub fn is_registered(&self) -> bool {
+        critical_section::with(|cs| self.waker.borrow_ref(cs).is_some())
+    }
+}
+
+impl Default for WakerSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wait until `ready` returns `Some`.
+///
+/// Each time the event is not ready yet, the task's waker is registered in `slot` and `enable`
+/// is called to enable the interrupt waking it up. `ready` is checked again after enabling the
+/// interrupt, so that an event happening in between is not missed.
+pub(crate) async fn wait_for<T>(
+    slot: &WakerSlot,
+    mut ready: impl FnMut() -> Option<T>,
+    mut enable: impl FnMut(),
+) -> T {
+    poll_fn(|cx| {
+        if let Some(value) = ready() {
+            return Poll::Ready(value);
+        }
+        slot.register(cx.waker());
+        enable();
+        match ready() {
+            Some(value) => Poll::Ready(value),
+            None => Poll::Pending,
+        }
+    })
+    .await
+}
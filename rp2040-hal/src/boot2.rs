@@ -0,0 +1,54 @@
+//! Second stage bootloader
+//!
+//! The first 256 bytes of flash hold the second stage bootloader (boot2), which configures
+//! the SSI and the flash chip for fast execute-in-place access. It depends on the flash chip,
+//! so the [`rp2040-boot2`](https://crates.io/crates/rp2040-boot2) crate provides variants for
+//! several of them.
+//!
+//! Enabling exactly one of the following features places the matching bootloader in the
+//! `.boot2` section, so it does not need to be declared by the application:
+//!
+//! * **boot2-w25q080** - Winbond W25Q080 and compatible chips, as found on the Raspberry Pi Pico
+//! * **boot2-at25sf128a** - Adesto AT25SF128A
+//! * **boot2-is25lp080** - ISSI IS25LP080
+//! * **boot2-generic-03h** - Any flash chip, using the slow but universal `03h` read command
+//!
+//! The linker script must keep the section, for example with
+//! `.boot2 ORIGIN(BOOT2) : { KEEP(*(.boot2)); } > BOOT2` as in the usual `memory.x`.
+//!
+//! The bootloader is exported as the `BOOT2_FIRMWARE` symbol, the name the templates and
+//! board support packages use. An application enabling one of these features must remove its
+//! own declaration, e.g.
+//!
+//! ```ignore
+//! #[link_section = ".boot2"]
+//! #[no_mangle]
+//! #[used]
+//! pub static BOOT2_FIRMWARE: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+//! ```
+//!
+//! Otherwise the two symbols clash at link time, or overflow the 256 bytes of the `.boot2`
+//! section when the application declared it under another name.
+
+const SELECTED: usize = cfg!(feature = "boot2-w25q080") as usize
+    + cfg!(feature = "boot2-at25sf128a") as usize
+    + cfg!(feature = "boot2-is25lp080") as usize
+    + cfg!(feature = "boot2-generic-03h") as usize;
+
+const _: () = assert!(SELECTED == 1, "only one boot2 feature may be enabled");
+
+/// The selected second stage bootloader.
+#[link_section = ".boot2"]
+#[no_mangle]
+#[used]
+pub static BOOT2_FIRMWARE: [u8; 256] = {
+    #[cfg(feature = "boot2-w25q080")]
+    let boot2 = rp2040_boot2::BOOT_LOADER_W25Q080;
+    #[cfg(feature = "boot2-at25sf128a")]
+    let boot2 = rp2040_boot2::BOOT_LOADER_AT25SF128A;
+    #[cfg(feature = "boot2-is25lp080")]
+    let boot2 = rp2040_boot2::BOOT_LOADER_IS25LP080;
+    #[cfg(feature = "boot2-generic-03h")]
+    let boot2 = rp2040_boot2::BOOT_LOADER_GENERIC_03H;
+    boot2
+};
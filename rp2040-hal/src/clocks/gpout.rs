@@ -0,0 +1,89 @@
+//! Routing of the GPOUT clocks to their pins
+//!
+//! Each of the four GPOUT clocks drives a single pin: GPOUT0 GPIO21, GPOUT1 GPIO23, GPOUT2
+//! GPIO24 and GPOUT3 GPIO25. The source and fractional divider are set with
+//! [`Clock::configure_clock`](super::Clock::configure_clock), as for the other clocks.
+//!
+//! ```no_run
+//! use fugit::HertzU32;
+//! use rp2040_hal::{clocks::{Clock, ClocksManager}, gpio::Pins, pac, Sio};
+//! let mut peripherals = pac::Peripherals::take().unwrap();
+//! let sio = Sio::new(peripherals.SIO);
+//! let pins = Pins::new(peripherals.IO_BANK0, peripherals.PADS_BANK0, sio.gpio_bank0, &mut peripherals.RESETS);
+//! let mut clocks: ClocksManager = todo!();
+//! // Output clk_sys / 1000 on GPIO21
+//! let sys = clocks.system_clock.freq();
+//! clocks.gpio_output0_clock.configure_clock(&clocks.system_clock, sys / 1000).unwrap();
+//! clocks.gpio_output0_clock.set_duty_cycle_correction(true);
+//! let gpout = clocks.gpio_output0_clock.output_to(pins.gpio21);
+//! ```
+
+use super::{
+    GpioOutput0Clock, GpioOutput1Clock, GpioOutput2Clock, GpioOutput3Clock, StoppableClock,
+};
+use crate::gpio::{
+    bank0::{Gpio21, Gpio23, Gpio24, Gpio25},
+    Function, FunctionClock, Pin, PinId, PullType,
+};
+
+/// A pin driven by one of the GPOUT clocks.
+pub struct GpoutPin<I: PinId, P: PullType> {
+    pin: Pin<I, FunctionClock, P>,
+}
+
+impl<I: PinId, P: PullType> GpoutPin<I, P> {
+    /// Releases the pin.
+    ///
+    /// The clock keeps running until it is disabled with [`StoppableClock::disable`].
+    pub fn free(self) -> Pin<I, FunctionClock, P> {
+        self.pin
+    }
+}
+
+macro_rules! gpout {
+    ($($name:ident: $reg:ident, $pin:ident),*) => {
+        $crate::paste::paste! {
+            $(
+            impl $name {
+                /// Enable or disable the duty cycle correction.
+                ///
+                /// With an odd integer divider, the output is only high for less than half of
+                /// the period, unless the correction is enabled. It has no effect on the
+                /// fractional part of the divider.
+                pub fn set_duty_cycle_correction(&mut self, enable: bool) {
+                    unsafe { self.shared_dev.get() }
+                        .[<$reg _ctrl>]
+                        .modify(|_, w| w.dc50().bit(enable));
+                }
+
+                /// Whether the duty cycle correction is enabled.
+                pub fn duty_cycle_correction(&self) -> bool {
+                    unsafe { self.shared_dev.get() }
+                        .[<$reg _ctrl>]
+                        .read()
+                        .dc50()
+                        .bit_is_set()
+                }
+
+                #[doc = "Route this clock to its pin, [`" $pin "`], and enable it."]
+                pub fn output_to<F: Function, P: PullType>(
+                    &mut self,
+                    pin: Pin<$pin, F, P>,
+                ) -> GpoutPin<$pin, P> {
+                    self.enable();
+                    GpoutPin {
+                        pin: pin.into_function(),
+                    }
+                }
+            }
+            )*
+        }
+    };
+}
+
+gpout!(
+    GpioOutput0Clock: clk_gpout0, Gpio21,
+    GpioOutput1Clock: clk_gpout1, Gpio23,
+    GpioOutput2Clock: clk_gpout2, Gpio24,
+    GpioOutput3Clock: clk_gpout3, Gpio25
+);
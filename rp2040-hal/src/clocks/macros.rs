@@ -119,13 +119,13 @@ macro_rules! clock {
                 fn unwrap_src(&self) -> $crate::pac::clocks::[<$reg _ctrl>]::SRC_A{
                     match self {
                         Self::Src(v) => *v,
-                        Self::Aux(_) => panic!(),
+                        Self::Aux(_) => hw_unreachable!("Not a glitchless source."),
                     }
                 }
 
                 fn unwrap_aux(&self) -> $crate::pac::clocks::[<$reg _ctrl>]::AUXSRC_A {
                     match self {
-                        Self::Src(_) => panic!(),
+                        Self::Src(_) => hw_unreachable!("Not an auxiliary source."),
                         Self::Aux(v) => *v
                     }
                 }
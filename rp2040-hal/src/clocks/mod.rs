@@ -60,17 +60,33 @@
 //! # }
 //! ```
 //!
+//! ## Changing clocks at runtime
+//!
+//! The drivers compute their dividers from the clock frequency given when creating them, so
+//! they have to be updated when clk_sys or clk_peri change, e.g. after
+//! [`PhaseLockedLoop::reconfigure`](crate::pll::PhaseLockedLoop::reconfigure):
+//! * UART: [`UartPeripheral::set_baudrate`](crate::uart::UartPeripheral::set_baudrate), with
+//!   the frequency of clk_peri
+//! * SPI: [`Spi::set_baudrate`](crate::spi::Spi::set_baudrate), with the frequency of clk_peri
+//! * I2C: [`I2C::set_frequency`](crate::i2c::I2C::set_frequency), with the frequency of clk_sys
+//! * RTC: [`RealTimeClock::set_clock`](crate::rtc::RealTimeClock::set_clock), after changing
+//!   clk_rtc
+//!
 //! See [Chapter 2 Section 15](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) for more details
 use core::{convert::Infallible, marker::PhantomData};
 use fugit::{HertzU32, RateExtU32};
+use rp_hal_common::clocks::fractional_div;
 
 use crate::{
     pac::{self, CLOCKS, PLL_SYS, PLL_USB, RESETS, XOSC},
     pll::{
         common_configs::{PLL_SYS_125MHZ, PLL_USB_48MHZ},
-        setup_pll_blocking, Error as PllError, Locked, PhaseLockedLoop,
+        setup_pll_blocking, Error as PllError, Locked, PLLConfig, PhaseLockedLoop,
     },
+    power::SleepClocks,
+    ssi::{Error as SsiError, Ssi},
     typelevel::Sealed,
+    vreg::{Error as VregError, Voltage, Vreg},
     watchdog::Watchdog,
     xosc::{setup_xosc_blocking, CrystalOscillator, Error as XoscError, Stable},
 };
@@ -78,6 +94,9 @@ use crate::{
 #[macro_use]
 mod macros;
 mod clock_sources;
+mod gpout;
+
+pub use gpout::GpoutPin;
 
 use clock_sources::PllSys;
 
@@ -102,6 +121,7 @@ impl ShareableClocks {
 /// Something when wrong setting up the clock
 #[non_exhaustive]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ClockError {
     /// The frequency desired is higher than the source frequency
     CantIncreaseFreq,
@@ -301,13 +321,238 @@ impl ClocksManager {
             .configure_clock(&self.system_clock, self.system_clock.freq())
     }
 
+    /// Select the clocks kept running while both processors are sleeping.
+    ///
+    /// This applies to every `wfi` and `wfe` with the SLEEPDEEP bit set, e.g. in the idle task
+    /// of an RTOS. [`power::sleep`](crate::power::sleep) sets the clocks itself, and restores
+    /// these afterwards.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{clocks::ClocksManager, power::SleepClocks};
+    /// let mut clocks: ClocksManager = todo!();
+    /// // Only keep the RTC and the timer running
+    /// clocks.configure_sleep_enable(SleepClocks::new().rtc().timer());
+    /// ```
+    pub fn configure_sleep_enable(&mut self, clocks: SleepClocks) {
+        let (en0, en1) = clocks.bits();
+        self.clocks.sleep_en0.write(|w| unsafe { w.bits(en0) });
+        self.clocks.sleep_en1.write(|w| unsafe { w.bits(en1) });
+    }
+
+    /// The clocks kept running while both processors are sleeping.
+    pub fn sleep_enable(&self) -> SleepClocks {
+        SleepClocks::from_bits(
+            self.clocks.sleep_en0.read().bits(),
+            self.clocks.sleep_en1.read().bits(),
+        )
+    }
+
+    /// Select the clocks running while the processors are awake, stopping the clocks of the
+    /// unused blocks.
+    ///
+    /// The clocks the processors need to run code and the clocks of the system control blocks,
+    /// see [`SleepClocks::core`] and [`SleepClocks::system`], are always kept running. The
+    /// registers of a block whose clock is stopped must not be accessed.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{clocks::ClocksManager, power::SleepClocks};
+    /// let mut clocks: ClocksManager = todo!();
+    /// clocks.configure_wake_enable(SleepClocks::new().gpio().timer().uart0());
+    /// ```
+    pub fn configure_wake_enable(&mut self, clocks: SleepClocks) {
+        let (en0, en1) = clocks.core().system().bits();
+        self.clocks.wake_en0.write(|w| unsafe { w.bits(en0) });
+        self.clocks.wake_en1.write(|w| unsafe { w.bits(en1) });
+    }
+
+    /// The clocks running while the processors are awake.
+    pub fn wake_enable(&self) -> SleepClocks {
+        SleepClocks::from_bits(
+            self.clocks.wake_en0.read().bits(),
+            self.clocks.wake_en1.read().bits(),
+        )
+    }
+
+    /// Enable the resuscitation (resus) of clk_sys.
+    ///
+    /// If clk_sys stops for `timeout` cycles of clk_ref, e.g. because its PLL was shut down by
+    /// mistake, the hardware switches it back to clk_ref. The firmware keeps running at the
+    /// frequency of clk_ref, and can call [`ClocksManager::recover_from_resus`] to bring the
+    /// clock configuration back in line with the hardware.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::clocks::ClocksManager;
+    /// let mut clocks: ClocksManager = todo!();
+    /// clocks.enable_resus(0xff);
+    /// clocks.enable_resus_interrupt();
+    /// // In the CLOCKS_IRQ interrupt handler
+    /// if clocks.is_resussed() {
+    ///     clocks.recover_from_resus().unwrap();
+    ///     // Set up the PLL and clk_sys again, as well as anything depending on clk_sys
+    /// }
+    /// ```
+    pub fn enable_resus(&mut self, timeout: u8) {
+        self.clocks.clk_sys_resus_ctrl.write(|w| unsafe {
+            w.timeout().bits(timeout);
+            w.enable().set_bit()
+        });
+    }
+
+    /// Disable the resuscitation of clk_sys.
+    pub fn disable_resus(&mut self) {
+        self.clocks
+            .clk_sys_resus_ctrl
+            .modify(|_, w| w.enable().clear_bit());
+    }
+
+    /// Whether clk_sys was resuscitated.
+    pub fn is_resussed(&self) -> bool {
+        self.clocks
+            .clk_sys_resus_status
+            .read()
+            .resussed()
+            .bit_is_set()
+    }
+
+    /// Enable the CLOCKS_IRQ interrupt, raised when clk_sys is resuscitated.
+    pub fn enable_resus_interrupt(&mut self) {
+        self.clocks.inte.modify(|_, w| w.clk_sys_resus().set_bit());
+    }
+
+    /// Disable the CLOCKS_IRQ interrupt.
+    pub fn disable_resus_interrupt(&mut self) {
+        self.clocks
+            .inte
+            .modify(|_, w| w.clk_sys_resus().clear_bit());
+    }
+
+    /// Recover from a resuscitation of clk_sys.
+    ///
+    /// This selects clk_ref as the source of clk_sys, which it already is in hardware, and then
+    /// clears the resus, which also clears the interrupt. clk_peri is updated if it runs from
+    /// clk_sys. The PLL which stopped and the clocks running from it have to be set up again.
+    pub fn recover_from_resus(&mut self) -> Result<(), ClockError> {
+        let peri_ctrl = self.clocks.clk_peri_ctrl.read();
+        let peri_from_sys = peri_ctrl.enable().bit_is_set() && peri_ctrl.auxsrc().is_clk_sys();
+        let ref_freq = self.reference_clock.freq();
+        self.system_clock
+            .configure_clock(&self.reference_clock, ref_freq)?;
+        if peri_from_sys {
+            self.peripheral_clock
+                .configure_clock(&self.system_clock, ref_freq)?;
+        }
+        self.clocks
+            .clk_sys_resus_ctrl
+            .modify(|_, w| w.clear().set_bit());
+        self.clocks
+            .clk_sys_resus_ctrl
+            .modify(|_, w| w.clear().clear_bit());
+        Ok(())
+    }
+
+    /// Measure the frequency of a clock with the frequency counter.
+    ///
+    /// The counter is timed by clk_ref, so the result is only as accurate as the frequency
+    /// recorded for `self.reference_clock`. The measurement takes about 1 ms and has a
+    /// resolution of 1 kHz. A clock which is not running measures as 0 Hz.
+    pub fn measure_frequency(&self, src: FC0Source) -> HertzU32 {
+        HertzU32::kHz(self.frequency_count_khz(src))
+    }
+
+    /// Measure a clock with the frequency counter, in kHz.
+    pub(crate) fn frequency_count_khz(&self, src: FC0Source) -> u32 {
+        // Wait for a previous measurement to complete.
+        while self.clocks.fc0_status.read().running().bit_is_set() {}
+        self.clocks
+            .fc0_ref_khz
+            .write(|w| unsafe { w.fc0_ref_khz().bits(self.reference_clock.freq().to_kHz()) });
+        self.clocks
+            .fc0_interval
+            .write(|w| unsafe { w.fc0_interval().bits(10) });
+        self.clocks
+            .fc0_min_khz
+            .write(|w| unsafe { w.fc0_min_khz().bits(0) });
+        self.clocks
+            .fc0_max_khz
+            .write(|w| unsafe { w.fc0_max_khz().bits(0x1ff_ffff) });
+        self.clocks
+            .fc0_src
+            .write(|w| w.fc0_src().variant(src.into()));
+        let status = loop {
+            let status = self.clocks.fc0_status.read();
+            if status.done().bit_is_set() {
+                break status;
+            }
+        };
+        // With the full range allowed, the measurement only fails if the clock died.
+        if status.fail().bit_is_set() || status.died().bit_is_set() {
+            return 0;
+        }
+        self.clocks.fc0_result.read().khz().bits()
+    }
+
     /// Releases the CLOCKS block
     pub fn free(self) -> CLOCKS {
         self.clocks
     }
 }
 
+/// The clocks which can be measured by [`ClocksManager::measure_frequency`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FC0Source {
+    /// The system PLL output
+    PllSys,
+    /// The USB PLL output
+    PllUsb,
+    /// The ring oscillator
+    Rosc,
+    /// The ring oscillator, phase shifted
+    RoscPhaseShifted,
+    /// The crystal oscillator
+    Xosc,
+    /// The clock input on GPIO20
+    GPin0,
+    /// The clock input on GPIO22
+    GPin1,
+    /// clk_ref
+    ReferenceClock,
+    /// clk_sys
+    SystemClock,
+    /// clk_peri
+    PeripheralClock,
+    /// clk_usb
+    UsbClock,
+    /// clk_adc
+    AdcClock,
+    /// clk_rtc
+    RtcClock,
+}
+
+impl From<FC0Source> for pac::clocks::fc0_src::FC0_SRC_A {
+    fn from(src: FC0Source) -> Self {
+        use pac::clocks::fc0_src::FC0_SRC_A;
+        match src {
+            FC0Source::PllSys => FC0_SRC_A::PLL_SYS_CLKSRC_PRIMARY,
+            FC0Source::PllUsb => FC0_SRC_A::PLL_USB_CLKSRC_PRIMARY,
+            FC0Source::Rosc => FC0_SRC_A::ROSC_CLKSRC,
+            FC0Source::RoscPhaseShifted => FC0_SRC_A::ROSC_CLKSRC_PH,
+            FC0Source::Xosc => FC0_SRC_A::XOSC_CLKSRC,
+            FC0Source::GPin0 => FC0_SRC_A::CLKSRC_GPIN0,
+            FC0Source::GPin1 => FC0_SRC_A::CLKSRC_GPIN1,
+            FC0Source::ReferenceClock => FC0_SRC_A::CLK_REF,
+            FC0Source::SystemClock => FC0_SRC_A::CLK_SYS,
+            FC0Source::PeripheralClock => FC0_SRC_A::CLK_PERI,
+            FC0Source::UsbClock => FC0_SRC_A::CLK_USB,
+            FC0Source::AdcClock => FC0_SRC_A::CLK_ADC,
+            FC0Source::RtcClock => FC0_SRC_A::CLK_RTC,
+        }
+    }
+}
+
 /// Possible init errors
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InitError {
     /// Something went wrong setting up the Xosc
     XoscErr(XoscError),
@@ -315,6 +560,10 @@ pub enum InitError {
     PllError(PllError),
     /// Something went wrong setting up the Clocks
     ClockError(ClockError),
+    /// Something went wrong setting up the core voltage
+    VregError(VregError),
+    /// Something went wrong setting up the flash clock
+    SsiError(SsiError),
 }
 
 /// Initialize the clocks and plls according to the reference implementation
@@ -332,12 +581,131 @@ pub fn init_clocks_and_plls(
     // Configure watchdog tick generation to tick over every microsecond
     watchdog.enable_tick_generation((xosc_crystal_freq / 1_000_000) as u8);
 
-    let mut clocks = ClocksManager::new(clocks_dev);
+    let clocks = ClocksManager::new(clocks_dev);
+    init_plls_and_clocks(
+        xosc,
+        clocks,
+        pll_sys_dev,
+        PLL_SYS_125MHZ,
+        pll_usb_dev,
+        resets,
+    )
+}
+
+/// Configuration of [`init_clocks_and_plls_with_config`].
+pub struct ClocksConfig<'a> {
+    xosc_crystal_freq: HertzU32,
+    sys_freq: HertzU32,
+    vreg: Option<&'a mut Vreg>,
+    flash: Option<(&'a mut Ssi, HertzU32)>,
+}
+
+impl<'a> ClocksConfig<'a> {
+    /// Run clk_sys as close as possible to `sys_freq`, from a crystal of `xosc_crystal_freq`.
+    pub fn new(xosc_crystal_freq: HertzU32, sys_freq: HertzU32) -> Self {
+        Self {
+            xosc_crystal_freq,
+            sys_freq,
+            vreg: None,
+            flash: None,
+        }
+    }
+
+    /// Raise the core voltage as needed by the system clock, before raising the clock.
+    ///
+    /// The voltage is never lowered. See [`Voltage::for_frequency`].
+    pub fn vreg(mut self, vreg: &'a mut Vreg) -> Self {
+        self.vreg = Some(vreg);
+        self
+    }
+
+    /// Keep the flash clock at most `max_flash_freq`, adjusting the SSI divider before raising
+    /// the system clock.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of [`Ssi::set_clock_divider`] apply when the clocks are initialized.
+    pub unsafe fn flash(mut self, ssi: &'a mut Ssi, max_flash_freq: HertzU32) -> Self {
+        self.flash = Some((ssi, max_flash_freq));
+        self
+    }
+}
+
+/// Initialize the clocks and plls, running clk_sys at the frequency of `config`, e.g. to
+/// overclock the chip.
+///
+/// The system PLL configuration is searched with [`PLLConfig::from_freq`], and the other clocks
+/// are set up as by [`init_clocks_and_plls`]. The frequency actually achieved is the one of
+/// `clocks.system_clock`.
+///
+/// ```no_run
+/// use fugit::HertzU32;
+/// use rp2040_hal::{clocks::{init_clocks_and_plls_with_config, Clock, ClocksConfig}, pac, ssi::Ssi, vreg::Vreg, watchdog::Watchdog};
+/// let mut peripherals = pac::Peripherals::take().unwrap();
+/// let mut watchdog = Watchdog::new(peripherals.WATCHDOG);
+/// let mut vreg = Vreg::new(peripherals.VREG_AND_CHIP_RESET);
+/// let mut ssi = Ssi::new(peripherals.XIP_SSI);
+/// let config = unsafe { ClocksConfig::new(HertzU32::MHz(12), HertzU32::MHz(250)).vreg(&mut vreg).flash(&mut ssi, HertzU32::MHz(133)) };
+/// let clocks = init_clocks_and_plls_with_config(config, peripherals.XOSC, peripherals.CLOCKS, peripherals.PLL_SYS, peripherals.PLL_USB, &mut peripherals.RESETS, &mut watchdog).ok().unwrap();
+/// assert_eq!(clocks.system_clock.freq(), HertzU32::MHz(250));
+/// ```
+pub fn init_clocks_and_plls_with_config(
+    config: ClocksConfig<'_>,
+    xosc_dev: XOSC,
+    clocks_dev: CLOCKS,
+    pll_sys_dev: PLL_SYS,
+    pll_usb_dev: PLL_USB,
+    resets: &mut RESETS,
+    watchdog: &mut Watchdog,
+) -> Result<ClocksManager, InitError> {
+    let (pll_sys_config, sys_freq) =
+        PLLConfig::from_freq(config.xosc_crystal_freq, config.sys_freq)
+            .ok_or(InitError::PllError(PllError::BadArgument))?;
+
+    if let Some(vreg) = config.vreg {
+        let voltage = Voltage::for_frequency(sys_freq)
+            .ok_or(InitError::VregError(VregError::VoltageTooLow))?;
+        if vreg.voltage().map_or(true, |current| current < voltage) {
+            vreg.set_voltage(voltage, sys_freq)
+                .map_err(InitError::VregError)?;
+        }
+    }
+    if let Some((ssi, max_flash_freq)) = config.flash {
+        // Safety: guaranteed by the caller of `ClocksConfig::flash`. clk_sys is at most
+        // `sys_freq` until it is raised, so the flash clock stays below the maximum.
+        unsafe { ssi.set_max_frequency(sys_freq, max_flash_freq) }.map_err(InitError::SsiError)?;
+    }
+
+    let xosc =
+        setup_xosc_blocking(xosc_dev, config.xosc_crystal_freq).map_err(InitError::XoscErr)?;
+
+    // Configure watchdog tick generation to tick over every microsecond
+    watchdog.enable_tick_generation((config.xosc_crystal_freq.to_Hz() / 1_000_000) as u8);
 
+    let clocks = ClocksManager::new(clocks_dev);
+    init_plls_and_clocks(
+        xosc,
+        clocks,
+        pll_sys_dev,
+        pll_sys_config,
+        pll_usb_dev,
+        resets,
+    )
+}
+
+/// Set up the PLLs, then the clocks as in [`ClocksManager::init_default`].
+fn init_plls_and_clocks(
+    xosc: CrystalOscillator<Stable>,
+    mut clocks: ClocksManager,
+    pll_sys_dev: PLL_SYS,
+    pll_sys_config: PLLConfig,
+    pll_usb_dev: PLL_USB,
+    resets: &mut RESETS,
+) -> Result<ClocksManager, InitError> {
     let pll_sys = setup_pll_blocking(
         pll_sys_dev,
         xosc.operating_frequency(),
-        PLL_SYS_125MHZ,
+        pll_sys_config,
         &mut clocks,
         resets,
     )
@@ -356,70 +724,3 @@ pub fn init_clocks_and_plls(
         .map_err(InitError::ClockError)?;
     Ok(clocks)
 }
-
-// Calculates (numerator<<8)/denominator, avoiding 64bit division
-// Returns None if the result would not fit in 32 bit.
-fn fractional_div(numerator: u32, denominator: u32) -> Option<u32> {
-    if denominator.eq(&numerator) {
-        return Some(1 << 8);
-    }
-
-    let div_int = numerator / denominator;
-    if div_int >= 1 << 24 {
-        return None;
-    }
-
-    let div_rem = numerator - (div_int * denominator);
-
-    let div_frac = if div_rem < 1 << 24 {
-        // div_rem is small enough to shift it by 8 bits without overflow
-        (div_rem << 8) / denominator
-    } else {
-        // div_rem is too large. Shift denominator right, instead.
-        // As 1<<24 < div_rem < denominator, relative error caused by the
-        // lost lower 8 bits of denominator is smaller than 2^-16
-        (div_rem) / (denominator >> 8)
-    };
-
-    Some((div_int << 8) + div_frac)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_fractional_div() {
-        // easy values
-        assert_eq!(fractional_div(1, 1), Some(1 << 8));
-
-        // typical values
-        assert_eq!(fractional_div(125_000_000, 48_000_000), Some(666));
-        assert_eq!(fractional_div(48_000_000, 46875), Some(1024 << 8));
-
-        // resulting frequencies
-        assert_eq!(
-            fractional_div(
-                125_000_000,
-                fractional_div(125_000_000, 48_000_000).unwrap()
-            ),
-            Some(48_048_048)
-        );
-        assert_eq!(
-            fractional_div(48_000_000, fractional_div(48_000_000, 46875).unwrap()),
-            Some(46875)
-        );
-
-        // not allowed in src/clocks/mod.rs, but should still deliver correct results
-        assert_eq!(fractional_div(1, 2), Some(128));
-        assert_eq!(fractional_div(1, 256), Some(1));
-        assert_eq!(fractional_div(1, 257), Some(0));
-
-        // borderline cases
-        assert_eq!(fractional_div((1 << 24) - 1, 1), Some(((1 << 24) - 1) << 8));
-        assert_eq!(fractional_div(1 << 24, 1), None);
-        assert_eq!(fractional_div(1 << 24, 2), Some(1 << (23 + 8)));
-        assert_eq!(fractional_div(1 << 24, (1 << 24) + 1), Some(1 << 8));
-        assert_eq!(fractional_div(u32::MAX, u32::MAX), Some(1 << 8));
-    }
-}
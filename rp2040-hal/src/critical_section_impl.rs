@@ -1,3 +1,18 @@
+//! Multicore-aware `critical-section` implementation
+//!
+//! The single-core implementation provided by `cortex-m` only masks interrupts on the
+//! calling core, so a `critical_section::Mutex` shared between both cores is not
+//! actually protected. This implementation additionally takes hardware spinlock 31
+//! ([`Spinlock31`](crate::sio::Spinlock31)), so that only one core at a time can be
+//! inside a critical section.
+//!
+//! Critical sections may be nested. The outermost call takes the spinlock and records
+//! the owning core; nested calls on the same core only pass a token back to `release`
+//! telling it to leave the spinlock and the interrupt state untouched.
+//!
+//! As the spinlocks are not reset by a software reset, the [`entry`](crate::entry)
+//! macro releases all of them before `main` runs.
+
 use core::sync::atomic::{AtomicU8, Ordering};
 
 struct RpSpinlockCs;
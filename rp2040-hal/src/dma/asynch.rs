@@ -0,0 +1,60 @@
+//! Direct memory access (DMA) - Async Code
+//!
+//! The `wait_async` methods of the transfers wait for the DMA_IRQ_0 interrupt of their channels
+//! instead of polling. See [`async_utils`](crate::async_utils).
+
+use super::SingleChannel;
+use crate::{
+    async_utils::{wait_for, WakerSlot},
+    atomic_register_access::{write_bitmask_clear, write_bitmask_set},
+    pac::DMA,
+};
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY: WakerSlot = WakerSlot::new();
+static WAKERS: [WakerSlot; 12] = [EMPTY; 12];
+
+/// Wake the tasks waiting for DMA transfers.
+///
+/// Call this from the `DMA_IRQ_0` interrupt handler. It disables the DMA_IRQ_0 signal of the
+/// channels which completed and have a task waiting on them, and leaves the other channels to
+/// the rest of the handler.
+pub fn on_interrupt() {
+    // Safety: only the interrupt enables and the status of the channels with a waiting task are
+    // modified, through the atomic alias or the write-1-to-clear register.
+    let dma = unsafe { &*DMA::ptr() };
+    let status = dma.ints0.read().bits();
+    let waiting = (0..WAKERS.len())
+        .filter(|&id| status & (1 << id) != 0 && WAKERS[id].is_registered())
+        .fold(0, |mask, id| mask | 1 << id);
+    if waiting != 0 {
+        unsafe {
+            write_bitmask_clear(dma.inte0.as_ptr(), waiting);
+            dma.ints0.write(|w| w.bits(waiting));
+        }
+        for (id, waker) in WAKERS.iter().enumerate() {
+            if waiting & (1 << id) != 0 {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Wait until the channel is no longer busy.
+pub(super) async fn wait_until_done<CH: SingleChannel>(ch: &CH) {
+    let id = ch.id();
+    wait_for(
+        &WAKERS[usize::from(id)],
+        || {
+            ch.ch()
+                .ch_ctrl_trig
+                .read()
+                .busy()
+                .bit_is_clear()
+                .then_some(())
+        },
+        // Safety: We only use the atomic alias of the register.
+        || unsafe { write_bitmask_set((*DMA::ptr()).inte0.as_ptr(), 1 << id) },
+    )
+    .await
+}
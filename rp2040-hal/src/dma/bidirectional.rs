@@ -157,4 +157,15 @@ where
         // TODO: Use a tuple type?
         ((self.ch.0, self.ch.1), self.from, self.bidi, self.to)
     }
+
+    /// Wait until the transfer is complete without blocking, see [`wait`](Self::wait)
+    ///
+    /// This enables the DMA_IRQ_0 signal of the channels, so
+    /// [`on_interrupt`](super::on_interrupt) must be called from the `DMA_IRQ_0` handler.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> ((CH1, CH2), FROM, BIDI, TO) {
+        super::asynch::wait_until_done(&self.ch.0).await;
+        super::asynch::wait_until_done(&self.ch.1).await;
+        self.wait()
+    }
 }
@@ -135,6 +135,15 @@ where
             !self.ch.0.ch().ch_ctrl_trig.read().busy().bit_is_set()
         }
     }
+
+    #[cfg(feature = "async")]
+    async fn wait_until_done(&self) {
+        if self.second_ch {
+            super::asynch::wait_until_done(&self.ch.1).await
+        } else {
+            super::asynch::wait_until_done(&self.ch.0).await
+        }
+    }
 }
 
 impl<CH1, CH2, FROM, TO, WORD> Transfer<CH1, CH2, FROM, TO, ()>
@@ -155,6 +164,16 @@ where
         // TODO: Use a tuple type?
         (self.ch.0, self.ch.1, self.from, self.to)
     }
+
+    /// Wait until the transfer is complete without blocking, see [`wait`](Self::wait)
+    ///
+    /// This enables the DMA_IRQ_0 signal of the active channel, so
+    /// [`on_interrupt`](super::on_interrupt) must be called from the `DMA_IRQ_0` handler.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> (CH1, CH2, FROM, TO) {
+        self.wait_until_done().await;
+        self.wait()
+    }
 }
 
 impl<CH1, CH2, FROM, TO, WORD> Transfer<CH1, CH2, FROM, TO, ()>
@@ -281,6 +300,16 @@ where
             },
         )
     }
+
+    /// Wait until the transfer is complete without blocking, see [`wait`](Self::wait)
+    ///
+    /// This enables the DMA_IRQ_0 signal of the active channel, so
+    /// [`on_interrupt`](super::on_interrupt) must be called from the `DMA_IRQ_0` handler.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> (FROM, Transfer<CH1, CH2, NEXT, TO, ()>) {
+        self.wait_until_done().await;
+        self.wait()
+    }
 }
 
 impl<CH1, CH2, FROM, TO, NEXT, WORD> Transfer<CH1, CH2, FROM, TO, WriteNext<NEXT>>
@@ -313,4 +342,14 @@ where
             },
         )
     }
+
+    /// Wait until the transfer is complete without blocking, see [`wait`](Self::wait)
+    ///
+    /// This enables the DMA_IRQ_0 signal of the active channel, so
+    /// [`on_interrupt`](super::on_interrupt) must be called from the `DMA_IRQ_0` handler.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> (TO, Transfer<CH1, CH2, FROM, NEXT, ()>) {
+        self.wait_until_done().await;
+        self.wait()
+    }
 }
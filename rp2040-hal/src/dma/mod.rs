@@ -23,7 +23,6 @@
 //! alternatingly.
 
 use core::marker::PhantomData;
-use embedded_dma::{ReadBuffer, WriteBuffer};
 
 use crate::{
     pac::{self, DMA},
@@ -31,9 +30,14 @@ use crate::{
     typelevel::Sealed,
 };
 // Export these types for easier use by external code
+#[cfg(feature = "async")]
+pub use crate::dma::asynch::on_interrupt;
 pub use crate::dma::single_channel::SingleChannel;
+pub use rp_hal_common::dma::{EndlessReadTarget, EndlessWriteTarget, ReadTarget, WriteTarget};
 
 // Bring in our submodules
+#[cfg(feature = "async")]
+mod asynch;
 pub mod bidirectional;
 pub mod double_buffer;
 pub mod single_buffer;
@@ -151,122 +155,6 @@ impl<CH: ChannelIndex> ChannelRegs for Channel<CH> {
     }
 }
 
-/// Trait which is implemented by anything that can be read via DMA.
-///
-/// # Safety
-///
-/// The implementing type must be safe to use for DMA reads. This means:
-///
-/// - The range returned by rx_address_count must point to a valid address,
-///   and if rx_increment is true, count must fit into the allocated buffer.
-/// - As long as no `&mut self` method is called on the implementing object:
-///   - `rx_address_count` must always return the same value, if called multiple
-///     times.
-///   - The memory specified by the pointer and size returned by `rx_address_count`
-///     must not be freed during the transfer it is used in as long as `self` is not dropped.
-pub unsafe trait ReadTarget {
-    /// Type which is transferred in a single DMA transfer.
-    type ReceivedWord;
-
-    /// Returns the DREQ number for this data source (`None` for memory buffers).
-    fn rx_treq() -> Option<u8>;
-
-    /// Returns the address and the maximum number of words that can be transferred from this data
-    /// source in a single DMA operation.
-    ///
-    /// For peripherals, the count should likely be u32::MAX. If a data source implements
-    /// EndlessReadTarget, it is suitable for infinite transfers from or to ring buffers. Note that
-    /// ring buffers designated for endless transfers, but with a finite buffer size, should return
-    /// the size of their individual buffers here.
-    ///
-    /// # Safety
-    ///
-    /// This function has the same safety guarantees as `ReadBuffer::read_buffer`.
-    fn rx_address_count(&self) -> (u32, u32);
-
-    /// Returns whether the address shall be incremented after each transfer.
-    fn rx_increment(&self) -> bool;
-}
-
-/// Marker which signals that `rx_address_count()` can be called multiple times.
-///
-/// The DMA code will never call `rx_address_count()` to request more than two buffers to configure
-/// two DMA channels. In the case of peripherals, the function can always return the same values.
-pub trait EndlessReadTarget: ReadTarget {}
-
-/// Safety: ReadBuffer and ReadTarget have the same safety requirements.
-unsafe impl<B: ReadBuffer> ReadTarget for B {
-    type ReceivedWord = <B as ReadBuffer>::Word;
-
-    fn rx_treq() -> Option<u8> {
-        None
-    }
-
-    fn rx_address_count(&self) -> (u32, u32) {
-        let (ptr, len) = unsafe { self.read_buffer() };
-        (ptr as u32, len as u32)
-    }
-
-    fn rx_increment(&self) -> bool {
-        true
-    }
-}
-
-/// Trait which is implemented by anything that can be written via DMA.
-///
-/// # Safety
-///
-/// The implementing type must be safe to use for DMA writes. This means:
-///
-/// - The range returned by tx_address_count must point to a valid address,
-///   and if tx_increment is true, count must fit into the allocated buffer.
-/// - As long as no other `&mut self` method is called on the implementing object:
-///   - `tx_address_count` must always return the same value, if called multiple
-///     times.
-///   - The memory specified by the pointer and size returned by `tx_address_count`
-///     must not be freed during the transfer it is used in as long as `self` is not dropped.
-pub unsafe trait WriteTarget {
-    /// Type which is transferred in a single DMA transfer.
-    type TransmittedWord;
-
-    /// Returns the DREQ number for this data sink (`None` for memory buffers).
-    fn tx_treq() -> Option<u8>;
-
-    /// Returns the address and the maximum number of words that can be transferred from this data
-    /// source in a single DMA operation.
-    ///
-    /// See `ReadTarget::rx_address_count` for a complete description of the semantics of this
-    /// function.
-    fn tx_address_count(&mut self) -> (u32, u32);
-
-    /// Returns whether the address shall be incremented after each transfer.
-    fn tx_increment(&self) -> bool;
-}
-
-/// Marker which signals that `tx_address_count()` can be called multiple times.
-///
-/// The DMA code will never call `tx_address_count()` to request more than two buffers to configure
-/// two DMA channels. In the case of peripherals, the function can always return the same values.
-pub trait EndlessWriteTarget: WriteTarget {}
-
-/// Safety: WriteBuffer and WriteTarget have the same safety requirements.
-unsafe impl<B: WriteBuffer> WriteTarget for B {
-    type TransmittedWord = <B as WriteBuffer>::Word;
-
-    fn tx_treq() -> Option<u8> {
-        None
-    }
-
-    fn tx_address_count(&mut self) -> (u32, u32) {
-        let (ptr, len) = unsafe { self.write_buffer() };
-        (ptr as u32, len as u32)
-    }
-
-    fn tx_increment(&self) -> bool {
-        true
-    }
-}
-
 /// Pacing for DMA transfers.
 ///
 /// Generally, while memory-to-memory DMA transfers can operate at maximum possible throughput,
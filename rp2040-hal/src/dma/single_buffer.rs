@@ -112,4 +112,14 @@ where
 
         (self.ch, self.from, self.to)
     }
+
+    /// Wait until the transfer is complete without blocking, see [`wait`](Self::wait)
+    ///
+    /// This enables the DMA_IRQ_0 signal of the channel, so
+    /// [`on_interrupt`](super::on_interrupt) must be called from the `DMA_IRQ_0` handler.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> (CH, FROM, TO) {
+        super::asynch::wait_until_done(&self.ch).await;
+        self.wait()
+    }
 }
@@ -0,0 +1,372 @@
+//! Entropy pool combining the hardware noise sources
+//!
+//! The RP2040 has no dedicated random number generator. [`EntropyPool`] gathers the noise
+//! available on the chip:
+//!
+//! - the random bit of the [ring oscillator](crate::rosc), sampled from the jitter of the
+//!   ROSC against the system clock,
+//! - the least significant bits of ADC conversions,
+//! - the low bits of the timer, depending on when the program reaches the sampling points.
+//!
+//! The samples are mixed into a ChaCha20 state, from which the output is generated. The raw
+//! ring oscillator samples go through the repetition count and adaptive proportion health
+//! tests of NIST SP 800-90B, which catch a stuck or heavily biased oscillator, e.g. because it
+//! was stopped or synchronized to the system clock.
+//!
+//! The noise sources are not characterized, so no claim is made about the amount of entropy
+//! they provide. Gathering more samples than needed, e.g. with several calls to
+//! [`EntropyPool::add_rosc`], is cheap insurance.
+//!
+//! With the **getrandom** feature, a global pool seeded by [`seed`] backs the `getrandom`
+//! crate through its custom backend, so that crates depending on it work on the RP2040.
+//!
+//! ```no_run
+//! use rp2040_hal::{entropy, pac, rosc::RingOscillator, Adc, Timer};
+//! # let mut peripherals = pac::Peripherals::take().unwrap();
+//! # let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+//! let rosc = RingOscillator::new(peripherals.ROSC).initialize();
+//! let mut adc = Adc::new(peripherals.ADC, &mut peripherals.RESETS);
+//! let timer = Timer::new(peripherals.TIMER, &mut peripherals.RESETS, &clocks);
+//! entropy::seed(&rosc, Some(&mut adc), &timer).unwrap();
+//! ```
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::{
+    pac,
+    rosc::{Enabled, RingOscillator},
+    Adc, Timer,
+};
+
+/// Entropy pool error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// No noise was added to the pool yet.
+    NotSeeded,
+    /// The ring oscillator samples failed a health test.
+    HealthTestFailed,
+    /// The ring oscillator is not running.
+    RoscStopped,
+}
+
+/// "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// Ring oscillator bytes gathered by [`EntropyPool::add_rosc`].
+const ROSC_SAMPLES: usize = 64;
+
+/// Cutoff of the repetition count test: a stuck oscillator repeats the same byte.
+///
+/// With at least 1 bit of entropy per byte, the false positive rate is below 2^-20.
+const REPETITION_CUTOFF: usize = 21;
+
+/// Cutoff of the adaptive proportion test, over windows of [`ROSC_SAMPLES`] bytes.
+///
+/// With at least 1 bit of entropy per byte, the first byte of a window shows up that many times
+/// with a probability below 2^-20.
+const PROPORTION_CUTOFF: usize = 51;
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// The ChaCha20 block function.
+fn chacha20_block(input: &[u32; 16]) -> [u32; 16] {
+    let mut s = *input;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+    for (word, input) in s.iter_mut().zip(input) {
+        *word = word.wrapping_add(*input);
+    }
+    s
+}
+
+/// Run the health tests on raw samples.
+fn health_test(samples: &[u8]) -> Result<(), Error> {
+    let mut run = 1;
+    for pair in samples.windows(2) {
+        run = if pair[0] == pair[1] { run + 1 } else { 1 };
+        if run >= REPETITION_CUTOFF {
+            return Err(Error::HealthTestFailed);
+        }
+    }
+    if let Some(first) = samples.first() {
+        if samples.iter().filter(|sample| *sample == first).count() >= PROPORTION_CUTOFF {
+            return Err(Error::HealthTestFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Read a byte of ring oscillator random bits, if the ring oscillator is running.
+fn rosc_byte() -> Option<u8> {
+    // Safety: only reads the status and random bit registers.
+    let rosc = unsafe { &*pac::ROSC::ptr() };
+    if rosc.status.read().enabled().bit_is_clear() {
+        return None;
+    }
+    Some((0..8).fold(0, |byte, _| {
+        (byte << 1) | rosc.randombit.read().randombit().bit() as u8
+    }))
+}
+
+/// Read the low word of the timer.
+fn timer_low() -> u32 {
+    // Safety: only reads the raw counter, which has no side effect.
+    unsafe { (*pac::TIMER::ptr()).timerawl.read().bits() }
+}
+
+/// A pool of hardware noise, generating random bytes.
+pub struct EntropyPool {
+    state: [u32; 16],
+    seeded: bool,
+}
+
+impl EntropyPool {
+    /// An empty pool.
+    pub const fn new() -> Self {
+        let mut state = [0; 16];
+        let mut i = 0;
+        while i < 4 {
+            state[i] = CHACHA_CONSTANTS[i];
+            i += 1;
+        }
+        EntropyPool {
+            state,
+            seeded: false,
+        }
+    }
+
+    /// Whether noise was added to the pool.
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    /// Mix words into the key of the ChaCha20 state.
+    fn absorb(&mut self, words: &[u32]) {
+        for chunk in words.chunks(8) {
+            for (key, word) in self.state[4..12].iter_mut().zip(chunk) {
+                *key ^= word;
+            }
+            self.rekey();
+        }
+    }
+
+    /// Replace the key with the next block, so that earlier output cannot be recovered.
+    fn rekey(&mut self) {
+        let block = chacha20_block(&self.state);
+        self.state[4..12].copy_from_slice(&block[..8]);
+        self.next_block_counter();
+    }
+
+    fn next_block_counter(&mut self) {
+        let (low, carry) = self.state[12].overflowing_add(1);
+        self.state[12] = low;
+        self.state[13] = self.state[13].wrapping_add(carry as u32);
+    }
+
+    /// Gather ring oscillator random bits, after checking them with the health tests.
+    ///
+    /// Nothing is added to the pool if the health tests fail.
+    pub fn add_rosc(&mut self, _rosc: &RingOscillator<Enabled>) -> Result<(), Error> {
+        let mut samples = [0u8; ROSC_SAMPLES];
+        for sample in samples.iter_mut() {
+            *sample = rosc_byte().ok_or(Error::RoscStopped)?;
+        }
+        health_test(&samples)?;
+        let mut words = [0u32; ROSC_SAMPLES / 4];
+        for (word, bytes) in words.iter_mut().zip(samples.chunks(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) ^ timer_low();
+        }
+        self.absorb(&words);
+        self.seeded = true;
+        Ok(())
+    }
+
+    /// Gather the noise of 64 ADC conversions of the temperature sensor channel.
+    ///
+    /// The least significant bits of the conversions are mostly noise. This does not count as
+    /// seeding the pool, as the ADC noise is not health tested.
+    pub fn add_adc(&mut self, adc: &mut Adc) {
+        let mut words = [0u32; 8];
+        for (i, word) in words.iter_mut().enumerate() {
+            for _ in 0..8 {
+                *word = (*word << 4) | u32::from(adc.inner_read(4) & 0xf);
+            }
+            *word ^= timer_low().rotate_left(i as u32);
+        }
+        self.absorb(&words);
+    }
+
+    /// Gather the low bits of the timer.
+    ///
+    /// This only depends on when it is called, so it mostly helps telling apart devices and
+    /// boots which are otherwise identical. This does not count as seeding the pool.
+    pub fn add_timer(&mut self, timer: &Timer) {
+        let counter = timer.get_counter().ticks();
+        self.absorb(&[counter as u32, (counter >> 32) as u32]);
+    }
+
+    /// Fill `dest` with random bytes.
+    ///
+    /// If the ring oscillator is running, fresh samples are mixed in first.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        if !self.seeded {
+            return Err(Error::NotSeeded);
+        }
+        let mut fresh = [timer_low(), 0];
+        if let Some(byte) = rosc_byte() {
+            fresh[1] = u32::from_le_bytes([byte, rosc_byte().unwrap_or(0), 0, 0]);
+        }
+        self.absorb(&fresh);
+        for chunk in dest.chunks_mut(64) {
+            let block = chacha20_block(&self.state);
+            self.next_block_counter();
+            for (bytes, word) in chunk.chunks_mut(4).zip(block.iter()) {
+                bytes.copy_from_slice(&word.to_le_bytes()[..bytes.len()]);
+            }
+        }
+        self.rekey();
+        Ok(())
+    }
+}
+
+impl Default for EntropyPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl rand_core::RngCore for EntropyPool {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the pool is not seeded. Use `try_fill_bytes` to handle it.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        EntropyPool::fill_bytes(self, dest).expect("entropy pool not seeded")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        EntropyPool::fill_bytes(self, dest).map_err(|_| {
+            // The code is non-zero.
+            rand_core::Error::from(
+                core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START).unwrap(),
+            )
+        })
+    }
+}
+
+static POOL: Mutex<RefCell<EntropyPool>> = Mutex::new(RefCell::new(EntropyPool::new()));
+
+/// Seed the global pool, used by [`fill_bytes`] and the `getrandom` backend.
+///
+/// The ring oscillator samples must pass the health tests. The ADC and timer are mixed in as
+/// well, if given.
+pub fn seed(
+    rosc: &RingOscillator<Enabled>,
+    adc: Option<&mut Adc>,
+    timer: &Timer,
+) -> Result<(), Error> {
+    critical_section::with(|cs| {
+        let mut pool = POOL.borrow_ref_mut(cs);
+        pool.add_timer(timer);
+        if let Some(adc) = adc {
+            pool.add_adc(adc);
+        }
+        pool.add_rosc(rosc)
+    })
+}
+
+/// Fill `dest` with random bytes from the global pool.
+///
+/// Fails with [`Error::NotSeeded`] until [`seed`] succeeded.
+pub fn fill_bytes(dest: &mut [u8]) -> Result<(), Error> {
+    critical_section::with(|cs| POOL.borrow_ref_mut(cs).fill_bytes(dest))
+}
+
+#[cfg(feature = "getrandom")]
+mod getrandom_backend {
+    use core::num::NonZeroU32;
+
+    use super::Error;
+
+    fn getrandom(dest: &mut [u8]) -> Result<(), getrandom::Error> {
+        super::fill_bytes(dest).map_err(|error| {
+            let code = getrandom::Error::CUSTOM_START
+                + match error {
+                    Error::NotSeeded => 0,
+                    Error::HealthTestFailed => 1,
+                    Error::RoscStopped => 2,
+                };
+            // The code is non-zero.
+            getrandom::Error::from(NonZeroU32::new(code).unwrap())
+        })
+    }
+
+    getrandom::register_custom_getrandom!(getrandom);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chacha20_block_test_vector() {
+        // RFC 8439, section 2.3.2.
+        let mut input = [0u32; 16];
+        input[..4].copy_from_slice(&CHACHA_CONSTANTS);
+        for (i, word) in input[4..12].iter_mut().enumerate() {
+            let b = 4 * i as u32;
+            *word = u32::from_le_bytes([b as u8, b as u8 + 1, b as u8 + 2, b as u8 + 3]);
+        }
+        input[12..].copy_from_slice(&[0x0000_0001, 0x0900_0000, 0x4a00_0000, 0x0000_0000]);
+        let output = chacha20_block(&input);
+        assert_eq!(output[0], 0xe4e7_f110);
+        assert_eq!(output[1], 0x1559_3bd1);
+        assert_eq!(output[15], 0x4e3c_50a2);
+    }
+
+    #[test]
+    fn health_tests() {
+        let mut samples = [0u8; ROSC_SAMPLES];
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = (i as u8).wrapping_mul(151);
+        }
+        assert_eq!(health_test(&samples), Ok(()));
+
+        // Stuck
+        samples[10..10 + REPETITION_CUTOFF].fill(0xaa);
+        assert_eq!(health_test(&samples), Err(Error::HealthTestFailed));
+
+        // Biased, without long runs
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = if i % 5 == 4 { i as u8 } else { 0 };
+        }
+        assert_eq!(health_test(&samples), Err(Error::HealthTestFailed));
+    }
+}
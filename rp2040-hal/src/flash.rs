@@ -0,0 +1,611 @@
+//! In-application flash programming
+//!
+//! Erase and program the external QSPI flash the RP2040 executes from, using the
+//! flash routines of the bootrom.
+//!
+//! While the flash is being erased or programmed, it cannot be accessed through the
+//! XIP window, so no code can be executed from flash. The functions in this module
+//! take care of this for the calling core: they disable interrupts and run the whole
+//! sequence from RAM, restoring XIP mode (by re-running the second stage bootloader)
+//! before returning. It is up to the caller to make sure nothing else touches the flash
+//! meanwhile, in particular:
+//!
+//! * the other core must either be stopped, paused with
+//!   [`Core::pause`](crate::multicore::Core::pause), or run entirely from RAM
+//!   (see [`Core::spawn_in_ram`](crate::multicore::Core::spawn_in_ram)),
+//! * no DMA channel may read from or write to the XIP address range.
+//!
+//! ```no_run
+//! use rp2040_hal::flash;
+//!
+//! // The last 4 kB sector of a 2 MB flash.
+//! const OFFSET: u32 = 2 * 1024 * 1024 - flash::SECTOR_SIZE;
+//!
+//! let data = [0x42; flash::PAGE_SIZE as usize];
+//! unsafe {
+//!     flash::erase(OFFSET, flash::SECTOR_SIZE).unwrap();
+//!     flash::program(OFFSET, &data).unwrap();
+//! }
+//! ```
+
+use embedded_storage::nor_flash::{
+    self, ErrorType, MultiwriteNorFlash, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+};
+
+use crate::rom_data;
+
+/// Start of the flash in the address space, through the cached XIP window.
+pub const FLASH_BASE: u32 = 0x1000_0000;
+
+/// Size of the largest flash the XIP window can address.
+pub const MAX_FLASH_SIZE: u32 = 16 * 1024 * 1024;
+
+/// End of the XIP windows: the cached one at [`FLASH_BASE`] is followed by the no-allocate,
+/// uncached and uncached no-allocate aliases of the flash.
+const XIP_END: u32 = 0x1400_0000;
+
+/// Size of a flash sector, the smallest unit which can be erased.
+pub const SECTOR_SIZE: u32 = 4096;
+
+/// Size of a flash page, the unit in which the flash is programmed.
+pub const PAGE_SIZE: u32 = 256;
+
+/// Size of the block erased by [`BLOCK_ERASE_CMD`].
+const BLOCK_SIZE: u32 = 65536;
+
+/// 64 kB block erase command, used to speed up erasing large ranges.
+const BLOCK_ERASE_CMD: u8 = 0xd8;
+
+/// Size of the second stage bootloader at the start of the flash.
+const BOOT2_SIZE_WORDS: usize = 64;
+
+/// Read unique ID command, followed by 4 dummy bytes and 8 bytes of ID.
+const UNIQUE_ID_CMD: u8 = 0x4b;
+
+/// Read JEDEC ID command, followed by 3 bytes of ID.
+const JEDEC_ID_CMD: u8 = 0x9f;
+
+/// Read SFDP command, followed by 3 address bytes, 1 dummy byte and the data.
+const READ_SFDP_CMD: u8 = 0x5a;
+
+/// Base address of the SSI, which drives the QSPI bus.
+const SSI_BASE: usize = 0x1800_0000;
+
+/// Address of the `GPIO_QSPI_SS_CTRL` register, used to drive the chip select manually.
+const QSPI_SS_CTRL: usize = 0x4001_800c;
+
+/// Address of the SIO `GPIO_HI_IN` register, the input levels of the QSPI pins.
+#[cfg_attr(not(target_arch = "arm"), allow(dead_code))]
+const SIO_GPIO_HI_IN: usize = 0xd000_0008;
+
+/// Bit of the chip select in `GPIO_HI_IN`.
+const QSPI_SS_BIT: u32 = 1 << 1;
+
+/// Errors for flash operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The offset or length is not a multiple of the sector or page size.
+    Unaligned,
+    /// The range extends past the end of the flash.
+    OutOfBounds,
+    /// The data to program is itself located in flash.
+    DataInFlash,
+}
+
+impl NorFlashError for Error {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::Unaligned => NorFlashErrorKind::NotAligned,
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Error::DataInFlash => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl From<NorFlashErrorKind> for Error {
+    fn from(kind: NorFlashErrorKind) -> Self {
+        match kind {
+            NorFlashErrorKind::NotAligned => Error::Unaligned,
+            _ => Error::OutOfBounds,
+        }
+    }
+}
+
+/// Check that `offset..offset + len` is aligned to `align` and fits in the flash.
+fn check_range(offset: u32, len: u32, align: u32) -> Result<(), Error> {
+    if offset % align != 0 || len % align != 0 {
+        return Err(Error::Unaligned);
+    }
+    match offset.checked_add(len) {
+        Some(end) if end <= MAX_FLASH_SIZE => Ok(()),
+        _ => Err(Error::OutOfBounds),
+    }
+}
+
+/// Erase `len` bytes of flash starting at `offset` from the start of the flash.
+///
+/// Both `offset` and `len` must be multiples of [`SECTOR_SIZE`].
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self). The caller must also make sure it does not erase the running
+/// program or data still in use.
+pub unsafe fn erase(offset: u32, len: u32) -> Result<(), Error> {
+    check_range(offset, len, SECTOR_SIZE)?;
+    write_flash(offset, core::ptr::null(), len as usize, true);
+    Ok(())
+}
+
+/// Program `data` to the flash starting at `offset` from the start of the flash.
+///
+/// Both `offset` and `data.len()` must be multiples of [`PAGE_SIZE`], and the range must
+/// have been erased beforehand. `data` must not be located in flash itself, through any of the
+/// XIP windows.
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self). The caller must also make sure it does not overwrite the running
+/// program or data still in use.
+pub unsafe fn program(offset: u32, data: &[u8]) -> Result<(), Error> {
+    let len = u32::try_from(data.len()).map_err(|_| Error::OutOfBounds)?;
+    check_range(offset, len, PAGE_SIZE)?;
+    let start = data.as_ptr() as u32;
+    if start < XIP_END && start.saturating_add(len) > FLASH_BASE {
+        return Err(Error::DataInFlash);
+    }
+    write_flash(offset, data.as_ptr(), data.len(), false);
+    Ok(())
+}
+
+/// Read the 64-bit unique ID of the flash chip.
+///
+/// Most flash chips are programmed with a unique ID at manufacture, which makes it usable as a
+/// board serial number.
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self).
+///
+/// ```no_run
+/// let id = unsafe { rp2040_hal::flash::unique_id() };
+/// ```
+pub unsafe fn unique_id() -> u64 {
+    let mut buf = [0u8; 13];
+    buf[0] = UNIQUE_ID_CMD;
+    command(&mut buf);
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&buf[5..]);
+    u64::from_be_bytes(id)
+}
+
+/// Manufacturer and device identification of the flash chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JedecId {
+    /// JEDEC manufacturer ID, for example `0xef` for Winbond.
+    pub manufacturer: u8,
+    /// Manufacturer specific memory type.
+    pub memory_type: u8,
+    /// Capacity, usually encoded as the base 2 logarithm of the size in bytes.
+    pub capacity: u8,
+}
+
+impl JedecId {
+    /// Size of the flash in bytes, if [`capacity`](Self::capacity) is a plausible base 2
+    /// logarithm of it.
+    ///
+    /// The encoding is not standardised, so this should only be used as a fallback when
+    /// [`size`] cannot read the SFDP tables.
+    pub fn capacity_bytes(&self) -> Option<u32> {
+        match self.capacity {
+            0x10..=0x1f => Some(1 << self.capacity),
+            _ => None,
+        }
+    }
+}
+
+/// Read the JEDEC ID of the flash chip.
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self).
+pub unsafe fn jedec_id() -> JedecId {
+    let mut buf = [0u8; 4];
+    buf[0] = JEDEC_ID_CMD;
+    command(&mut buf);
+    JedecId {
+        manufacturer: buf[1],
+        memory_type: buf[2],
+        capacity: buf[3],
+    }
+}
+
+/// Detect the size of the flash chip in bytes.
+///
+/// The size is read from the Serial Flash Discoverable Parameters (SFDP) if the chip supports
+/// them, and otherwise guessed from the capacity byte of the [JEDEC ID](jedec_id). Returns
+/// `None` if neither gives a usable value.
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self).
+///
+/// ```no_run
+/// let size = unsafe { rp2040_hal::flash::size() }.unwrap_or(2 * 1024 * 1024);
+/// ```
+pub unsafe fn size() -> Option<u32> {
+    sfdp_size().or_else(|| jedec_id().capacity_bytes())
+}
+
+/// Read the BOOTSEL button, which pulls the chip select of the flash low when pressed.
+///
+/// The chip select is released for a few microseconds to read it, during which the flash
+/// cannot be accessed. This works while running from flash, unlike reading the pin through
+/// [`gpio::qspi::Pins`](crate::gpio::qspi::Pins).
+///
+/// # Safety
+///
+/// Nothing else may access the flash while this runs, see the [module level
+/// documentation](self).
+///
+/// ```no_run
+/// if unsafe { rp2040_hal::flash::bootsel_pressed() } {
+///     rp2040_hal::rom_data::reset_to_usb_boot(0, 0);
+/// }
+/// ```
+pub unsafe fn bootsel_pressed() -> bool {
+    #[cfg(target_arch = "arm")]
+    debug_assert!(crate::is_in_ram(
+        read_bootsel_in_ram as unsafe fn() -> u32 as usize
+    ));
+    let gpio_hi_in = cortex_m::interrupt::free(|_| read_bootsel_in_ram());
+    gpio_hi_in & QSPI_SS_BIT == 0
+}
+
+/// Read the `GPIO_HI_IN` register with the output of the chip select disabled.
+///
+/// This must not access the flash in any way until the chip select is restored, see
+/// [`run_in_ram`] for why it is written in assembly.
+#[crate::ram_function]
+unsafe fn read_bootsel_in_ram() -> u32 {
+    #[allow(unused_mut)]
+    let mut gpio_hi_in = u32::MAX;
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "ldr r3, [r0]", // Save GPIO_QSPI_SS_CTRL
+        "movs r4, #3",
+        "lsls r4, r4, #12",
+        "mvns r4, r4",
+        "ands r4, r3",
+        "movs r5, #2",
+        "lsls r5, r5, #12",
+        "orrs r4, r5",
+        "str r4, [r0]", // OEOVER = disable output
+        // Let the pull-up charge the line for about 3000 cycles.
+        "1:",
+        "subs r2, #1",
+        "bne 1b",
+        "ldr r4, [r1]", // GPIO_HI_IN
+        "str r3, [r0]", // Restore GPIO_QSPI_SS_CTRL
+        in("r0") QSPI_SS_CTRL,
+        in("r1") SIO_GPIO_HI_IN,
+        inout("r2") 1000u32 => _,
+        out("r3") _,
+        out("r4") gpio_hi_in,
+        out("r5") _,
+    );
+    gpio_hi_in
+}
+
+/// Read `N` bytes of the SFDP tables, starting at `address`.
+unsafe fn read_sfdp<const N: usize>(address: u32) -> [u8; N] {
+    // Command, 3 address bytes and a dummy byte, followed by the data.
+    let mut buf = [0u8; 32];
+    buf[0] = READ_SFDP_CMD;
+    buf[1..4].copy_from_slice(&address.to_be_bytes()[1..]);
+    command(&mut buf[..5 + N]);
+    let mut data = [0u8; N];
+    data.copy_from_slice(&buf[5..5 + N]);
+    data
+}
+
+/// Read the flash density from the SFDP basic flash parameter table.
+unsafe fn sfdp_size() -> Option<u32> {
+    let header: [u8; 16] = read_sfdp(0);
+    if &header[..4] != b"SFDP" {
+        return None;
+    }
+    // The first parameter header always describes the basic flash parameter table.
+    let table = u32::from_le_bytes([header[12], header[13], header[14], 0]);
+    let params: [u8; 8] = read_sfdp(table);
+    let density = u32::from_le_bytes([params[4], params[5], params[6], params[7]]);
+    let bits = if density & 1 << 31 == 0 {
+        density.checked_add(1)?
+    } else {
+        1u32.checked_shl(density & !(1 << 31))?
+    };
+    Some(bits / 8)
+}
+
+/// A region of the flash, accessible through the `embedded-storage` NOR flash traits.
+///
+/// Offsets passed to the trait methods are relative to the start of the region. Writes may
+/// have any alignment and length: they are padded to whole pages with `0xff`, which leaves
+/// the other bytes of the page untouched. This also means the same location can be written
+/// to multiple times between erases, each write clearing more bits.
+///
+/// ```no_run
+/// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+/// use rp2040_hal::flash::{FlashRegion, SECTOR_SIZE};
+///
+/// // Use the last 64 kB of a 2 MB flash for storage.
+/// let mut storage = unsafe { FlashRegion::new(2 * 1024 * 1024 - 16 * SECTOR_SIZE, 16 * SECTOR_SIZE) }
+///     .unwrap();
+/// storage.erase(0, SECTOR_SIZE).unwrap();
+/// storage.write(0, b"hello").unwrap();
+/// let mut buf = [0; 5];
+/// storage.read(0, &mut buf).unwrap();
+/// ```
+pub struct FlashRegion {
+    start: u32,
+    len: u32,
+}
+
+impl FlashRegion {
+    /// Create a region of `len` bytes, starting at `start` from the start of the flash.
+    ///
+    /// Both `start` and `len` must be multiples of [`SECTOR_SIZE`].
+    ///
+    /// # Safety
+    ///
+    /// The region must not overlap with the program or any other data in use, and the
+    /// requirements of [`erase`] and [`program`] apply to every write or erase through it.
+    pub unsafe fn new(start: u32, len: u32) -> Result<Self, Error> {
+        check_range(start, len, SECTOR_SIZE)?;
+        Ok(Self { start, len })
+    }
+
+    /// The offset of the start of this region from the start of the flash.
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+}
+
+impl ErrorType for FlashRegion {
+    type Error = Error;
+}
+
+impl ReadNorFlash for FlashRegion {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        nor_flash::check_read(self, offset, bytes.len())?;
+        let src = (FLASH_BASE + self.start + offset) as *const u8;
+        // Safety: the range was checked to be within this region.
+        unsafe { core::ptr::copy_nonoverlapping(src, bytes.as_mut_ptr(), bytes.len()) };
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.len as usize
+    }
+}
+
+impl NorFlash for FlashRegion {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE as usize;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        nor_flash::check_erase(self, from, to)?;
+        // Safety: the caller of `FlashRegion::new` upholds the requirements.
+        unsafe { erase(self.start + from, to - from) }
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        nor_flash::check_write(self, offset, bytes.len())?;
+        let mut address = self.start + offset;
+        let mut bytes = bytes;
+        let mut page = [0xff_u8; PAGE_SIZE as usize];
+        while !bytes.is_empty() {
+            let page_start = address - address % PAGE_SIZE;
+            let in_page = (address - page_start) as usize;
+            let count = bytes.len().min(PAGE_SIZE as usize - in_page);
+            page.fill(0xff);
+            page[in_page..in_page + count].copy_from_slice(&bytes[..count]);
+            // Safety: the caller of `FlashRegion::new` upholds the requirements.
+            unsafe { program(page_start, &page)? };
+            address += count as u32;
+            bytes = &bytes[count..];
+        }
+        Ok(())
+    }
+}
+
+impl MultiwriteNorFlash for FlashRegion {}
+
+/// Everything needed while the flash is not accessible.
+///
+/// The ROM functions are looked up before leaving XIP mode, as the lookup code lives in flash.
+#[repr(C)]
+struct FlashSequence {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    /// Either `flash_range_erase` or `flash_range_program`.
+    operation: usize,
+    /// Arguments passed to `operation`.
+    args: [usize; 4],
+    flash_flush_cache: unsafe extern "C" fn(),
+    /// Address of a copy of the second stage bootloader in RAM, with the thumb bit set.
+    enter_xip: usize,
+}
+
+/// Prepare and run a flash operation with interrupts disabled.
+unsafe fn write_flash(offset: u32, data: *const u8, len: usize, erase: bool) {
+    // Copy the second stage bootloader to RAM, so it can be used to return to the fast XIP
+    // mode it configured at boot.
+    let mut boot2 = [0u32; BOOT2_SIZE_WORDS];
+    core::ptr::copy_nonoverlapping(
+        FLASH_BASE as *const u32,
+        boot2.as_mut_ptr(),
+        BOOT2_SIZE_WORDS,
+    );
+
+    let (operation, args) = if erase {
+        (
+            rom_data::flash_range_erase::ptr() as usize,
+            [
+                offset as usize,
+                len,
+                BLOCK_SIZE as usize,
+                BLOCK_ERASE_CMD as usize,
+            ],
+        )
+    } else {
+        (
+            rom_data::flash_range_program::ptr() as usize,
+            [offset as usize, data as usize, len, 0],
+        )
+    };
+    let sequence = FlashSequence {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        operation,
+        args,
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        // The bootloader returns to its caller when not called from the bootrom.
+        enter_xip: boot2.as_ptr() as usize | 1,
+    };
+
+    #[cfg(target_arch = "arm")]
+    debug_assert!(crate::is_in_ram(
+        run_in_ram as unsafe fn(&FlashSequence) as usize
+    ));
+    cortex_m::interrupt::free(|_| run_in_ram(&sequence));
+}
+
+/// Everything needed to send a raw command while the flash is not accessible.
+#[repr(C)]
+struct CommandSequence {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    /// Bytes to send, replaced with the bytes received.
+    buf: *mut u8,
+    len: usize,
+    ssi: usize,
+    ss_ctrl: usize,
+    flash_flush_cache: unsafe extern "C" fn(),
+    enter_xip: usize,
+}
+
+/// Send the command in `buf` to the flash, replacing it with the response.
+///
+/// The whole exchange happens in a single chip select assertion, so `buf` holds the command,
+/// any address or dummy bytes, and room for the response.
+unsafe fn command(buf: &mut [u8]) {
+    let mut boot2 = [0u32; BOOT2_SIZE_WORDS];
+    core::ptr::copy_nonoverlapping(
+        FLASH_BASE as *const u32,
+        boot2.as_mut_ptr(),
+        BOOT2_SIZE_WORDS,
+    );
+
+    let sequence = CommandSequence {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        buf: buf.as_mut_ptr(),
+        len: buf.len(),
+        ssi: SSI_BASE,
+        ss_ctrl: QSPI_SS_CTRL,
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        enter_xip: boot2.as_ptr() as usize | 1,
+    };
+
+    #[cfg(target_arch = "arm")]
+    debug_assert!(crate::is_in_ram(
+        command_in_ram as unsafe fn(&CommandSequence) as usize
+    ));
+    cortex_m::interrupt::free(|_| command_in_ram(&sequence));
+}
+
+/// Run a flash operation.
+///
+/// This must not access the flash in any way until XIP is restored. It is written in
+/// assembly, as even trivial helpers may not be inlined in debug builds, and would then be
+/// called from flash.
+#[crate::ram_function]
+#[allow(unused_variables)]
+unsafe fn run_in_ram(sequence: &FlashSequence) {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "ldr r4, [r5, #0]",
+        "blx r4", // connect_internal_flash()
+        "ldr r4, [r5, #4]",
+        "blx r4", // flash_exit_xip()
+        "ldr r0, [r5, #12]",
+        "ldr r1, [r5, #16]",
+        "ldr r2, [r5, #20]",
+        "ldr r3, [r5, #24]",
+        "ldr r4, [r5, #8]",
+        "blx r4", // operation(args[0], args[1], args[2], args[3])
+        "ldr r4, [r5, #28]",
+        "blx r4", // flash_flush_cache()
+        "ldr r4, [r5, #32]",
+        "blx r4", // enter_xip()
+        // r5 is callee-saved, so it still holds `sequence` after each call.
+        in("r5") sequence,
+        out("r4") _,
+        clobber_abi("C"),
+    );
+}
+
+/// Send a raw command to the flash.
+///
+/// After `flash_exit_xip` the SSI is in serial mode with 8-bit frames, so each byte written to
+/// the data register clocks one byte in. The chip select is forced low for the whole transfer;
+/// `flash_flush_cache` releases it again.
+#[crate::ram_function]
+#[allow(unused_variables)]
+unsafe fn command_in_ram(sequence: &CommandSequence) {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "ldr r4, [r5, #0]",
+        "blx r4", // connect_internal_flash()
+        "ldr r4, [r5, #4]",
+        "blx r4", // flash_exit_xip()
+        "ldr r0, [r5, #8]", // buf
+        "ldr r1, [r5, #12]", // len
+        "ldr r2, [r5, #16]", // SSI
+        "ldr r3, [r5, #20]", // GPIO_QSPI_SS_CTRL
+        "movs r4, #2",
+        "lsls r4, r4, #8",
+        "str r4, [r3]", // OUTOVER = drive low
+        "1:",
+        "ldrb r4, [r0]",
+        "str r4, [r2, #0x60]", // DR0
+        "2:",
+        "ldr r4, [r2, #0x28]", // SR
+        "lsrs r4, r4, #4", // RFNE into carry
+        "bcc 2b",
+        "ldr r4, [r2, #0x60]",
+        "strb r4, [r0]",
+        "adds r0, #1",
+        "subs r1, #1",
+        "bne 1b",
+        "movs r4, #3",
+        "lsls r4, r4, #8",
+        "str r4, [r3]", // OUTOVER = drive high
+        "ldr r4, [r5, #24]",
+        "blx r4", // flash_flush_cache()
+        "ldr r4, [r5, #28]",
+        "blx r4", // enter_xip()
+        in("r5") sequence,
+        out("r4") _,
+        clobber_abi("C"),
+    );
+}
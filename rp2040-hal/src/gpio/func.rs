@@ -155,6 +155,9 @@ pub struct InvalidFunction;
 /// Where `impl ValidFunction<F> for I` reads as `F is a valid function implemented for the pin I`.
 pub trait ValidFunction<F: Function>: super::pin::PinId {}
 
+// The function of a pin can always be erased.
+impl<I: super::pin::PinId> ValidFunction<DynFunction> for I {}
+
 impl DynFunction {
     pub(crate) fn is_valid<P: super::pin::PinId>(&self, id: &P) -> bool {
         use DynBankId::*;
@@ -169,7 +172,7 @@ impl DynFunction {
             (Xip | Sio(_), Qspi, 0..=5) => true,
             (_, Qspi, 0..=5) => false,
 
-            _ => unreachable!(),
+            _ => hw_unreachable!("Invalid pin id."),
         }
     }
 }
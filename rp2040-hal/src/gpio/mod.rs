@@ -29,6 +29,22 @@
 //! let button2_pin = pins.gpio22.into_pull_up_input();
 //! ```
 //! See [examples/gpio_in_out.rs](https://github.com/rp-rs/rp-hal/tree/main/rp2040-hal/examples/gpio_in_out.rs) for a more practical example
+//!
+//! ## Pad configuration
+//! The electrical properties of a pin are set on its pad, independently of the pin's function,
+//! e.g. for fast PIO outputs or pins next to an analog input:
+//! ```no_run
+//! use rp2040_hal::{gpio::{OutputDriveStrength, OutputSlewRate, Pins}, pac, Sio};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let sio = Sio::new(pac.SIO);
+//! let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+//! let mut fast = pins.gpio0.into_push_pull_output();
+//! fast.set_drive_strength(OutputDriveStrength::TwelveMilliAmps);
+//! fast.set_slew_rate(OutputSlewRate::Fast);
+//! let mut quiet = pins.gpio1.into_floating_disabled();
+//! quiet.set_schmitt_enabled(false);
+//! quiet.set_input_enable(false);
+//! ```
 
 // Design Notes:
 //
@@ -44,23 +60,26 @@ pub use embedded_hal::digital::v2::PinState;
 use crate::{
     atomic_register_access::{write_bitmask_clear, write_bitmask_set},
     pac,
-    sio::Sio,
+    sio::{CoreId, Sio},
     typelevel::{self, Sealed},
 };
 
 mod func;
 pub(crate) mod pin;
+mod pin_bus;
 mod pin_group;
 mod pull;
 
 pub use func::*;
 pub use pin::{DynBankId, DynPinId, PinId};
+pub use pin_bus::PinBus;
 pub use pin_group::PinGroup;
 pub use pull::*;
 
 /// The amount of current that a pin can drive when used as an output.
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutputDriveStrength {
     /// 2 mA
     TwoMilliAmps,
@@ -74,6 +93,7 @@ pub enum OutputDriveStrength {
 
 /// The slew rate of a pin when used as an output.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutputSlewRate {
     /// Slew slow
     Slow,
@@ -83,6 +103,7 @@ pub enum OutputSlewRate {
 
 /// Interrupt kind.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Interrupt {
     /// While low
     LevelLow,
@@ -94,7 +115,7 @@ pub enum Interrupt {
     EdgeHigh,
 }
 impl Interrupt {
-    fn mask(&self) -> u32 {
+    pub(crate) fn mask(&self) -> u32 {
         match self {
             Interrupt::LevelLow => 0b0001,
             Interrupt::LevelHigh => 0b0010,
@@ -106,6 +127,7 @@ impl Interrupt {
 
 /// Interrupt override state.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InterruptOverride {
     /// Don't invert the interrupt.
     Normal = 0,
@@ -119,6 +141,7 @@ pub enum InterruptOverride {
 
 /// Input override state.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InputOverride {
     /// Don't invert the peripheral input.
     Normal = 0,
@@ -132,6 +155,7 @@ pub enum InputOverride {
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 /// Output enable override state.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutputEnableOverride {
     /// Use the original output enable signal from selected peripheral.
     Normal = 0,
@@ -145,6 +169,7 @@ pub enum OutputEnableOverride {
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 /// Output override state.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OutputOverride {
     /// Use the original output signal from selected peripheral.
     DontInvert = 0,
@@ -173,12 +198,12 @@ pub unsafe fn new_pin(id: DynPinId) -> Pin<DynPinId, DynFunction, DynPullType> {
     use pac::io_bank0::gpio::gpio_ctrl::FUNCSEL_A;
     use pin::pin_sealed::PinIdOps;
 
-    let funcsel = id
-        .io_ctrl()
-        .read()
-        .funcsel()
-        .variant()
-        .expect("Invalid funcsel read from register.");
+    let funcsel = id.io_ctrl().read().funcsel().variant();
+    // The values without a variant select no function, like NULL.
+    #[cfg(feature = "size-optimized")]
+    let funcsel = funcsel.unwrap_or(FUNCSEL_A::NULL);
+    #[cfg(not(feature = "size-optimized"))]
+    let funcsel = funcsel.expect("Invalid funcsel read from register.");
     let function = match funcsel {
         FUNCSEL_A::JTAG => DynFunction::Xip,
         FUNCSEL_A::SPI => DynFunction::Spi,
@@ -304,6 +329,26 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
         }
     }
 
+    /// Erase the Pin ID, function and pull type type checks.
+    ///
+    /// This allows to store pins of different configurations, e.g. in an array, and select
+    /// them at runtime. They can be converted back with [`Pin::try_into_pin`] and
+    /// [`Pin::try_into_function`].
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{gpio::{DynPin, FunctionSioOutput, Pins}, pac, Sio};
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let sio = Sio::new(pac.SIO);
+    /// let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+    /// let rows: [DynPin; 2] = [pins.gpio2.into_dyn(), pins.gpio3.into_push_pull_output().into_dyn()];
+    /// for row in rows {
+    ///     let row = row.try_into_function::<FunctionSioOutput>().ok().unwrap();
+    /// }
+    /// ```
+    pub fn into_dyn(self) -> DynPin {
+        self.into_dyn_pin().into_function().into_pull_type()
+    }
+
     /// Get the pin's pull type.
     pub fn pull_type(&self) -> DynPullType {
         self.pull_type.as_dyn()
@@ -497,7 +542,7 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
 
     /// Get the state of the digital output circuitery of the pad.
     #[inline]
-    pub fn get_output_disable(&mut self) -> bool {
+    pub fn get_output_disable(&self) -> bool {
         self.id.pad_ctrl().read().od().bit_is_set()
     }
 
@@ -509,7 +554,7 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
 
     /// Get the state of the digital input circuitery of the pad.
     #[inline]
-    pub fn get_input_enable(&mut self) -> bool {
+    pub fn get_input_enable(&self) -> bool {
         self.id.pad_ctrl().read().ie().bit_is_set()
     }
 
@@ -522,6 +567,18 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
     // =======================
     // IO related methods
 
+    /// Get the input override.
+    #[inline]
+    pub fn get_input_override(&self) -> InputOverride {
+        use pac::io_bank0::gpio::gpio_ctrl::INOVER_A;
+        match self.id.io_ctrl().read().inover().variant() {
+            INOVER_A::NORMAL => InputOverride::Normal,
+            INOVER_A::INVERT => InputOverride::Invert,
+            INOVER_A::LOW => InputOverride::AlwaysLow,
+            INOVER_A::HIGH => InputOverride::AlwaysHigh,
+        }
+    }
+
     /// Set the input override.
     #[inline]
     pub fn set_input_override(&mut self, override_value: InputOverride) {
@@ -535,6 +592,18 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
         self.id.io_ctrl().modify(|_, w| w.inover().variant(variant));
     }
 
+    /// Get the output enable override.
+    #[inline]
+    pub fn get_output_enable_override(&self) -> OutputEnableOverride {
+        use pac::io_bank0::gpio::gpio_ctrl::OEOVER_A;
+        match self.id.io_ctrl().read().oeover().variant() {
+            OEOVER_A::NORMAL => OutputEnableOverride::Normal,
+            OEOVER_A::INVERT => OutputEnableOverride::Invert,
+            OEOVER_A::DISABLE => OutputEnableOverride::Disable,
+            OEOVER_A::ENABLE => OutputEnableOverride::Enable,
+        }
+    }
+
     /// Set the output enable override.
     #[inline]
     pub fn set_output_enable_override(&mut self, override_value: OutputEnableOverride) {
@@ -548,6 +617,18 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
         self.id.io_ctrl().modify(|_, w| w.oeover().variant(variant));
     }
 
+    /// Get the output override.
+    #[inline]
+    pub fn get_output_override(&self) -> OutputOverride {
+        use pac::io_bank0::gpio::gpio_ctrl::OUTOVER_A;
+        match self.id.io_ctrl().read().outover().variant() {
+            OUTOVER_A::NORMAL => OutputOverride::DontInvert,
+            OUTOVER_A::INVERT => OutputOverride::Invert,
+            OUTOVER_A::LOW => OutputOverride::AlwaysLow,
+            OUTOVER_A::HIGH => OutputOverride::AlwaysHigh,
+        }
+    }
+
     /// Set the output override.
     #[inline]
     pub fn set_output_override(&mut self, override_value: OutputOverride) {
@@ -563,6 +644,18 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
             .modify(|_, w| w.outover().variant(variant));
     }
 
+    /// Get the interrupt override.
+    #[inline]
+    pub fn get_interrupt_override(&self) -> InterruptOverride {
+        use pac::io_bank0::gpio::gpio_ctrl::IRQOVER_A;
+        match self.id.io_ctrl().read().irqover().variant() {
+            IRQOVER_A::NORMAL => InterruptOverride::Normal,
+            IRQOVER_A::INVERT => InterruptOverride::Invert,
+            IRQOVER_A::LOW => InterruptOverride::AlwaysLow,
+            IRQOVER_A::HIGH => InterruptOverride::AlwaysHigh,
+        }
+    }
+
     /// Set the interrupt override.
     #[inline]
     pub fn set_interrupt_override(&mut self, override_value: InterruptOverride) {
@@ -643,23 +736,46 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
     /// Interrupt status.
     #[inline]
     pub fn interrupt_status(&self, interrupt: Interrupt) -> bool {
-        let (reg, offset) = self.id.proc_ints(Sio::core());
-        let mask = interrupt.mask();
-        (reg.read().bits() >> offset) & mask == mask
+        self.interrupt_status_on(Sio::core(), interrupt)
     }
 
     /// Is interrupt enabled.
     #[inline]
     pub fn is_interrupt_enabled(&self, interrupt: Interrupt) -> bool {
-        let (reg, offset) = self.id.proc_inte(Sio::core());
-        let mask = interrupt.mask();
-        (reg.read().bits() >> offset) & mask == mask
+        self.is_interrupt_enabled_on(Sio::core(), interrupt)
     }
 
     /// Enable or disable interrupt.
+    ///
+    /// The interrupt is routed to `IO_IRQ_BANK0` of the current core.
     #[inline]
     pub fn set_interrupt_enabled(&self, interrupt: Interrupt, enabled: bool) {
-        let (reg, offset) = self.id.proc_inte(Sio::core());
+        self.set_interrupt_enabled_on(Sio::core(), interrupt, enabled)
+    }
+
+    /// Interrupt status, as seen by `core`.
+    #[inline]
+    pub fn interrupt_status_on(&self, core: CoreId, interrupt: Interrupt) -> bool {
+        let (reg, offset) = self.id.proc_ints(core);
+        let mask = interrupt.mask();
+        (reg.read().bits() >> offset) & mask == mask
+    }
+
+    /// Is interrupt enabled on `core`.
+    #[inline]
+    pub fn is_interrupt_enabled_on(&self, core: CoreId, interrupt: Interrupt) -> bool {
+        let (reg, offset) = self.id.proc_inte(core);
+        let mask = interrupt.mask();
+        (reg.read().bits() >> offset) & mask == mask
+    }
+
+    /// Enable or disable interrupt on `core`.
+    ///
+    /// This allows to handle the interrupt of a pin on the other core, e.g. to set it up
+    /// before launching it.
+    #[inline]
+    pub fn set_interrupt_enabled_on(&self, core: CoreId, interrupt: Interrupt, enabled: bool) {
+        let (reg, offset) = self.id.proc_inte(core);
         let mask = interrupt.mask();
         unsafe {
             if enabled {
@@ -692,7 +808,22 @@ impl<I: PinId, F: func::Function, P: PullType> Pin<I, F, P> {
         }
     }
 
+    /// Raw interrupt status, regardless of the interrupt and dormant wake enables.
+    ///
+    /// Edges stay latched until cleared with [`Pin::clear_interrupt`], which allows to tell
+    /// which pin woke the chip from DORMANT mode after [`power::dormant`](crate::power::dormant)
+    /// disabled the wake events again.
+    #[inline]
+    pub fn raw_interrupt_status(&self, interrupt: Interrupt) -> bool {
+        let (reg, offset) = self.id.intr();
+        let mask = interrupt.mask();
+        (reg.read().bits() >> offset) & mask == mask
+    }
+
     /// Dormant wake status.
+    ///
+    /// This is only set while the dormant wake is enabled, see [`Pin::raw_interrupt_status`]
+    /// to query the event after resuming.
     #[inline]
     pub fn dormant_wake_status(&self, interrupt: Interrupt) -> bool {
         let (reg, offset) = self.id.dormant_wake_ints();
@@ -779,6 +910,9 @@ impl<I: PinId, C: SioConfig, P: PullType> Pin<I, FunctionSio<C>, P> {
         }
     }
 }
+/// A pin whose id, function and pull type are only known at runtime, see [`Pin::into_dyn`].
+pub type DynPin = Pin<DynPinId, DynFunction, DynPullType>;
+
 impl<F: func::Function, P: PullType> Pin<DynPinId, F, P> {
     /// Try to return to a type-checked pin id.
     ///
@@ -1093,6 +1227,55 @@ gpio!(
     ]
 );
 
+impl qspi::Pins {
+    /// Take ownership of the QSPI pins for use as general purpose I/O.
+    ///
+    /// This is meant for applications which run entirely from RAM, for example when loaded
+    /// over USB or by a debugger, or which do not have a flash chip at all. The SSI is
+    /// disabled and consumed, so the flash cannot be accessed anymore, and the pins and their
+    /// pads are reset like the bank 0 pins in [`Pins::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the HAL code is not running from RAM, as execution from flash would stop as
+    /// soon as the pins are taken.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{gpio::qspi, pac, Sio};
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let sio = Sio::new(pac.SIO);
+    /// let pins = qspi::Pins::new_without_flash(
+    ///     pac.IO_QSPI,
+    ///     pac.PADS_QSPI,
+    ///     sio.gpio_qspi,
+    ///     pac.XIP_SSI,
+    ///     &mut pac.RESETS,
+    /// );
+    /// let led = pins.qspi_sd3.into_push_pull_output();
+    /// ```
+    pub fn new_without_flash(
+        io: pac::IO_QSPI,
+        pads: pac::PADS_QSPI,
+        sio: crate::sio::SioGpioQspi,
+        ssi: pac::XIP_SSI,
+        reset: &mut pac::RESETS,
+    ) -> Self {
+        #[cfg(target_arch = "arm")]
+        assert!(crate::is_in_ram(
+            Self::new_without_flash
+                as fn(
+                    pac::IO_QSPI,
+                    pac::PADS_QSPI,
+                    crate::sio::SioGpioQspi,
+                    pac::XIP_SSI,
+                    &mut pac::RESETS,
+                ) -> Self as usize
+        ));
+        ssi.ssienr.write(|w| w.ssi_en().clear_bit());
+        Self::new(io, pads, sio, reset)
+    }
+}
+
 pub use bank0::Pins;
 
 //==============================================================================
@@ -1328,7 +1511,21 @@ macro_rules! bsp_pins {
 /// A wrapper [`AnyPin`]`<Function = `[`FunctionSioOutput`]`>` emulating open-drain function.
 ///
 /// This wrapper implements both InputPin and OutputPin, to simulate an open-drain pin as needed for
-/// example by the wire protocol the DHT11 sensor speaks.
+/// example by the wire protocol the DHT11 sensor speaks, by 1-Wire or by a bit-banged I2C bus.
+/// Setting the pin low drives it low, and setting it high releases it, leaving it to the pull
+/// type of the pin or an external pull-up.
+///
+/// ```no_run
+/// use embedded_hal::digital::v2::{InputPin, OutputPin};
+/// use rp2040_hal::{gpio::{InOutPin, Pins}, pac, Sio};
+/// let mut pac = pac::Peripherals::take().unwrap();
+/// let sio = Sio::new(pac.SIO);
+/// let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+/// let mut line = InOutPin::new(pins.gpio4.into_pull_up_input());
+/// line.set_low().unwrap();
+/// line.set_high().unwrap();
+/// let released = line.is_high().unwrap();
+/// ```
 ///
 /// <https://how2electronics.com/interfacing-dht11-temperature-humidity-sensor-with-raspberry-pi-pico/>
 pub struct InOutPin<T: AnyPin> {
@@ -1396,13 +1593,24 @@ impl<T: AnyPin> embedded_hal::digital::v2::OutputPin for InOutPin<T> {
     }
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+impl<T: AnyPin> embedded_hal::digital::v2::StatefulOutputPin for InOutPin<T> {
+    fn is_set_high(&self) -> Result<bool, Error> {
+        self.is_set_low().map(|low| !low)
+    }
+
+    fn is_set_low(&self) -> Result<bool, Error> {
+        Ok(self.inner.get_output_enable_override() == OutputEnableOverride::Enable)
+    }
+}
+
+#[cfg(feature = "eh1")]
 mod eh1 {
-    use eh1_0_alpha::digital::{
-        ErrorType, InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin,
-    };
+    use eh1_0::digital::{ErrorType, InputPin, OutputPin, StatefulOutputPin};
 
-    use super::{Error, FunctionSio, Pin, PinId, PullType, SioConfig, SioInput, SioOutput};
+    use super::{
+        AnyPin, Error, FunctionSio, InOutPin, OutputEnableOverride, Pin, PinId, PullType,
+        SioConfig, SioInput, SioOutput,
+    };
 
     impl<I, P, S> ErrorType for Pin<I, FunctionSio<S>, P>
     where
@@ -1434,20 +1642,14 @@ mod eh1 {
         I: PinId,
         P: PullType,
     {
-        fn is_set_high(&self) -> Result<bool, Self::Error> {
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
             Ok(self._is_set_high())
         }
 
-        fn is_set_low(&self) -> Result<bool, Self::Error> {
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
             Ok(self._is_set_low())
         }
-    }
 
-    impl<I, P> ToggleableOutputPin for Pin<I, FunctionSio<SioOutput>, P>
-    where
-        I: PinId,
-        P: PullType,
-    {
         fn toggle(&mut self) -> Result<(), Self::Error> {
             self._toggle();
             Ok(())
@@ -1459,15 +1661,53 @@ mod eh1 {
         I: PinId,
         P: PullType,
     {
-        fn is_high(&self) -> Result<bool, Self::Error> {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
             Ok(self._is_high())
         }
 
-        fn is_low(&self) -> Result<bool, Self::Error> {
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
             Ok(self._is_low())
         }
     }
 
+    impl<T: AnyPin> ErrorType for InOutPin<T> {
+        type Error = Error;
+    }
+
+    impl<T: AnyPin> InputPin for InOutPin<T> {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.inner._is_high())
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.inner._is_low())
+        }
+    }
+
+    impl<T: AnyPin> OutputPin for InOutPin<T> {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.inner
+                .set_output_enable_override(OutputEnableOverride::Enable);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.inner
+                .set_output_enable_override(OutputEnableOverride::Disable);
+            Ok(())
+        }
+    }
+
+    impl<T: AnyPin> StatefulOutputPin for InOutPin<T> {
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            self.is_set_low().map(|low| !low)
+        }
+
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.inner.get_output_enable_override() == OutputEnableOverride::Enable)
+        }
+    }
+
     impl<'a, I, F, P> ErrorType for super::AsInputPin<'a, I, F, P>
     where
         I: PinId,
@@ -1480,12 +1720,179 @@ mod eh1 {
     impl<'a, I: PinId, F: super::func::Function, P: PullType> InputPin
         for super::AsInputPin<'a, I, F, P>
     {
-        fn is_high(&self) -> Result<bool, Self::Error> {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
             Ok(self.0._is_high())
         }
 
-        fn is_low(&self) -> Result<bool, Self::Error> {
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
             Ok(self.0._is_low())
         }
     }
 }
+
+#[cfg(feature = "async")]
+pub use self::asynch::on_interrupt;
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::{DynBankId, Error, FunctionSio, Interrupt, Pin, PinId, PullType, SioInput};
+    use crate::{
+        async_utils::{wait_for, WakerSlot},
+        atomic_register_access::{write_bitmask_clear, write_bitmask_set},
+        pac,
+        sio::{CoreId, Sio},
+    };
+
+    /// One slot per pin: bank0 pins first, then the QSPI pins.
+    static PIN_WAKERS: [WakerSlot; 36] = {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: WakerSlot = WakerSlot::new();
+        [EMPTY; 36]
+    };
+
+    fn waker<I: PinId>(id: &I) -> &'static WakerSlot {
+        let pin = id.as_dyn();
+        match pin.bank {
+            DynBankId::Bank0 => &PIN_WAKERS[usize::from(pin.num)],
+            DynBankId::Qspi => &PIN_WAKERS[30 + usize::from(pin.num)],
+        }
+    }
+
+    /// Wake the tasks waiting on a pin of the bank0 or QSPI bank.
+    ///
+    /// Call this from the `IO_IRQ_BANK0` and `IO_IRQ_QSPI` interrupt handlers of the core
+    /// running the waiting tasks. It disables the interrupts which fired on that core, so the
+    /// pins waited on should not be used with other interrupt handling on the same core.
+    pub fn on_interrupt() {
+        let core = Sio::core();
+        // Safety: the status registers are only read, and only the enable bits of the
+        // interrupts that fired are cleared, using the atomic clear alias.
+        let (bank0, qspi) = unsafe { (&*pac::IO_BANK0::PTR, &*pac::IO_QSPI::PTR) };
+        for index in 0..4 {
+            let (ints, inte) = match core {
+                CoreId::Core0 => (
+                    bank0.proc0_ints[index].read().bits(),
+                    bank0.proc0_inte[index].as_ptr(),
+                ),
+                CoreId::Core1 => (
+                    bank0.proc1_ints[index].read().bits(),
+                    bank0.proc1_inte[index].as_ptr(),
+                ),
+            };
+            wake_pending(ints, inte, 8 * index);
+        }
+        let (ints, inte) = match core {
+            CoreId::Core0 => (qspi.proc0_ints.read().bits(), qspi.proc0_inte.as_ptr()),
+            CoreId::Core1 => (qspi.proc1_ints.read().bits(), qspi.proc1_inte.as_ptr()),
+        };
+        wake_pending(ints, inte, 30);
+    }
+
+    fn wake_pending(ints: u32, inte: *mut u32, first_pin: usize) {
+        if ints == 0 {
+            return;
+        }
+        // Safety: see `on_interrupt`.
+        unsafe { write_bitmask_clear(inte, ints) };
+        for pin in 0..8 {
+            if (ints >> (4 * pin)) & 0xF != 0 {
+                PIN_WAKERS[first_pin + pin].wake();
+            }
+        }
+    }
+
+    impl<I: PinId, P: PullType> Pin<I, FunctionSio<SioInput>, P> {
+        /// Wait until the pin is high.
+        ///
+        /// This requires [`on_interrupt`] to be called from the interrupt handler of the pin's
+        /// bank, see [`async_utils`](crate::async_utils).
+        pub async fn wait_for_high(&mut self) {
+            self.wait_for_level(Interrupt::LevelHigh).await
+        }
+
+        /// Wait until the pin is low.
+        ///
+        /// See [`Pin::wait_for_high`].
+        pub async fn wait_for_low(&mut self) {
+            self.wait_for_level(Interrupt::LevelLow).await
+        }
+
+        /// Wait for a rising edge.
+        ///
+        /// Only edges happening after the call are seen. See [`Pin::wait_for_high`].
+        pub async fn wait_for_rising_edge(&mut self) {
+            self.wait_for_edge(Interrupt::EdgeHigh.mask()).await
+        }
+
+        /// Wait for a falling edge.
+        ///
+        /// Only edges happening after the call are seen. See [`Pin::wait_for_high`].
+        pub async fn wait_for_falling_edge(&mut self) {
+            self.wait_for_edge(Interrupt::EdgeLow.mask()).await
+        }
+
+        /// Wait for a rising or falling edge.
+        ///
+        /// Only edges happening after the call are seen. See [`Pin::wait_for_high`].
+        pub async fn wait_for_any_edge(&mut self) {
+            self.wait_for_edge(Interrupt::EdgeHigh.mask() | Interrupt::EdgeLow.mask())
+                .await
+        }
+
+        async fn wait_for_level(&mut self, level: Interrupt) {
+            let high = level == Interrupt::LevelHigh;
+            let (inte, offset) = self.id.proc_inte(Sio::core());
+            wait_for(
+                waker(&self.id),
+                || (self._is_high() == high).then_some(()),
+                // Safety: only this pin's enable bit is set, using the atomic set alias.
+                || unsafe { write_bitmask_set(inte.as_ptr(), level.mask() << offset) },
+            )
+            .await
+        }
+
+        async fn wait_for_edge(&mut self, mask: u32) {
+            let (intr, offset) = self.id.intr();
+            let (inte, _) = self.id.proc_inte(Sio::core());
+            // The edge interrupts are latched: forget the edges seen before the call.
+            intr.write(|w| unsafe { w.bits(mask << offset) });
+            wait_for(
+                waker(&self.id),
+                || {
+                    let seen = (intr.read().bits() >> offset) & mask;
+                    (seen != 0).then(|| intr.write(|w| unsafe { w.bits(seen << offset) }))
+                },
+                // Safety: only this pin's enable bits are set, using the atomic set alias.
+                || unsafe { write_bitmask_set(inte.as_ptr(), mask << offset) },
+            )
+            .await
+        }
+    }
+
+    impl<I: PinId, P: PullType> embedded_hal_async::digital::Wait for Pin<I, FunctionSio<SioInput>, P> {
+        async fn wait_for_high(&mut self) -> Result<(), Error> {
+            Pin::wait_for_high(self).await;
+            Ok(())
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Error> {
+            Pin::wait_for_low(self).await;
+            Ok(())
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Error> {
+            Pin::wait_for_rising_edge(self).await;
+            Ok(())
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Error> {
+            Pin::wait_for_falling_edge(self).await;
+            Ok(())
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Error> {
+            Pin::wait_for_any_edge(self).await;
+            Ok(())
+        }
+    }
+}
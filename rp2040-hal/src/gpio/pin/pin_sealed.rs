@@ -68,7 +68,7 @@ macro_rules! accessor_fns {
                             3 => core::mem::transmute(&qspi.gpio_qspisd1().[<gpio_ $reg:lower>]),
                             4 => core::mem::transmute(&qspi.gpio_qspisd2().[<gpio_ $reg:lower>]),
                             5 => core::mem::transmute(&qspi.gpio_qspisd3().[<gpio_ $reg:lower>]),
-                            _ => unreachable!("Invalid QSPI bank pin number."),
+                            _ => hw_unreachable!("Invalid QSPI bank pin number."),
                         }
                     },
                 }
@@ -147,7 +147,7 @@ where
                     3 => core::mem::transmute(&qspi.gpio_qspi_sd1),
                     4 => core::mem::transmute(&qspi.gpio_qspi_sd2),
                     5 => core::mem::transmute(&qspi.gpio_qspi_sd3),
-                    _ => unreachable!("Invalid QSPI bank pin number."),
+                    _ => hw_unreachable!("Invalid QSPI bank pin number."),
                 }
             },
         }
@@ -0,0 +1,96 @@
+//! Parallel buses of consecutive pins
+//!
+//! A [`PinBus`] drives or samples several consecutive bank 0 pins with a single SIO register
+//! access, so all of them change on the same cycle and a value is read coherently. Use it
+//! instead of individual pins for parallel data lines, where setting the pins one by one
+//! would expose intermediate values and cost one access per pin.
+
+use super::{pin::pin_sealed::PinIdOps, DynBankId, DynPinId, FunctionSioOutput, Pin, PullType};
+
+/// A parallel bus of `WIDTH` consecutive bank 0 pins, written and read in a single access.
+///
+/// Bit 0 of a bus value is the lowest pin of the bus, e.g. for 8080 or 6800 style LCD
+/// interfaces, or parallel ADCs and DACs.
+///
+/// ```no_run
+/// use rp2040_hal::{gpio::{PinBus, Pins}, pac, Sio};
+///
+/// let mut peripherals = pac::Peripherals::take().unwrap();
+/// let sio = Sio::new(peripherals.SIO);
+/// let pins = Pins::new(peripherals.IO_BANK0, peripherals.PADS_BANK0, sio.gpio_bank0, &mut peripherals.RESETS);
+///
+/// let mut bus = PinBus::new([
+///     pins.gpio8.into_push_pull_output().into_dyn_pin(),
+///     pins.gpio9.into_push_pull_output().into_dyn_pin(),
+///     pins.gpio10.into_push_pull_output().into_dyn_pin(),
+///     pins.gpio11.into_push_pull_output().into_dyn_pin(),
+/// ])
+/// .ok()
+/// .unwrap();
+/// bus.write(0b1010);
+/// bus.set_output_enabled(false);
+/// let value = bus.read();
+/// ```
+pub struct PinBus<const WIDTH: usize, P: PullType> {
+    pins: [Pin<DynPinId, FunctionSioOutput, P>; WIDTH],
+    base: u8,
+    mask: u32,
+}
+
+impl<const WIDTH: usize, P: PullType> PinBus<WIDTH, P> {
+    /// Create a new [`PinBus`]
+    ///
+    /// The pins must be consecutive bank 0 GPIOs, in increasing order. Otherwise they are
+    /// returned as the error.
+    pub fn new(
+        pins: [Pin<DynPinId, FunctionSioOutput, P>; WIDTH],
+    ) -> Result<Self, [Pin<DynPinId, FunctionSioOutput, P>; WIDTH]> {
+        let base = match pins.first() {
+            Some(pin) if pin.id().bank == DynBankId::Bank0 => pin.id().num,
+            _ => return Err(pins),
+        };
+        let contiguous = pins
+            .iter()
+            .zip(base..)
+            .all(|(pin, num)| pin.id().bank == DynBankId::Bank0 && pin.id().num == num);
+        if !contiguous {
+            return Err(pins);
+        }
+        // There are 30 bank 0 pins, so the bus is at most 30 bits wide.
+        let mask = ((1u32 << WIDTH) - 1) << base;
+        Ok(Self { pins, base, mask })
+    }
+
+    /// Releases the pins, with their outputs enabled again.
+    pub fn free(mut self) -> [Pin<DynPinId, FunctionSioOutput, P>; WIDTH] {
+        self.set_output_enabled(true);
+        self.pins
+    }
+
+    /// Drive the bus with `value`, changing all pins at the same time.
+    ///
+    /// The bits of `value` above `WIDTH` are ignored.
+    pub fn write(&mut self, value: u32) {
+        let id = self.pins[0].id();
+        let old = id.sio_out().read().bits();
+        id.sio_out_xor()
+            .write(|w| unsafe { w.bits((old ^ (value << self.base)) & self.mask) });
+    }
+
+    /// Read the level of all pins of the bus at the same time.
+    pub fn read(&self) -> u32 {
+        (crate::sio::Sio::read_bank0() & self.mask) >> self.base
+    }
+
+    /// Enable or disable the outputs of all pins at the same time.
+    ///
+    /// The outputs must be disabled to read a value driven by another device.
+    pub fn set_output_enabled(&mut self, enabled: bool) {
+        let id = self.pins[0].id();
+        if enabled {
+            id.sio_oe_set().write(|w| unsafe { w.bits(self.mask) });
+        } else {
+            id.sio_oe_clr().write(|w| unsafe { w.bits(self.mask) });
+        }
+    }
+}
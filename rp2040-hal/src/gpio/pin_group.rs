@@ -45,7 +45,7 @@ impl<P: PinId, M: PullType, T: WritePinHList> WritePinHList
     for HCons<Pin<P, FunctionSioOutput, M>, T>
 {
     fn write_mask(&self) -> u32 {
-        self.tail.write_mask()
+        (1 << self.head.id().num) | self.tail.write_mask()
     }
 }
 
@@ -151,6 +151,20 @@ where
         }
     }
 
+    /// Write the output pins of the group all at the same time, from the bit field `state`.
+    ///
+    /// Each pin takes the value of its own index in `state`, as returned by [`PinGroup::read`].
+    /// The other bits are ignored.
+    pub fn set_u32(&mut self, state: u32) {
+        use super::pin::pin_sealed::PinIdOps;
+        let mask = self.0.write_mask();
+        let head_id = self.0.head.borrow().id();
+        let old = head_id.sio_out().read().bits();
+        head_id
+            .sio_out_xor()
+            .write(|w| unsafe { w.bits((old ^ state) & mask) });
+    }
+
     /// Toggles this set of pins all at the same time.
     pub fn toggle(&mut self) {
         use super::pin::pin_sealed::PinIdOps;
@@ -0,0 +1,155 @@
+//! Inter-Integrated Circuit (I2C) bus - Async Code
+//!
+//! This module implements the `embedded-hal-async` I2C trait for the controller mode, waiting
+//! for the I2C interrupts instead of polling. See [`async_utils`](crate::async_utils).
+
+use embedded_hal_async::i2c::{I2c, Operation};
+
+use super::{Controller, Error, I2cDevice, I2C};
+use crate::{
+    async_utils::{wait_for, WakerSlot},
+    pac::{self, i2c0::RegisterBlock},
+};
+
+static WAKERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+
+// Bits of IC_INTR_MASK, where 1 unmasks the interrupt.
+const RX_FULL: u32 = 1 << 2;
+const TX_EMPTY: u32 = 1 << 4;
+const TX_ABRT: u32 = 1 << 6;
+const STOP_DET: u32 = 1 << 9;
+
+/// Wake the task using the I2C controller.
+///
+/// Call this from the `I2Cx_IRQ` interrupt handler, e.g. `on_interrupt::<pac::I2C0>()`. It
+/// masks all the I2C interrupts, so the I2C block should not be used with other interrupt
+/// handling at the same time.
+pub fn on_interrupt<D: I2cDevice>() {
+    // Safety: only the interrupt mask is modified, from the interrupt handler which cannot be
+    // preempted by the task modifying it.
+    let rb: &RegisterBlock = unsafe {
+        match D::ID {
+            0 => &*pac::I2C0::ptr(),
+            _ => &*pac::I2C1::ptr(),
+        }
+    };
+    if rb.ic_intr_stat.read().bits() != 0 {
+        rb.ic_intr_mask.write(|w| unsafe { w.bits(0) });
+        WAKERS[D::ID].wake();
+    }
+}
+
+/// Wait until `ready` returns true, sleeping until one of the interrupts in `mask` fires.
+async fn wait_until<D: I2cDevice>(i2c: &D, mask: u32, mut ready: impl FnMut(&D) -> bool) {
+    wait_for(
+        &WAKERS[D::ID],
+        || ready(i2c).then_some(()),
+        || i2c.ic_intr_mask.write(|w| unsafe { w.bits(mask) }),
+    )
+    .await
+}
+
+impl<T: I2cDevice, PINS> I2C<T, PINS, Controller> {
+    async fn read_async(
+        &mut self,
+        buffer: &mut [u8],
+        force_restart: bool,
+        do_stop: bool,
+    ) -> Result<(), Error> {
+        let len = buffer.len();
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            let first = i == 0;
+            let last = i + 1 == len;
+
+            // Only one read command is in flight, so the FIFO always has space for it.
+            self.i2c.ic_data_cmd.write(|w| {
+                if force_restart && first {
+                    w.restart().enable();
+                } else {
+                    w.restart().disable();
+                }
+
+                if do_stop && last {
+                    w.stop().enable();
+                } else {
+                    w.stop().disable();
+                }
+
+                w.cmd().read()
+            });
+
+            wait_until(&self.i2c, RX_FULL | TX_ABRT, |i2c| {
+                i2c.ic_rxflr.read().bits() != 0 || i2c.ic_tx_abrt_source.read().bits() != 0
+            })
+            .await;
+
+            if let Some(abort_reason) = self.read_and_clear_abort_reason() {
+                return Err(Error::Abort(abort_reason));
+            }
+
+            *byte = self.i2c.ic_data_cmd.read().dat().bits();
+        }
+
+        Ok(())
+    }
+
+    async fn write_async(&mut self, bytes: &[u8], do_stop: bool) -> Result<(), Error> {
+        for (i, byte) in bytes.iter().enumerate() {
+            let last = i + 1 == bytes.len();
+
+            self.i2c.ic_data_cmd.write(|w| {
+                if do_stop && last {
+                    w.stop().enable();
+                } else {
+                    w.stop().disable();
+                }
+                unsafe { w.dat().bits(*byte) }
+            });
+
+            // As in the blocking `write_internal`, TX_EMPTY is only raised once the byte left the
+            // shift register, since TX_EMPTY_CTRL is set.
+            wait_until(&self.i2c, TX_EMPTY | TX_ABRT, |i2c| {
+                i2c.ic_raw_intr_stat.read().tx_empty().is_active()
+                    || i2c.ic_tx_abrt_source.read().bits() != 0
+            })
+            .await;
+
+            let abort_reason = self.read_and_clear_abort_reason();
+
+            if abort_reason.is_some() || (do_stop && last) {
+                wait_until(&self.i2c, STOP_DET, |i2c| {
+                    i2c.ic_raw_intr_stat.read().stop_det().is_active()
+                })
+                .await;
+
+                self.i2c.ic_clr_stop_det.read().clr_stop_det();
+            }
+
+            if let Some(abort_reason) = abort_reason {
+                return Err(Error::Abort(abort_reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: I2cDevice, PINS> I2c for I2C<T, PINS, Controller> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let addr: u16 = address.into();
+        Self::validate(addr, None, None)?;
+        self.setup(addr);
+        let len = operations.len();
+        for (i, operation) in operations.iter_mut().enumerate() {
+            let last = i + 1 == len;
+            match operation {
+                Operation::Read(buf) => self.read_async(buf, false, last).await?,
+                Operation::Write(buf) => self.write_async(buf, last).await?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,8 +2,8 @@ use core::{marker::PhantomData, ops::Deref};
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
 use fugit::HertzU32;
 
-#[cfg(feature = "eh1_0_alpha")]
-use eh1_0_alpha::i2c as eh1;
+#[cfg(feature = "eh1")]
+use eh1_0::i2c as eh1;
 
 use super::{i2c_reserved_addr, Controller, Error, ValidPinScl, ValidPinSda, I2C};
 use crate::{
@@ -48,52 +48,7 @@ where
         i2c.ic_tx_tl.write(|w| unsafe { w.tx_tl().bits(0) });
         i2c.ic_rx_tl.write(|w| unsafe { w.rx_tl().bits(0) });
 
-        let freq_in = system_clock.to_Hz();
-
-        // There are some subtleties to I2C timing which we are completely ignoring here
-        // See: https://github.com/raspberrypi/pico-sdk/blob/bfcbefafc5d2a210551a4d9d80b4303d4ae0adf7/src/rp2_common/hardware_i2c/i2c.c#L69
-        let period = (freq_in + freq / 2) / freq;
-        let lcnt = period * 3 / 5; // spend 3/5 (60%) of the period low
-        let hcnt = period - lcnt; // and 2/5 (40%) of the period high
-
-        // Check for out-of-range divisors:
-        assert!(hcnt <= 0xffff);
-        assert!(lcnt <= 0xffff);
-        assert!(hcnt >= 8);
-        assert!(lcnt >= 8);
-
-        // Per I2C-bus specification a device in standard or fast mode must
-        // internally provide a hold time of at least 300ns for the SDA signal to
-        // bridge the undefined region of the falling edge of SCL. A smaller hold
-        // time of 120ns is used for fast mode plus.
-        let sda_tx_hold_count = if freq < 1000000 {
-            // sda_tx_hold_count = freq_in [cycles/s] * 300ns * (1s / 1e9ns)
-            // Reduce 300/1e9 to 3/1e7 to avoid numbers that don't fit in uint.
-            // Add 1 to avoid division truncation.
-            ((freq_in * 3) / 10000000) + 1
-        } else {
-            // fast mode plus requires a clk_in > 32MHz
-            assert!(freq_in >= 32_000_000);
-
-            // sda_tx_hold_count = freq_in [cycles/s] * 120ns * (1s / 1e9ns)
-            // Reduce 120/1e9 to 3/25e6 to avoid numbers that don't fit in uint.
-            // Add 1 to avoid division truncation.
-            ((freq_in * 3) / 25000000) + 1
-        };
-        assert!(sda_tx_hold_count <= lcnt - 2);
-
-        unsafe {
-            i2c.ic_fs_scl_hcnt
-                .write(|w| w.ic_fs_scl_hcnt().bits(hcnt as u16));
-            i2c.ic_fs_scl_lcnt
-                .write(|w| w.ic_fs_scl_lcnt().bits(lcnt as u16));
-            i2c.ic_fs_spklen.write(|w| {
-                w.ic_fs_spklen()
-                    .bits(if lcnt < 16 { 1 } else { (lcnt / 16) as u8 })
-            });
-            i2c.ic_sda_hold
-                .modify(|_r, w| w.ic_sda_tx_hold().bits(sda_tx_hold_count as u16));
-        }
+        configure_timing(&i2c, freq, system_clock.to_Hz());
 
         // Enable I2C block
         i2c.ic_enable.write(|w| w.enable().enabled());
@@ -105,8 +60,71 @@ where
         }
     }
 }
+
+/// Set the SCL timing for a bus frequency of `freq` and a system clock of `freq_in`, in Hz.
+fn configure_timing(i2c: &Block, freq: u32, freq_in: u32) {
+    // There are some subtleties to I2C timing which we are completely ignoring here
+    // See: https://github.com/raspberrypi/pico-sdk/blob/bfcbefafc5d2a210551a4d9d80b4303d4ae0adf7/src/rp2_common/hardware_i2c/i2c.c#L69
+    let period = (freq_in + freq / 2) / freq;
+    let lcnt = period * 3 / 5; // spend 3/5 (60%) of the period low
+    let hcnt = period - lcnt; // and 2/5 (40%) of the period high
+
+    // Check for out-of-range divisors:
+    assert!(hcnt <= 0xffff);
+    assert!(lcnt <= 0xffff);
+    assert!(hcnt >= 8);
+    assert!(lcnt >= 8);
+
+    // Per I2C-bus specification a device in standard or fast mode must
+    // internally provide a hold time of at least 300ns for the SDA signal to
+    // bridge the undefined region of the falling edge of SCL. A smaller hold
+    // time of 120ns is used for fast mode plus.
+    let sda_tx_hold_count = if freq < 1000000 {
+        // sda_tx_hold_count = freq_in [cycles/s] * 300ns * (1s / 1e9ns)
+        // Reduce 300/1e9 to 3/1e7 to avoid numbers that don't fit in uint.
+        // Add 1 to avoid division truncation.
+        ((freq_in * 3) / 10000000) + 1
+    } else {
+        // fast mode plus requires a clk_in > 32MHz
+        assert!(freq_in >= 32_000_000);
+
+        // sda_tx_hold_count = freq_in [cycles/s] * 120ns * (1s / 1e9ns)
+        // Reduce 120/1e9 to 3/25e6 to avoid numbers that don't fit in uint.
+        // Add 1 to avoid division truncation.
+        ((freq_in * 3) / 25000000) + 1
+    };
+    assert!(sda_tx_hold_count <= lcnt - 2);
+
+    unsafe {
+        i2c.ic_fs_scl_hcnt
+            .write(|w| w.ic_fs_scl_hcnt().bits(hcnt as u16));
+        i2c.ic_fs_scl_lcnt
+            .write(|w| w.ic_fs_scl_lcnt().bits(lcnt as u16));
+        i2c.ic_fs_spklen.write(|w| {
+            w.ic_fs_spklen()
+                .bits(if lcnt < 16 { 1 } else { (lcnt / 16) as u8 })
+        });
+        i2c.ic_sda_hold
+            .modify(|_r, w| w.ic_sda_tx_hold().bits(sda_tx_hold_count as u16));
+    }
+}
+
 impl<T: Deref<Target = Block>, PINS> I2C<T, PINS, Controller> {
-    fn validate(
+    /// Change the bus frequency, e.g. after the system clock changed.
+    ///
+    /// The controller is disabled while the timing is updated, so this must not be called
+    /// during a transfer.
+    pub fn set_frequency(&mut self, freq: HertzU32, system_clock: HertzU32) {
+        let freq = freq.to_Hz();
+        assert!(freq <= 1_000_000);
+        assert!(freq > 0);
+
+        self.i2c.ic_enable.write(|w| w.enable().disabled());
+        configure_timing(&self.i2c, freq, system_clock.to_Hz());
+        self.i2c.ic_enable.write(|w| w.enable().enabled());
+    }
+
+    pub(super) fn validate(
         addr: u16,
         opt_tx_empty: Option<bool>,
         opt_rx_empty: Option<bool>,
@@ -131,13 +149,13 @@ impl<T: Deref<Target = Block>, PINS> I2C<T, PINS, Controller> {
         }
     }
 
-    fn setup(&mut self, addr: u16) {
+    pub(super) fn setup(&mut self, addr: u16) {
         self.i2c.ic_enable.write(|w| w.enable().disabled());
         self.i2c.ic_tar.write(|w| unsafe { w.ic_tar().bits(addr) });
         self.i2c.ic_enable.write(|w| w.enable().enabled());
     }
 
-    fn read_and_clear_abort_reason(&mut self) -> Option<u32> {
+    pub(super) fn read_and_clear_abort_reason(&mut self) -> Option<u32> {
         let abort_reason = self.i2c.ic_tx_abrt_source.read().bits();
         if abort_reason != 0 {
             // Note clearing the abort flag also clears the reason, and
@@ -291,7 +309,7 @@ impl<T: Deref<Target = Block>, PINS> I2C<T, PINS, Controller> {
     /// - `SAD+R/W` = slave address followed by bit 1 to indicate reading or 0 to indicate writing
     /// - `SR` = repeated start condition
     /// - `SP` = stop condition
-    #[cfg(feature = "eh1_0_alpha")]
+    #[cfg(feature = "eh1")]
     pub fn transaction_iter<'a, O>(&mut self, address: u8, operations: O) -> Result<(), Error>
     where
         O: IntoIterator<Item = eh1::Operation<'a>>,
@@ -347,12 +365,12 @@ impl<T: Deref<Target = Block>, PINS> Write for I2C<T, PINS, Controller> {
     }
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<T: Deref<Target = Block>, PINS> eh1::ErrorType for I2C<T, PINS, Controller> {
     type Error = Error;
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<T: Deref<Target = Block>, PINS> eh1::I2c for I2C<T, PINS, Controller> {
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
         Write::write(self, addr, bytes)
@@ -74,8 +74,8 @@ macro_rules! intrinsics_aliases {
 /// additional behaviour:
 ///
 /// * `slower_than_default` - indicates that the override is slower than the
-///   default implementation.  Currently this just disables the override
-///   entirely.
+///   default implementation.  The override is disabled unless the feature
+///   `rom-slow-intrinsics` is set, trading speed for flash space.
 /// * `bootrom_v2` - indicates that the override is only available
 ///   on a V2 bootrom or higher.  Only enabled when the feature
 ///   `rom-v2-intrinsics` is set.
@@ -96,11 +96,20 @@ macro_rules! intrinsics {
     ) => {
         // Not exported, but defined so the actual implementation is
         // considered used
+        #[cfg(not(feature = "rom-slow-intrinsics"))]
         #[allow(dead_code)]
         fn $name( $($argname: $ty),* ) -> $ret {
             $($body)*
         }
 
+        #[cfg(feature = "rom-slow-intrinsics")]
+        intrinsics! {
+            $(#[$($attr)*])*
+            extern $abi fn $name( $($argname: $ty),* ) -> $ret {
+                $($body)*
+            }
+        }
+
         intrinsics!($($rest)*);
     };
 
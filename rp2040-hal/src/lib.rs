@@ -7,21 +7,46 @@
 //!
 //! # Crate features
 //!
+//! * **async** -
+//!   Interrupt driven async drivers for GPIO inputs, timer alarms, UART (through the
+//!   `embedded-io-async` traits), I2C and SPI (through the `embedded-hal-async` traits), ADC and
+//!   DMA, see [`async_utils`]. Requires rust 1.75, unlike the other features which build on the
+//!   MSRV of 1.64
+//! * **boot2-w25q080**, **boot2-at25sf128a**, **boot2-is25lp080**, **boot2-generic-03h** -
+//!   Place the second stage bootloader for the given flash chip in the `.boot2` section, see
+//!   [`boot2`]
 //! * **chrono** -
 //!   Modifies some RTC access functions to use chrono types instead of a rp2040-hal specific
 //!   DateTime type
 //! * **critical-section-impl** -
-//!   critical section that is safe for multicore use
+//!   critical section that is safe for multicore use. It combines interrupt masking with
+//!   hardware spinlock 31, which is then reserved for this purpose.
 //! * **defmt** -
 //!   Implement `defmt::Format` for several types.
 //! * **disable-intrinsics** -
 //!   Disable automatic mapping of language features (like floating point math) to ROM functions
+//! * **embassy-time-driver** -
+//!   Implement the `embassy-time` driver with the timer and its alarm 0, see
+//!   [`timer::embassy`]. Requires rust 1.82
+//! * **eh1** -
+//!   Implement the embedded-hal 1.0 traits (digital, spi, i2c, delay and pwm) and the
+//!   embedded-hal-nb 1.0 traits (serial and spi) for GPIO, SPI, I2C, UART, Timer, SysTick and
+//!   PWM, alongside the embedded-hal 0.2 traits
 //! * **eh1_0_alpha** -
-//!   Support alpha release of embedded-hal
+//!   Deprecated alias of `eh1`
+//! * **getrandom** -
+//!   Back the `getrandom` crate with the global pool of [`entropy`], see [`entropy::seed`]
+//! * **panic-persist** -
+//!   Provide a panic handler which records the panic with [`panic_persist::record`] and
+//!   resets the chip
 //! * **rom-func-cache** -
 //!   Memoize(cache) ROM function pointers on first use to improve performance
 //! * **rt** -
 //!   Minimal startup / runtime for Cortex-M microcontrollers
+//! * **rom-slow-intrinsics** -
+//!   Also use ROM functions for intrinsics where they are slower than the compiler's
+//!   implementation, like float comparisons, some conversions and trigonometry. This saves
+//!   flash space, as the ROM versions do not take any.
 //! * **rom-v2-intrinsics** -
 //!   This enables ROM functions for f64 math that were not present in the earliest RP2040s
 //! * **rp2040-e5** -
@@ -34,6 +59,14 @@
 //! * **rtic-monotonic** -
 //!   Implement
 //!   `rtic_monotonic::Monotonic` based on the RP2040 timer peripheral
+//! * **size-optimized** -
+//!   Remove the panics of register access paths reading values the hardware never produces,
+//!   like an invalid core number, and of internal checks which cannot fail, like the source
+//!   type of a clock. A reserved FUNCSEL value read by [`gpio::new_pin`] becomes
+//!   `DynFunction::Null` instead of panicking. This saves about a dozen bytes of flash in the
+//!   examples built with `opt-level = "z"` and LTO, and more in applications which use several
+//!   of these paths. Error types are unchanged, so the formatting code is only linked in if
+//!   the panic handler formats its message.
 
 #![warn(missing_docs)]
 #![no_std]
@@ -43,21 +76,51 @@ pub use paste;
 /// Re-export of the PAC
 pub use rp2040_pac as pac;
 
+/// Marks a branch which the hardware never takes, e.g. an invalid value read from a register.
+///
+/// With the **size-optimized** feature this is an `unreachable_unchecked`, which removes the
+/// panic and its message from the register access paths.
+macro_rules! hw_unreachable {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "size-optimized")]
+        #[allow(unused_unsafe)]
+        unsafe {
+            core::hint::unreachable_unchecked()
+        }
+        #[cfg(not(feature = "size-optimized"))]
+        unreachable!($($arg)*)
+    }};
+}
+
 #[macro_use]
 mod intrinsics;
 
 pub mod adc;
+#[cfg(feature = "async")]
+pub mod async_utils;
 pub(crate) mod atomic_register_access;
+#[cfg(any(
+    feature = "boot2-w25q080",
+    feature = "boot2-at25sf128a",
+    feature = "boot2-is25lp080",
+    feature = "boot2-generic-03h"
+))]
+pub mod boot2;
 pub mod clocks;
 #[cfg(feature = "critical-section-impl")]
 mod critical_section_impl;
 pub mod dma;
+pub mod entropy;
+pub mod flash;
 mod float;
 pub mod gpio;
 pub mod i2c;
 pub mod multicore;
+pub mod panic_persist;
+pub mod peripherals;
 pub mod pio;
 pub mod pll;
+pub mod power;
 pub mod prelude;
 pub mod pwm;
 pub mod resets;
@@ -67,18 +130,24 @@ pub mod rtc;
 pub mod sio;
 pub mod spi;
 pub mod ssi;
+pub mod stack_guard;
+pub mod sysinfo;
+pub mod systick;
 pub mod timer;
 pub mod typelevel;
 pub mod uart;
 pub mod usb;
 pub mod vector_table;
+pub mod vreg;
 pub mod watchdog;
+pub mod xip;
 pub mod xosc;
 
 // Provide access to common datastructures to avoid repeating ourselves
 pub use adc::Adc;
 pub use clocks::Clock;
 pub use i2c::I2C;
+pub use peripherals::Peripherals;
 /// Attribute to declare the entry point of the program
 ///
 /// This is based on and can be used like the [entry attribute from
@@ -88,12 +157,43 @@ pub use i2c::I2C;
 /// of `main`. As spinlocks are not automatically unlocked on software resets,
 /// this can prevent unexpected deadlocks when running from a debugger.
 pub use rp2040_hal_macros::entry;
+/// Attribute to place a function in RAM
+///
+/// The function is placed in the `.data` section, which is copied from flash to RAM during
+/// startup, and it is never inlined into its callers. This is required for code which must
+/// keep running while the flash is not accessible, e.g. while it is being programmed.
+///
+/// Note that this only moves the function itself: anything it calls must be inlined or be
+/// a RAM function as well. Inlining is not guaranteed, in particular in debug builds, so code
+/// which runs while the flash is not accessible is best kept free of function calls, or
+/// written in assembly as done by the [`flash`] and [`ssi`] modules. [`is_in_ram`] can be
+/// used to check where a function ended up.
+///
+/// The attribute cannot be used on generic or async functions, as their code is generated at
+/// the call site, nor combined with `#[inline]`.
+///
+/// ```no_run
+/// #[rp2040_hal::ram_function]
+/// fn toggle_fast(sio: &rp2040_hal::pac::sio::RegisterBlock) {
+///     sio.gpio_out_xor.write(|w| unsafe { w.bits(1 << 25) });
+/// }
+/// ```
+pub use rp2040_hal_macros::ram_function;
 use sio::CoreId;
 pub use sio::Sio;
 pub use spi::Spi;
 pub use timer::Timer;
 pub use watchdog::Watchdog;
 
+/// Check if an address is in the SRAM, including SRAM4 and SRAM5.
+///
+/// This can be used to check that a [`ram_function`] or a buffer really is in RAM.
+pub fn is_in_ram(address: usize) -> bool {
+    const SRAM_START: usize = 0x2000_0000;
+    const SRAM_END: usize = 0x2004_2000;
+    (SRAM_START..SRAM_END).contains(&address)
+}
+
 /// Trigger full reset of the RP2040.
 ///
 /// Uses the watchdog and the power-on state machine (PSM) to reset all on-chip components.
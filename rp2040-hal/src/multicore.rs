@@ -21,66 +21,48 @@
 //!     let mut sio = Sio::new(pac.SIO);
 //!     // Other init code above this line
 //!     let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
-//!     let cores = mc.cores();
-//!     let core1 = &mut cores[1];
-//!     let _test = core1.spawn(unsafe { &mut CORE1_STACK.mem }, core1_task);
+//!     let _test = mc.spawn(unsafe { &mut CORE1_STACK.mem }, core1_task);
 //!     // The rest of your application below this line
 //!     # loop {}
 //! }
 //!
 //! ```
 //!
-//! For inter-processor communications, see [`crate::sio::SioFifo`] and [`crate::sio::Spinlock0`]
+//! For inter-processor communications, see [`crate::sio::SioFifo`], [`crate::sio::Channel`],
+//! [`rpc`] and [`crate::sio::Spinlock0`]
 //!
 //! For a detailed example, see [examples/multicore_fifo_blink.rs](https://github.com/rp-rs/rp-hal/tree/main/rp2040-hal/examples/multicore_fifo_blink.rs)
 
+use core::cell::UnsafeCell;
 use core::mem::ManuallyDrop;
 use core::sync::atomic::compiler_fence;
+use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering;
 
 use crate::pac;
+use crate::sio::CoreId;
 use crate::Sio;
 
+pub mod rpc;
+
 /// Errors for multicore operations.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     /// Operation is invalid on this core.
     InvalidCore,
     /// Core was unresponsive to commands.
     Unresponsive,
+    /// Code or data required to be in RAM is not.
+    NotInRam,
 }
 
 #[inline(always)]
-fn install_stack_guard(stack_limit: *mut usize) {
-    let core = unsafe { pac::CorePeripherals::steal() };
-
+fn core1_setup(stack_limit: *mut usize) {
     // Trap if MPU is already configured
-    if core.MPU.ctrl.read() != 0 {
+    if unsafe { crate::stack_guard::install(stack_limit) }.is_err() {
         cortex_m::asm::udf();
     }
-
-    // The minimum we can protect is 32 bytes on a 32 byte boundary, so round up which will
-    // just shorten the valid stack range a tad.
-    let addr = (stack_limit as u32 + 31) & !31;
-    // Mask is 1 bit per 32 bytes of the 256 byte range... clear the bit for the segment we want
-    let subregion_select = 0xff ^ (1 << ((addr >> 5) & 7));
-    unsafe {
-        core.MPU.ctrl.write(5); // enable mpu with background default map
-        const RBAR_VALID: u32 = 0x10;
-        core.MPU.rbar.write((addr & !0xff) | RBAR_VALID);
-        core.MPU.rasr.write(
-            1 // enable region
-               | (0x7 << 1) // size 2^(7 + 1) = 256
-               | (subregion_select << 8)
-               | 0x10000000, // XN = disable instruction fetch; no other bits means no permissions
-        );
-    }
-}
-
-#[inline(always)]
-fn core1_setup(stack_limit: *mut usize) {
-    install_stack_guard(stack_limit);
     // TODO: irq priorities
 }
 
@@ -124,6 +106,16 @@ impl<'p> Multicore<'p> {
     pub fn cores(&mut self) -> &'p mut [Core] {
         &mut self.cores
     }
+
+    /// Spawn a function on core 1.
+    ///
+    /// This is a shorthand for calling [`Core::spawn`] on the second of the [`cores`](Self::cores).
+    pub fn spawn<F>(&mut self, stack: &'static mut [usize], entry: F) -> Result<(), Error>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.cores[1].spawn(stack, entry)
+    }
 }
 
 /// A handle for controlling a logical core.
@@ -135,6 +127,109 @@ pub struct Core<'p> {
     )>,
 }
 
+/// Set while core 1 runs a task started by [`Core::spawn`] or [`Core::spawn_in_ram`].
+static CORE1_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Number of times the bootrom launch handshake is retried before giving up.
+const HANDSHAKE_RETRIES: u32 = 16;
+
+/// Number of polls of the FIFO before a handshake response is considered lost.
+const HANDSHAKE_TIMEOUT: u32 = 100_000;
+
+/// Force core1 off and back on, which restarts it in the bootrom.
+///
+/// Core 1 then runs [`wait_for_vector`](crate::rom_data::wait_for_vector), waiting to be
+/// started with [`launch`]. Prefer [`Core::reset`], unless implementing a custom runtime.
+pub fn reset_core1(psm: &mut pac::PSM) {
+    CORE1_RUNNING.store(false, Ordering::Relaxed);
+    psm.frce_off.modify(|_, w| w.proc1().set_bit());
+    while !psm.frce_off.read().proc1().bit_is_set() {
+        cortex_m::asm::nop();
+    }
+    psm.frce_off.modify(|_, w| w.proc1().clear_bit());
+}
+
+/// Read from the FIFO, giving up after `HANDSHAKE_TIMEOUT` polls.
+fn read_with_timeout(fifo: &mut crate::sio::SioFifo) -> Option<u32> {
+    for _ in 0..HANDSHAKE_TIMEOUT {
+        if let Some(value) = fifo.read() {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Start core 1 in `entry` using the bootrom's launch protocol.
+///
+/// After reset, core 1 is waiting to receive commands over FIFO.
+/// This is the sequence to have it jump to some code.
+///
+/// This is the low level building block of [`Core::spawn`], for runtimes which need to set up
+/// core 1 themselves. The bootrom sets `VTOR` to `vector_table`, `MSP` to `stack_ptr` and
+/// jumps to `entry`, with the thumb bit set by the caller. Core 1 must be waiting in the
+/// bootrom, for example after [`reset_core1`].
+///
+/// # Safety
+///
+/// `vector_table` must point to a valid vector table, `stack_ptr` to the end of a stack
+/// reserved for core 1, and `entry` to a function which never returns. All of them must stay
+/// valid for as long as core 1 uses them.
+///
+/// ```no_run
+/// use rp2040_hal::{multicore, pac, Sio};
+///
+/// static mut STACK: [usize; 1024] = [0; 1024];
+///
+/// extern "C" fn core1_main() -> ! {
+///     loop {}
+/// }
+///
+/// let mut pac = pac::Peripherals::take().unwrap();
+/// let mut sio = Sio::new(pac.SIO);
+/// multicore::reset_core1(&mut pac.PSM);
+/// unsafe {
+///     multicore::launch(
+///         &mut sio.fifo,
+///         pac.PPB.vtor.read().bits(),
+///         STACK.as_mut_ptr_range().end,
+///         core1_main as usize,
+///     )
+/// }
+/// .unwrap();
+/// ```
+pub unsafe fn launch(
+    fifo: &mut crate::sio::SioFifo,
+    vector_table: u32,
+    stack_ptr: *mut usize,
+    entry: usize,
+) -> Result<(), Error> {
+    let cmd_seq = [0, 0, 1, vector_table as usize, stack_ptr as usize, entry];
+
+    let mut seq = 0;
+    let mut fails = 0;
+    loop {
+        let cmd = cmd_seq[seq] as u32;
+        if cmd == 0 {
+            fifo.drain();
+            cortex_m::asm::sev();
+        }
+        fifo.write_blocking(cmd);
+        let response = fifo.read_blocking();
+        if cmd == response {
+            seq += 1;
+        } else {
+            seq = 0;
+            fails += 1;
+            if fails > HANDSHAKE_RETRIES {
+                return Err(Error::Unresponsive);
+            }
+        }
+        if seq >= cmd_seq.len() {
+            return Ok(());
+        }
+    }
+}
+
 impl<'p> Core<'p> {
     /// Get the id of this core.
     pub fn id(&self) -> u8 {
@@ -144,6 +239,47 @@ impl<'p> Core<'p> {
         }
     }
 
+    /// Whether this core is running a task.
+    ///
+    /// Core 0 is always running. Core 1 is running from a successful [`spawn`](Self::spawn)
+    /// or [`spawn_in_ram`](Self::spawn_in_ram) until it is [reset](Self::reset), or until the
+    /// closure given to `spawn` returns, after which the core idles in a `wfe` loop. A paused
+    /// core still counts as running.
+    pub fn is_running(&self) -> bool {
+        match self.inner {
+            None => true,
+            Some(..) => CORE1_RUNNING.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset this core back into the bootrom, where it waits for a new entry point.
+    ///
+    /// Once reset, this checks that the core answers the bootrom's launch handshake,
+    /// confirming that it is parked. [`spawn`](Self::spawn) can then be used to run
+    /// a new task on it.
+    ///
+    /// The same caveats as for `spawn` apply: resetting a core that is inside a critical
+    /// section or holding a spinlock will likely lead to deadlocks.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        if let Some((psm, _, fifo)) = self.inner.as_mut() {
+            reset_core1(psm);
+
+            // While waiting for an entry point, the bootrom echoes back every value it
+            // receives. A `0` restarts the launch sequence, so it is harmless to send.
+            for _ in 0..HANDSHAKE_RETRIES {
+                fifo.drain();
+                cortex_m::asm::sev();
+                fifo.write_blocking(0);
+                if read_with_timeout(fifo) == Some(0) {
+                    return Ok(());
+                }
+            }
+            Err(Error::Unresponsive)
+        } else {
+            Err(Error::InvalidCore)
+        }
+    }
+
     /// Spawn a function on this core.
     ///
     /// The closure should not return. It is currently defined as `-> ()` because `-> !` is not yet
@@ -151,6 +287,8 @@ impl<'p> Core<'p> {
     ///
     /// Core 1 will be reset from core 0 in order to spawn another task.
     ///
+    /// A [stack guard](crate::stack_guard) is installed by core 1 at the bottom of `stack`.
+    ///
     /// Resetting a single core of a running program can have undesired consequences. Deadlocks are
     /// likely if the core being reset happens to be inside a critical section.
     /// It may even break safety assumptions of some unsafe code. So, be careful when calling this method
@@ -185,6 +323,7 @@ impl<'p> Core<'p> {
                 sio.fifo.write_blocking(1);
 
                 entry();
+                CORE1_RUNNING.store(false, Ordering::Relaxed);
                 loop {
                     cortex_m::asm::wfe()
                 }
@@ -195,11 +334,7 @@ impl<'p> Core<'p> {
             // But there does not seem to be any obvious way to check that. A marker flag could be
             // set from this method and cleared for the wrapper after `entry` returned. But doing
             // so wouldn't be zero cost.
-            psm.frce_off.modify(|_, w| w.proc1().set_bit());
-            while !psm.frce_off.read().proc1().bit_is_set() {
-                cortex_m::asm::nop();
-            }
-            psm.frce_off.modify(|_, w| w.proc1().clear_bit());
+            reset_core1(psm);
 
             // Set up the stack
             // AAPCS requires in 6.2.1.2 that the stack is 8bytes aligned., we may need to trim the
@@ -237,42 +372,16 @@ impl<'p> Core<'p> {
 
             let vector_table = ppb.vtor.read().bits();
 
-            // After reset, core 1 is waiting to receive commands over FIFO.
-            // This is the sequence to have it jump to some code.
-            let cmd_seq = [
-                0,
-                0,
-                1,
-                vector_table as usize,
-                stack_ptr as usize,
-                core1_startup::<F> as usize,
-            ];
-
-            let mut seq = 0;
-            let mut fails = 0;
-            loop {
-                let cmd = cmd_seq[seq] as u32;
-                if cmd == 0 {
-                    fifo.drain();
-                    cortex_m::asm::sev();
-                }
-                fifo.write_blocking(cmd);
-                let response = fifo.read_blocking();
-                if cmd == response {
-                    seq += 1;
-                } else {
-                    seq = 0;
-                    fails += 1;
-                    if fails > 16 {
-                        // The second core isn't responding, and isn't going to take the entrypoint,
-                        // so we have to drop it ourselves.
-                        drop(ManuallyDrop::into_inner(entry));
-                        return Err(Error::Unresponsive);
-                    }
-                }
-                if seq >= cmd_seq.len() {
-                    break;
-                }
+            // Set before launching, as the task may return before core 0 gets to run again.
+            CORE1_RUNNING.store(true, Ordering::Relaxed);
+            if let Err(e) =
+                unsafe { launch(fifo, vector_table, stack_ptr, core1_startup::<F> as usize) }
+            {
+                // The second core isn't responding, and isn't going to take the entrypoint,
+                // so we have to drop it ourselves.
+                CORE1_RUNNING.store(false, Ordering::Relaxed);
+                drop(ManuallyDrop::into_inner(entry));
+                return Err(e);
             }
 
             // Wait until the other core has copied `entry` before returning.
@@ -283,4 +392,292 @@ impl<'p> Core<'p> {
             Err(Error::InvalidCore)
         }
     }
+
+    /// Spawn a function on this core, running entirely from RAM.
+    ///
+    /// Unlike [`spawn`](Self::spawn), the core does not execute any code from flash to start
+    /// `entry`, so it can keep running while the flash is being erased or programmed. This
+    /// checks that `entry`, `stack` and `vector_table` are all located in SRAM, and returns
+    /// [`Error::NotInRam`] otherwise. `entry` is typically declared with
+    /// [`#[ram_function]`](crate::ram_function), and must only call RAM functions itself.
+    ///
+    /// The vector table is used by the core for its interrupts, so any enabled interrupt
+    /// handler must be placed in RAM as well. No stack guard is installed.
+    ///
+    /// The same caveats as for `spawn` apply regarding the reset of the core.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{pac, sio::Sio, multicore::{Multicore, Stack}, vector_table::VectorTable};
+    ///
+    /// static mut CORE1_STACK: Stack<1024> = Stack::new();
+    /// static mut CORE1_VECTORS: VectorTable = VectorTable::new();
+    ///
+    /// #[rp2040_hal::ram_function]
+    /// fn core1_task() -> ! {
+    ///     loop {}
+    /// }
+    ///
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let mut sio = Sio::new(pac.SIO);
+    /// let vector_table = unsafe {
+    ///     CORE1_VECTORS.init(&mut pac.PPB);
+    ///     &CORE1_VECTORS
+    /// };
+    /// let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
+    /// let cores = mc.cores();
+    /// let _ = cores[1].spawn_in_ram(unsafe { &mut CORE1_STACK.mem }, vector_table, core1_task);
+    /// ```
+    pub fn spawn_in_ram(
+        &mut self,
+        stack: &'static mut [usize],
+        vector_table: &'static crate::vector_table::VectorTable,
+        entry: fn() -> !,
+    ) -> Result<(), Error> {
+        // Runs on core 1, with the entry point pushed on its stack by core 0.
+        #[crate::ram_function]
+        extern "C" fn core1_ram_startup(_: u64, _: u64, entry: usize) -> ! {
+            let entry = unsafe { core::mem::transmute::<usize, fn() -> !>(entry) };
+            entry()
+        }
+        let startup = core1_ram_startup as extern "C" fn(u64, u64, usize) -> ! as usize;
+
+        if let Some((psm, _, fifo)) = self.inner.as_mut() {
+            let stack_range = stack.as_ptr_range();
+            if !crate::is_in_ram(entry as usize)
+                || !crate::is_in_ram(startup)
+                || !crate::is_in_ram(stack_range.start as usize)
+                || !crate::is_in_ram(stack_range.end as usize - 1)
+                || !crate::is_in_ram(vector_table as *const _ as usize)
+            {
+                return Err(Error::NotInRam);
+            }
+
+            reset_core1(psm);
+
+            // Keep the stack 8 bytes aligned, as in `spawn`.
+            let mut stack_ptr = stack.as_mut_ptr_range().end;
+            let misalignement_offset = stack_ptr.align_offset(8);
+            unsafe {
+                stack_ptr = stack_ptr.sub(misalignement_offset);
+                // Push `entry`, and one padding word to keep the alignment.
+                stack_ptr = stack_ptr.sub(2);
+                stack_ptr.write(entry as usize);
+            }
+            compiler_fence(Ordering::Release);
+
+            CORE1_RUNNING.store(true, Ordering::Relaxed);
+            let result =
+                unsafe { launch(fifo, vector_table as *const _ as u32, stack_ptr, startup) };
+            CORE1_RUNNING.store(result.is_ok(), Ordering::Relaxed);
+            result
+        } else {
+            Err(Error::InvalidCore)
+        }
+    }
+
+    /// Pause this core.
+    ///
+    /// Asks the core to stop executing code from flash: once paused, it waits in a loop located
+    /// in RAM with interrupts disabled until [`resume`](Self::resume) is called. This makes it
+    /// safe to erase or program the flash while the core is running a task.
+    ///
+    /// The core must cooperate by passing the messages it receives on its FIFO through
+    /// [`handle_pause_request`], usually from its `SIO_IRQ_PROC1` interrupt handler. The FIFO
+    /// must not be used for other messages while the pause handshake is in progress.
+    pub fn pause(&mut self) -> Result<(), Error> {
+        self.request(PAUSE_REQUEST)
+    }
+
+    /// Resume this core after it has been paused with [`pause`](Self::pause).
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.request(RESUME_REQUEST)
+    }
+
+    fn request(&mut self, request: u32) -> Result<(), Error> {
+        if let Some((_, _, fifo)) = self.inner.as_mut() {
+            fifo.write_blocking(request);
+            match read_with_timeout(fifo) {
+                Some(response) if response == request => Ok(()),
+                _ => Err(Error::Unresponsive),
+            }
+        } else {
+            Err(Error::InvalidCore)
+        }
+    }
+}
+
+/// Sent by [`Core::pause`], and echoed back once the core is paused.
+const PAUSE_REQUEST: u32 = 0x7061_7573;
+/// Sent by [`Core::resume`], and echoed back once the core is about to resume.
+const RESUME_REQUEST: u32 = 0x7265_736d;
+
+/// Handle a pause request sent by [`Core::pause`].
+///
+/// This must be called on the core to be paused, with that core's FIFO, whenever it has data
+/// to read, typically from the `SIO_IRQ_PROC1` interrupt handler. If the next value in the
+/// FIFO is a pause request, this does not return until the core is resumed. Any other value
+/// is read from the FIFO and returned, so that it can be processed as usual.
+///
+/// ```no_run
+/// use rp2040_hal::{multicore, pac, sio::SioFifo};
+///
+/// fn on_fifo_interrupt(fifo: &mut SioFifo) {
+///     while let Some(message) = multicore::handle_pause_request(fifo) {
+///         // process `message`
+///     }
+/// }
+/// ```
+pub fn handle_pause_request(fifo: &mut crate::sio::SioFifo) -> Option<u32> {
+    match fifo.read() {
+        Some(PAUSE_REQUEST) => {
+            cortex_m::interrupt::free(|_| unsafe { pause_in_ram() });
+            None
+        }
+        other => other,
+    }
+}
+
+/// Acknowledge a pause request and wait for a resume request.
+///
+/// The core waits for events with `SLEEPDEEP` set, so that it does not prevent the chip from
+/// entering SLEEP mode, e.g. in [`power::deep_sleep`](crate::power::deep_sleep).
+///
+/// This runs from RAM and is written in assembly: even trivial helpers like `read_volatile`
+/// may not be inlined in debug builds, and would then be called from flash while the other
+/// core is erasing or programming it.
+///
+/// # Safety
+///
+/// Interrupts must be disabled, as interrupt handlers would most likely run from flash.
+#[crate::ram_function]
+unsafe fn pause_in_ram() {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        // r0 = SIO_BASE
+        "movs r0, #0xd0",
+        "lsls r0, r0, #24",
+        // r5 = SCB SCR. Set SLEEPDEEP, so that the chip can enter SLEEP mode while this core
+        // waits for events.
+        "movs r5, #0xe0",
+        "lsls r5, r5, #24",
+        "movs r1, #0xed",
+        "lsls r1, r1, #8",
+        "adds r1, #0x10",
+        "adds r5, r5, r1",
+        "ldr r1, [r5]",
+        "movs r2, #4",
+        "orrs r1, r2",
+        "str r1, [r5]",
+        // Wait for room in the FIFO (FIFO_ST.RDY), then acknowledge the pause request.
+        "2:",
+        "ldr r1, [r0, #0x50]",
+        "movs r2, #2",
+        "tst r1, r2",
+        "beq 2b",
+        "str r3, [r0, #0x54]",
+        // Wait for data in the FIFO (FIFO_ST.VLD), until the resume request arrives. The other
+        // core sends an event after writing to the FIFO.
+        "3:",
+        "ldr r1, [r0, #0x50]",
+        "movs r2, #1",
+        "tst r1, r2",
+        "bne 5f",
+        "wfe",
+        "b 3b",
+        "5:",
+        "ldr r1, [r0, #0x58]",
+        "cmp r1, r4",
+        "bne 3b",
+        // Clear SLEEPDEEP.
+        "ldr r1, [r5]",
+        "movs r2, #4",
+        "bics r1, r2",
+        "str r1, [r5]",
+        // Acknowledge the resume request.
+        "4:",
+        "ldr r1, [r0, #0x50]",
+        "movs r2, #2",
+        "tst r1, r2",
+        "beq 4b",
+        "str r4, [r0, #0x54]",
+        in("r3") PAUSE_REQUEST,
+        in("r4") RESUME_REQUEST,
+        out("r0") _,
+        out("r1") _,
+        out("r2") _,
+        out("r5") _,
+        options(nostack),
+    );
+}
+
+/// A value with one instance per core.
+///
+/// Each core only ever accesses its own instance, selected by reading the SIO CPUID register.
+/// This is useful for per-core state such as loggers, schedulers or counters, which would
+/// otherwise need a spinlock or critical section to be shared safely between the cores.
+///
+/// ```no_run
+/// use rp2040_hal::multicore::CoreLocal;
+///
+/// static EVENTS: CoreLocal<u32> = CoreLocal::new(0, 0);
+///
+/// fn count_event() {
+///     EVENTS.with(|count| *count += 1);
+/// }
+/// ```
+pub struct CoreLocal<T> {
+    values: [UnsafeCell<T>; 2],
+    borrowed: [AtomicBool; 2],
+}
+
+// Safety: each core only accesses its own value, and accesses on the same core are
+// serialized by disabling interrupts and checking the borrow flag.
+unsafe impl<T: Send> Sync for CoreLocal<T> {}
+
+impl<T> CoreLocal<T> {
+    /// Create a new `CoreLocal` from the initial values for core 0 and core 1.
+    pub const fn new(core0: T, core1: T) -> Self {
+        Self {
+            values: [UnsafeCell::new(core0), UnsafeCell::new(core1)],
+            borrowed: [AtomicBool::new(false), AtomicBool::new(false)],
+        }
+    }
+
+    /// Run `f` with exclusive access to the current core's value.
+    ///
+    /// Interrupts are disabled on the current core while `f` runs, so it should be short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within `f` for the same `CoreLocal`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let core = Sio::core() as usize;
+        cortex_m::interrupt::free(|_| {
+            // Only this core ever touches this flag, and interrupts are disabled, so a
+            // plain load and store is enough.
+            let borrowed = &self.borrowed[core];
+            assert!(
+                !borrowed.load(Ordering::Relaxed),
+                "CoreLocal already borrowed on this core"
+            );
+            borrowed.store(true, Ordering::Relaxed);
+            // Safety: the borrow flag guarantees this is the only reference to this core's value.
+            let result = f(unsafe { &mut *self.values[core].get() });
+            borrowed.store(false, Ordering::Relaxed);
+            result
+        })
+    }
+
+    /// Get a mutable reference to the value of the given core.
+    ///
+    /// This is safe as the mutable borrow statically guarantees that no core is accessing it.
+    pub fn get_mut(&mut self, core: CoreId) -> &mut T {
+        self.values[core as usize].get_mut()
+    }
+
+    /// Consume the `CoreLocal`, returning the values for core 0 and core 1.
+    pub fn into_inner(self) -> (T, T) {
+        let [core0, core1] = self.values;
+        (core0.into_inner(), core1.into_inner())
+    }
 }
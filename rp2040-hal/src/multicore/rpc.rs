@@ -0,0 +1,162 @@
+//! Remote procedure calls between the cores
+//!
+//! One core registers functions taking and returning a `u32` in an [`RpcServer`] and serves
+//! requests from its FIFO. The other core invokes them by index with [`call`], which waits
+//! for the result.
+//!
+//! ```no_run
+//! use rp2040_hal::{multicore::rpc::{self, RpcServer}, pac, sio::Sio};
+//!
+//! const SQUARE: u32 = 0;
+//!
+//! fn square(x: u32) -> u32 {
+//!     x * x
+//! }
+//!
+//! // On core 1
+//! fn core1_task() {
+//!     let pac = unsafe { pac::Peripherals::steal() };
+//!     let mut sio = Sio::new(pac.SIO);
+//!     let mut server = RpcServer::<4>::new();
+//!     server.register(SQUARE, square).unwrap();
+//!     loop {
+//!         server.serve(&mut sio.fifo);
+//!     }
+//! }
+//!
+//! // On core 0, once core 1 is running
+//! # let mut pac = pac::Peripherals::take().unwrap();
+//! # let mut sio = Sio::new(pac.SIO);
+//! let result = rpc::call(&mut sio.fifo, SQUARE, 12);
+//! ```
+//!
+//! Both cores must not use their FIFO for anything else while a call is in progress.
+
+use core::convert::Infallible;
+
+use crate::sio::{Channel, FifoMessage, SioFifo};
+
+/// Errors for remote procedure calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RpcError {
+    /// The other core called an index with no function registered.
+    UnknownFunction,
+    /// [`RpcServer::register`] was called with an index beyond the size of the table.
+    IndexOutOfRange,
+}
+
+/// A function which can be called from the other core.
+pub type RpcFunction = fn(u32) -> u32;
+
+struct Request {
+    function: u32,
+    argument: u32,
+}
+
+impl FifoMessage for Request {
+    const WORDS: usize = 2;
+
+    fn encode(&self, write: &mut dyn FnMut(u32)) {
+        write(self.function);
+        write(self.argument);
+    }
+
+    fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+        let function = read();
+        let argument = read();
+        Self { function, argument }
+    }
+}
+
+impl FifoMessage for Result<u32, RpcError> {
+    const WORDS: usize = 2;
+
+    fn encode(&self, write: &mut dyn FnMut(u32)) {
+        match self {
+            Ok(value) => {
+                write(0);
+                write(*value);
+            }
+            // The server only sends `UnknownFunction`, `IndexOutOfRange` is returned locally
+            // by `register`.
+            Err(_) => {
+                write(1);
+                write(0);
+            }
+        }
+    }
+
+    fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+        let status = read();
+        let value = read();
+        match status {
+            0 => Ok(value),
+            _ => Err(RpcError::UnknownFunction),
+        }
+    }
+}
+
+/// Call a function registered on the other core, and wait for its result.
+pub fn call(fifo: &mut SioFifo, function: u32, argument: u32) -> Result<u32, RpcError> {
+    Channel::new(fifo).send(&Request { function, argument });
+    Channel::new(fifo).recv()
+}
+
+/// A table of functions which can be called by the other core.
+pub struct RpcServer<const N: usize> {
+    functions: [Option<RpcFunction>; N],
+}
+
+impl<const N: usize> RpcServer<N> {
+    /// Create an empty server, with room for `N` functions.
+    pub const fn new() -> Self {
+        Self {
+            functions: [None; N],
+        }
+    }
+
+    /// Register `function` at `index`, replacing any function previously registered there.
+    ///
+    /// Returns [`RpcError::IndexOutOfRange`] if `index` is not less than `N`.
+    pub fn register(&mut self, index: u32, function: RpcFunction) -> Result<(), RpcError> {
+        let slot = self
+            .functions
+            .get_mut(index as usize)
+            .ok_or(RpcError::IndexOutOfRange)?;
+        *slot = Some(function);
+        Ok(())
+    }
+
+    /// Wait for a request from the other core, run the requested function and send back
+    /// its result.
+    pub fn serve(&self, fifo: &mut SioFifo) {
+        let request: Request = Channel::new(fifo).recv();
+        let result = self
+            .functions
+            .get(request.function as usize)
+            .copied()
+            .flatten()
+            .ok_or(RpcError::UnknownFunction)
+            .map(|function| function(request.argument));
+        Channel::new(fifo).send(&result);
+    }
+
+    /// Serve a request if one is pending.
+    ///
+    /// Returns `WouldBlock` if the FIFO is empty. This can be called from the FIFO
+    /// interrupt handler.
+    pub fn try_serve(&self, fifo: &mut SioFifo) -> nb::Result<(), Infallible> {
+        if !fifo.is_read_ready() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.serve(fifo);
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for RpcServer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
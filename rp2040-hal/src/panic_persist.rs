@@ -0,0 +1,128 @@
+//! Panic records surviving a reboot
+//!
+//! [`record`] saves the panic message and its location, truncated to [`MESSAGE_LEN`] bytes,
+//! into a RAM region which is not initialized at startup, and marks it valid with a magic word
+//! in watchdog scratch register 3. After the reboot, [`take`] returns the record so that it can
+//! be logged or sent somewhere. Applications using this module must leave `Scratch3` to it.
+//!
+//! The scratch registers are cleared on power-on, so a record is only found after a reset
+//! which kept the RAM powered, like the one done by [`reset`](crate::reset).
+//!
+//! With the **panic-persist** feature, the HAL provides a panic handler which records the
+//! panic and resets the chip:
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, panic_persist, Watchdog};
+//! let peripherals = pac::Peripherals::take().unwrap();
+//! let mut watchdog = Watchdog::new(peripherals.WATCHDOG);
+//! if let Some(panic) = panic_persist::take(&mut watchdog) {
+//!     // log panic.message()
+//! }
+//! ```
+//!
+//! The record is placed in the `.uninit` section of cortex-m-rt.
+
+use core::{
+    fmt::{self, Write},
+    mem::MaybeUninit,
+    panic::PanicInfo,
+};
+
+use crate::{
+    pac,
+    watchdog::{ScratchRegister, Watchdog},
+};
+
+/// Maximum length of the recorded panic message, in bytes.
+pub const MESSAGE_LEN: usize = 256;
+
+/// Magic word in scratch register 3, marking the record as valid.
+const MAGIC: u32 = 0x7061_6e63;
+
+/// A recorded panic.
+#[derive(Clone)]
+pub struct PanicRecord {
+    len: usize,
+    message: [u8; MESSAGE_LEN],
+}
+
+impl PanicRecord {
+    /// The panic message, including the location of the panic, truncated to [`MESSAGE_LEN`]
+    /// bytes.
+    pub fn message(&self) -> &str {
+        let bytes = &self.message[..self.len.min(MESSAGE_LEN)];
+        match core::str::from_utf8(bytes) {
+            Ok(message) => message,
+            // Truncated in the middle of a character, or garbage.
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Debug for PanicRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicRecord")
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PanicRecord {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "PanicRecord {{ message: {=str} }}", self.message())
+    }
+}
+
+#[link_section = ".uninit.panic_persist"]
+static mut RECORD: MaybeUninit<PanicRecord> = MaybeUninit::uninit();
+
+/// Writes into the record, dropping what does not fit.
+struct Truncating<'a>(&'a mut PanicRecord);
+
+impl Write for Truncating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let record = &mut *self.0;
+        let n = s.len().min(MESSAGE_LEN - record.len);
+        record.message[record.len..record.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        record.len += n;
+        Ok(())
+    }
+}
+
+/// Record a panic, to be retrieved with [`take`] after a reboot.
+///
+/// This is meant to be called from a panic handler, on the core which panicked. A panic of the
+/// other core at the same time may garble the record.
+pub fn record(info: &PanicInfo) {
+    // Safety: the record is only accessed here and in `take`, which cannot run at the same time
+    // as a panic handler of the same core. The watchdog scratch register is reserved for this.
+    unsafe {
+        let record = &mut *core::ptr::addr_of_mut!(RECORD).cast::<PanicRecord>();
+        record.len = 0;
+        let _ = write!(Truncating(record), "{}", info);
+        (*pac::WATCHDOG::ptr()).scratch3.write(|w| w.bits(MAGIC));
+    }
+}
+
+/// Take the panic recorded before the last reboot, if any.
+///
+/// This clears the record, so that it is only returned once.
+pub fn take(watchdog: &mut Watchdog) -> Option<PanicRecord> {
+    if watchdog.read_scratch(ScratchRegister::Scratch3) != MAGIC {
+        return None;
+    }
+    watchdog.write_scratch(ScratchRegister::Scratch3, 0);
+    // Safety: the magic word is only written once the record is complete.
+    let mut record = unsafe { (*core::ptr::addr_of!(RECORD)).assume_init_ref().clone() };
+    record.len = record.len.min(MESSAGE_LEN);
+    Some(record)
+}
+
+#[cfg(all(feature = "panic-persist", target_arch = "arm", target_os = "none"))]
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    record(info);
+    crate::reset()
+}
@@ -0,0 +1,178 @@
+//! HAL-level split of the PAC peripherals
+//!
+//! [`Peripherals`] consumes the [`pac::Peripherals`] and hands out the blocks which need no
+//! further configuration already wrapped in their HAL drivers: the bank0 pins, the SIO
+//! functions, the watchdog and the voltage regulator. Everything else is passed through
+//! unchanged, in particular the blocks needed by
+//! [`init_clocks_and_plls`](crate::clocks::init_clocks_and_plls).
+//!
+//! ```no_run
+//! use rp2040_hal::{clocks::init_clocks_and_plls, Peripherals};
+//!
+//! let mut p = Peripherals::take().unwrap();
+//! const XOSC_CRYSTAL_FREQ: u32 = 12_000_000; // Typically found in BSP crates
+//! let clocks = init_clocks_and_plls(
+//!     XOSC_CRYSTAL_FREQ,
+//!     p.xosc,
+//!     p.clocks,
+//!     p.pll_sys,
+//!     p.pll_usb,
+//!     &mut p.resets,
+//!     &mut p.watchdog,
+//! )
+//! .ok()
+//! .unwrap();
+//! let mut led = p.pins.gpio25.into_push_pull_output();
+//! ```
+
+use crate::{
+    gpio::Pins,
+    pac,
+    resets::Resets,
+    sio::{HwDivider, Interp0, Interp1, Sio, SioFifo, SioGpioQspi},
+    vreg::Vreg,
+    watchdog::Watchdog,
+};
+
+/// The peripherals of the RP2040, split into HAL drivers where possible.
+pub struct Peripherals {
+    /// The bank0 pins, with the bank taken out of reset.
+    pub pins: Pins,
+    /// The SIO token of the QSPI pins, see [`gpio::qspi::Pins`](crate::gpio::qspi::Pins).
+    pub gpio_qspi: SioGpioQspi,
+    /// The inter-core FIFO.
+    pub fifo: SioFifo,
+    /// The hardware divider.
+    pub hwdivider: HwDivider,
+    /// Interpolator 0.
+    pub interp0: Interp0,
+    /// Interpolator 1.
+    pub interp1: Interp1,
+    /// The watchdog.
+    pub watchdog: Watchdog,
+    /// The voltage regulator.
+    pub vreg: Vreg,
+
+    /// The RESETS block, needed to bring up the clocks and the other peripherals.
+    pub resets: Resets,
+    /// The CLOCKS block.
+    pub clocks: pac::CLOCKS,
+    /// The crystal oscillator.
+    pub xosc: pac::XOSC,
+    /// The ring oscillator.
+    pub rosc: pac::ROSC,
+    /// The system PLL.
+    pub pll_sys: pac::PLL_SYS,
+    /// The USB PLL.
+    pub pll_usb: pac::PLL_USB,
+
+    /// The ADC.
+    pub adc: pac::ADC,
+    /// The bus fabric control registers.
+    pub busctrl: pac::BUSCTRL,
+    /// The DMA controller.
+    pub dma: pac::DMA,
+    /// I2C controller 0.
+    pub i2c0: pac::I2C0,
+    /// I2C controller 1.
+    pub i2c1: pac::I2C1,
+    /// The QSPI bank IO registers.
+    pub io_qspi: pac::IO_QSPI,
+    /// The QSPI bank pad registers.
+    pub pads_qspi: pac::PADS_QSPI,
+    /// PIO block 0.
+    pub pio0: pac::PIO0,
+    /// PIO block 1.
+    pub pio1: pac::PIO1,
+    /// The Cortex-M0+ private peripheral bus.
+    pub ppb: pac::PPB,
+    /// The power-on state machine.
+    pub psm: pac::PSM,
+    /// The PWM slices.
+    pub pwm: pac::PWM,
+    /// The real time clock.
+    pub rtc: pac::RTC,
+    /// SPI controller 0.
+    pub spi0: pac::SPI0,
+    /// SPI controller 1.
+    pub spi1: pac::SPI1,
+    /// The system configuration registers.
+    pub syscfg: pac::SYSCFG,
+    /// The system information registers.
+    pub sysinfo: pac::SYSINFO,
+    /// The testbench manager.
+    pub tbman: pac::TBMAN,
+    /// The timer.
+    pub timer: pac::TIMER,
+    /// UART 0.
+    pub uart0: pac::UART0,
+    /// UART 1.
+    pub uart1: pac::UART1,
+    /// The USB controller's DPRAM.
+    pub usbctrl_dpram: pac::USBCTRL_DPRAM,
+    /// The USB controller's registers.
+    pub usbctrl_regs: pac::USBCTRL_REGS,
+    /// The XIP cache control registers.
+    pub xip_ctrl: pac::XIP_CTRL,
+    /// The SSI used for XIP.
+    pub xip_ssi: pac::XIP_SSI,
+}
+
+impl Peripherals {
+    /// Take the PAC peripherals and split them.
+    ///
+    /// Returns `None` if the PAC peripherals were taken before.
+    pub fn take() -> Option<Self> {
+        pac::Peripherals::take().map(Self::new)
+    }
+
+    /// Split the PAC peripherals.
+    pub fn new(p: pac::Peripherals) -> Self {
+        let mut resets = Resets::new(p.RESETS);
+        let sio = Sio::new(p.SIO);
+        let pins = Pins::new(p.IO_BANK0, p.PADS_BANK0, sio.gpio_bank0, &mut resets);
+        Self {
+            pins,
+            gpio_qspi: sio.gpio_qspi,
+            fifo: sio.fifo,
+            hwdivider: sio.hwdivider,
+            interp0: sio.interp0,
+            interp1: sio.interp1,
+            watchdog: Watchdog::new(p.WATCHDOG),
+            vreg: Vreg::new(p.VREG_AND_CHIP_RESET),
+
+            resets,
+            clocks: p.CLOCKS,
+            xosc: p.XOSC,
+            rosc: p.ROSC,
+            pll_sys: p.PLL_SYS,
+            pll_usb: p.PLL_USB,
+
+            adc: p.ADC,
+            busctrl: p.BUSCTRL,
+            dma: p.DMA,
+            i2c0: p.I2C0,
+            i2c1: p.I2C1,
+            io_qspi: p.IO_QSPI,
+            pads_qspi: p.PADS_QSPI,
+            pio0: p.PIO0,
+            pio1: p.PIO1,
+            ppb: p.PPB,
+            psm: p.PSM,
+            pwm: p.PWM,
+            rtc: p.RTC,
+            spi0: p.SPI0,
+            spi1: p.SPI1,
+            syscfg: p.SYSCFG,
+            sysinfo: p.SYSINFO,
+            tbman: p.TBMAN,
+            timer: p.TIMER,
+            uart0: p.UART0,
+            uart1: p.UART1,
+            usbctrl_dpram: p.USBCTRL_DPRAM,
+            usbctrl_regs: p.USBCTRL_REGS,
+            xip_ctrl: p.XIP_CTRL,
+            xip_ssi: p.XIP_SSI,
+        }
+    }
+}
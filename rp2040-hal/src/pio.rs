@@ -9,6 +9,7 @@ use crate::{
     gpio::{Function, FunctionPio0, FunctionPio1},
     pac::{self, dma::ch::ch_ctrl_trig::TREQ_SEL_A, pio0::RegisterBlock, PIO0, PIO1},
     resets::SubsystemReset,
+    rom_data,
     typelevel::Sealed,
 };
 
@@ -193,12 +194,15 @@ impl<P: PIOExt> PIO<P> {
                     Some(origin)
                 }
             } else {
-                for i in (0..=32 - (i.len() as u8)).rev() {
-                    if self.used_instruction_space & (mask << i) == 0 {
-                        return Some(i);
-                    }
+                // Bit n of `fits` is set if the instructions n..n + len are all free.
+                let free = !self.used_instruction_space;
+                let fits = (1..i.len()).fold(free, |fits, n| fits & (free >> n));
+                if fits == 0 {
+                    None
+                } else {
+                    // Use the highest offset, leaving the low addresses to programs with an origin
+                    Some((31 - rom_data::bits::clz32(fits)) as u8)
                 }
-                None
             }
         }
     }
@@ -459,6 +463,7 @@ impl<P: PIOExt, SM: StateMachineIndex> ValidStateMachine for (P, SM) {
 ///
 /// Note the GPIO is able to override/invert that.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinState {
     /// Pin in Low state.
     High,
@@ -470,6 +475,7 @@ pub enum PinState {
 ///
 /// Note the GPIO is able to override/invert that.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinDir {
     /// Pin set as an Input
     Input,
@@ -1791,6 +1797,7 @@ impl<'a, P: PIOExt, const IRQ: usize> Interrupt<'a, P, IRQ> {
 
 /// Provides easy access for decoding PIO's interrupt state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InterruptState(u32);
 
 macro_rules! raw_interrupt_accessor {
@@ -1820,6 +1827,7 @@ impl InterruptState {
 
 /// Comparison used for `mov x, status` instruction.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MovStatusConfig {
     /// The `mov x, status` instruction returns all ones if TX FIFO level is below the set status, otherwise all zeros.
     Tx(u8),
@@ -1829,6 +1837,7 @@ pub enum MovStatusConfig {
 
 /// Shift direction for input and output shifting.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ShiftDirection {
     /// Shift register to left.
     Left,
@@ -1898,6 +1907,7 @@ pub struct PIOBuilder<P> {
 
 /// Buffer sharing configuration.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Buffers {
     /// No sharing.
     RxTx,
@@ -1909,6 +1919,7 @@ pub enum Buffers {
 
 /// Errors that occurred during `PIO::install`.
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InstallError {
     /// There was not enough space for the instructions on the selected PIO.
     NoSpace,
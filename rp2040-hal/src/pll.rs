@@ -4,14 +4,22 @@
 use core::{
     convert::{Infallible, TryInto},
     marker::PhantomData,
-    ops::{Deref, Range, RangeInclusive},
+    ops::{Deref, Range},
 };
 
 use fugit::{HertzU32, RateExtU32};
+use rp_hal_common::pll::{FBDIV_RANGE, MIN_REF_FREQ, POSTDIV_RANGE, VCO_FREQ_RANGE};
 
 use nb::Error::WouldBlock;
 
-use crate::{clocks::ClocksManager, pac::RESETS, resets::SubsystemReset, typelevel::Sealed};
+use crate::{
+    clocks::{Clock, ClocksManager},
+    pac::RESETS,
+    resets::SubsystemReset,
+    typelevel::Sealed,
+};
+
+pub use rp_hal_common::pll::PLLConfig;
 
 /// State of the PLL
 pub trait State: Sealed {}
@@ -37,6 +45,60 @@ pub struct Locked {
     frequency: HertzU32,
 }
 
+impl Disabled {
+    /// Check `config` against the constraints of the PLL, and compute its register values.
+    fn from_config(xosc_frequency: HertzU32, config: PLLConfig) -> Result<Disabled, Error> {
+        let vco_freq = config.vco_freq;
+
+        if !VCO_FREQ_RANGE.contains(&vco_freq) {
+            return Err(Error::VcoFreqOutOfRange);
+        }
+
+        if !POSTDIV_RANGE.contains(&config.post_div1) || !POSTDIV_RANGE.contains(&config.post_div2)
+        {
+            return Err(Error::PostDivOutOfRage);
+        }
+
+        let ref_freq_max_vco = (vco_freq.to_Hz() / 16).Hz();
+        let ref_freq_range: Range<HertzU32> = MIN_REF_FREQ..ref_freq_max_vco;
+
+        let ref_freq_hz: HertzU32 = xosc_frequency
+            .to_Hz()
+            .checked_div(u32::from(config.refdiv))
+            .ok_or(Error::BadArgument)?
+            .Hz();
+
+        if !ref_freq_range.contains(&ref_freq_hz) {
+            return Err(Error::RefFreqOutOfRange);
+        }
+
+        let fbdiv = vco_freq
+            .to_Hz()
+            .checked_div(ref_freq_hz.to_Hz())
+            .ok_or(Error::BadArgument)?;
+
+        let fbdiv: u16 = fbdiv.try_into().map_err(|_| Error::BadArgument)?;
+
+        if !FBDIV_RANGE.contains(&fbdiv) {
+            return Err(Error::FeedbackDivOutOfRange);
+        }
+
+        let refdiv = config.refdiv;
+        let post_div1 = config.post_div1;
+        let post_div2 = config.post_div2;
+        let frequency: HertzU32 =
+            (ref_freq_hz * u32::from(fbdiv)) / (u32::from(post_div1) * u32::from(post_div2));
+
+        Ok(Disabled {
+            refdiv,
+            fbdiv,
+            post_div1,
+            post_div2,
+            frequency,
+        })
+    }
+}
+
 impl State for Disabled {}
 impl Sealed for Disabled {}
 impl State for Locked {}
@@ -96,21 +158,6 @@ pub enum Error {
     BadArgument,
 }
 
-/// Parameters for a PLL.
-pub struct PLLConfig {
-    /// Voltage Controlled Oscillator frequency.
-    pub vco_freq: HertzU32,
-
-    /// Reference divider
-    pub refdiv: u8,
-
-    /// Post Divider 1
-    pub post_div1: u8,
-
-    /// Post Divider 2
-    pub post_div2: u8,
-}
-
 /// Common configs for the two PLLs. Both assume the XOSC is cadenced at 12MHz !
 /// See Chapter 2, Section 18, §2
 pub mod common_configs {
@@ -141,59 +188,8 @@ impl<D: PhaseLockedLoopDevice> PhaseLockedLoop<Disabled, D> {
         xosc_frequency: HertzU32,
         config: PLLConfig,
     ) -> Result<PhaseLockedLoop<Disabled, D>, Error> {
-        const VCO_FREQ_RANGE: RangeInclusive<HertzU32> = HertzU32::MHz(400)..=HertzU32::MHz(1_600);
-        const POSTDIV_RANGE: Range<u8> = 1..7;
-        const FBDIV_RANGE: Range<u16> = 16..320;
-
-        let vco_freq = config.vco_freq;
-
-        if !VCO_FREQ_RANGE.contains(&vco_freq) {
-            return Err(Error::VcoFreqOutOfRange);
-        }
-
-        if !POSTDIV_RANGE.contains(&config.post_div1) || !POSTDIV_RANGE.contains(&config.post_div2)
-        {
-            return Err(Error::PostDivOutOfRage);
-        }
-
-        let ref_freq_max_vco = (vco_freq.to_Hz() / 16).Hz();
-        let ref_freq_range: Range<HertzU32> = HertzU32::MHz(5)..ref_freq_max_vco;
-
-        let ref_freq_hz: HertzU32 = xosc_frequency
-            .to_Hz()
-            .checked_div(u32::from(config.refdiv))
-            .ok_or(Error::BadArgument)?
-            .Hz();
-
-        if !ref_freq_range.contains(&ref_freq_hz) {
-            return Err(Error::RefFreqOutOfRange);
-        }
-
-        let fbdiv = vco_freq
-            .to_Hz()
-            .checked_div(ref_freq_hz.to_Hz())
-            .ok_or(Error::BadArgument)?;
-
-        let fbdiv: u16 = fbdiv.try_into().map_err(|_| Error::BadArgument)?;
-
-        if !FBDIV_RANGE.contains(&fbdiv) {
-            return Err(Error::FeedbackDivOutOfRange);
-        }
-
-        let refdiv = config.refdiv;
-        let post_div1 = config.post_div1;
-        let post_div2 = config.post_div2;
-        let frequency: HertzU32 =
-            (ref_freq_hz * u32::from(fbdiv)) / (u32::from(post_div1) * u32::from(post_div2));
-
         Ok(PhaseLockedLoop {
-            state: Disabled {
-                refdiv,
-                fbdiv,
-                post_div1,
-                post_div2,
-                frequency,
-            },
+            state: Disabled::from_config(xosc_frequency, config)?,
             device: dev,
         })
     }
@@ -280,6 +276,88 @@ impl<D: PhaseLockedLoopDevice> PhaseLockedLoop<Locked, D> {
     }
 }
 
+impl PhaseLockedLoop<Locked, crate::pac::PLL_SYS> {
+    /// Change the frequency of the running system PLL, e.g. to scale clk_sys with the load.
+    ///
+    /// clk_sys is switched glitchlessly to clk_ref while the PLL relocks, then back to the PLL
+    /// at its new frequency. clk_peri follows when it is derived from clk_sys. The new
+    /// frequencies are stored in `clocks`; the drivers which computed dividers from the previous
+    /// ones, like the UART baud rate, have to be set up again.
+    ///
+    /// The other clocks running from the PLL stop while it relocks. Raising the frequency above
+    /// 133 MHz may require raising the core voltage first.
+    ///
+    /// # Errors
+    ///
+    /// If `config` is invalid for `xosc_frequency`, the error is returned before anything is
+    /// changed, so the PLL and clocks are left untouched.
+    ///
+    /// Once the PLL has relocked, clk_sys and clk_peri are switched back to it with a divider
+    /// of 1, which does not fail for any PLL frequency. Should it fail anyway,
+    /// [`Error::BadArgument`] is returned with the PLL running at the new frequency, and:
+    /// - if switching clk_sys fails, clk_sys is left running from clk_ref, as recorded in
+    ///   `clocks`. A clk_peri derived from clk_sys follows it, while `clocks` still records the
+    ///   previous frequency of clk_peri;
+    /// - if switching clk_peri fails, clk_sys runs from the PLL at the new frequency, while
+    ///   `clocks` still records the previous frequency of clk_peri.
+    pub fn reconfigure(
+        &mut self,
+        xosc_frequency: HertzU32,
+        config: PLLConfig,
+        clocks: &mut ClocksManager,
+    ) -> Result<HertzU32, Error> {
+        let state = Disabled::from_config(xosc_frequency, config)?;
+
+        let peri_from_sys = {
+            // Safety: only reads the clk_peri configuration.
+            let clocks = unsafe { &*crate::pac::CLOCKS::ptr() };
+            let ctrl = clocks.clk_peri_ctrl.read();
+            ctrl.enable().bit_is_set() && ctrl.auxsrc().is_clk_sys()
+        };
+
+        // The error type of `reset_source_await` is `Infallible`, and `block!` retries until the
+        // glitchless mux has switched, so this cannot fail.
+        nb::block!(clocks.system_clock.reset_source_await()).unwrap();
+
+        // Power the PLL down and up again, as when initializing it.
+        self.device.pwr.reset();
+        self.device.fbdiv_int.reset();
+        self.device
+            .cs
+            .write(|w| unsafe { w.refdiv().bits(state.refdiv) });
+        self.device
+            .fbdiv_int
+            .write(|w| unsafe { w.fbdiv_int().bits(state.fbdiv) });
+        self.device.pwr.modify(|_, w| {
+            w.pd().clear_bit();
+            w.vcopd().clear_bit();
+            w
+        });
+        while self.device.cs.read().lock().bit_is_clear() {}
+        self.device.prim.write(|w| unsafe {
+            w.postdiv1().bits(state.post_div1);
+            w.postdiv2().bits(state.post_div2);
+            w
+        });
+        self.device.pwr.modify(|_, w| w.postdivpd().clear_bit());
+        self.state.frequency = state.frequency;
+
+        // The requested frequencies equal the source frequencies, so the dividers are 1. See the
+        // documentation above for the state left behind if this fails anyway.
+        clocks
+            .system_clock
+            .configure_clock(self, state.frequency)
+            .map_err(|_| Error::BadArgument)?;
+        if peri_from_sys {
+            clocks
+                .peripheral_clock
+                .configure_clock(&clocks.system_clock, state.frequency)
+                .map_err(|_| Error::BadArgument)?;
+        }
+        Ok(state.frequency)
+    }
+}
+
 /// Blocking helper method to setup the PLL without going through all the steps.
 pub fn setup_pll_blocking<D: PhaseLockedLoopDevice>(
     dev: D,
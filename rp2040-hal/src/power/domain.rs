@@ -0,0 +1,111 @@
+//! Power-on state machine (PSM) domains
+
+use crate::pac;
+
+/// A block sequenced by the power-on state machine.
+///
+/// Every block is brought out of reset in this order at power-on. A block forced off is held
+/// in reset, along with every block after it which depends on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum PowerDomain {
+    /// The ring oscillator.
+    Rosc = 0,
+    /// The crystal oscillator.
+    Xosc = 1,
+    /// The clock generators.
+    Clocks = 2,
+    /// The reset controller.
+    Resets = 3,
+    /// The bus fabric.
+    Busfabric = 4,
+    /// The bootrom.
+    Rom = 5,
+    /// SRAM bank 0.
+    Sram0 = 6,
+    /// SRAM bank 1.
+    Sram1 = 7,
+    /// SRAM bank 2.
+    Sram2 = 8,
+    /// SRAM bank 3.
+    Sram3 = 9,
+    /// SRAM bank 4.
+    Sram4 = 10,
+    /// SRAM bank 5.
+    Sram5 = 11,
+    /// The XIP cache and flash interface.
+    Xip = 12,
+    /// The voltage regulator and chip reset control.
+    VregAndChipReset = 13,
+    /// The single-cycle IO block.
+    Sio = 14,
+    /// Processor core 0.
+    Proc0 = 15,
+    /// Processor core 1.
+    Proc1 = 16,
+}
+
+impl PowerDomain {
+    /// Bit of the domain in the `FRCE_ON`, `FRCE_OFF` and `DONE` registers.
+    pub(crate) const fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Hold a domain in reset until [`release`] is called.
+///
+/// This waits for the domain to be in reset.
+///
+/// # Safety
+///
+/// Nothing may use the domain while it is forced off, including the code running this
+/// function: the running core, the SRAM banks holding its stack and data, the flash interface
+/// when running from flash, and the blocks needed to access them.
+pub unsafe fn force_off(psm: &mut pac::PSM, domain: PowerDomain) {
+    psm.frce_off
+        .modify(|r, w| unsafe { w.bits(r.bits() | domain.mask()) });
+    while is_done(psm, domain) {
+        cortex_m::asm::nop();
+    }
+}
+
+/// Release a domain forced off with [`force_off`].
+///
+/// This waits for the domain to be out of reset again.
+pub fn release(psm: &mut pac::PSM, domain: PowerDomain) {
+    psm.frce_off
+        .modify(|r, w| unsafe { w.bits(r.bits() & !domain.mask()) });
+    while !is_done(psm, domain) {
+        cortex_m::asm::nop();
+    }
+}
+
+/// Force a domain out of reset, or stop forcing it if `force` is false.
+///
+/// A domain forced on is kept out of reset even if the chip is reset through the PSM, e.g. by
+/// the watchdog when it is configured to reset this domain.
+pub fn force_on(psm: &mut pac::PSM, domain: PowerDomain, force: bool) {
+    psm.frce_on.modify(|r, w| unsafe {
+        if force {
+            w.bits(r.bits() | domain.mask())
+        } else {
+            w.bits(r.bits() & !domain.mask())
+        }
+    });
+}
+
+/// Restart a domain, forcing it off and releasing it.
+///
+/// # Safety
+///
+/// See [`force_off`].
+pub unsafe fn restart(psm: &mut pac::PSM, domain: PowerDomain) {
+    force_off(psm, domain);
+    release(psm, domain);
+}
+
+/// Whether a domain is out of reset and ready to be used.
+pub fn is_done(psm: &pac::PSM, domain: PowerDomain) -> bool {
+    psm.done.read().bits() & domain.mask() != 0
+}
@@ -0,0 +1,177 @@
+//! Memory power gating
+
+use super::Error;
+use crate::pac;
+
+/// A memory which can be powered down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum MemoryBank {
+    /// SRAM bank 0, striped with banks 1 to 3 from `0x2000_0000`.
+    Sram0 = 0,
+    /// SRAM bank 1, striped with banks 0, 2 and 3 from `0x2000_0000`.
+    Sram1 = 1,
+    /// SRAM bank 2, striped with banks 0, 1 and 3 from `0x2000_0000`.
+    Sram2 = 2,
+    /// SRAM bank 3, striped with banks 0 to 2 from `0x2000_0000`.
+    Sram3 = 3,
+    /// SRAM bank 4, at `0x2004_0000`.
+    Sram4 = 4,
+    /// SRAM bank 5, at `0x2004_1000`.
+    Sram5 = 5,
+    /// The USB controller's DPRAM.
+    UsbDpram = 6,
+}
+
+impl MemoryBank {
+    /// Bit of the memory in `MEMPOWERDOWN`.
+    const fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Start of the SRAM banks 0 to 3, striped word by word.
+const SRAM_STRIPED_BASE: u32 = 0x2000_0000;
+
+/// Start of SRAM bank 4, followed by SRAM bank 5.
+const SRAM4_BASE: u32 = 0x2004_0000;
+
+/// Size of SRAM banks 4 and 5.
+const SRAM4_5_SIZE: u32 = 0x1000;
+
+/// Start of the non-striped alias of the SRAM banks 0 to 3.
+const SRAM0_BASE: u32 = 0x2100_0000;
+
+/// Size of SRAM banks 0 to 3.
+const SRAM0_3_SIZE: u32 = 0x1_0000;
+
+/// The SRAM banks overlapping `start..end`, as `MEMPOWERDOWN` bits.
+fn banks_in_range(start: u32, end: u32) -> u32 {
+    let overlaps = |base: u32, size: u32| start < base + size && base < end;
+    let mut banks = 0;
+    if overlaps(SRAM_STRIPED_BASE, 4 * SRAM0_3_SIZE) {
+        banks |= 0b1111;
+    }
+    for bank in 0..4 {
+        if overlaps(SRAM0_BASE + bank * SRAM0_3_SIZE, SRAM0_3_SIZE) {
+            banks |= 1 << bank;
+        }
+    }
+    for bank in 4..6 {
+        if overlaps(SRAM4_BASE + (bank - 4) * SRAM4_5_SIZE, SRAM4_5_SIZE) {
+            banks |= 1 << bank;
+        }
+    }
+    banks
+}
+
+/// The range from the start of `.data` to the initial stack pointer, as laid out by
+/// cortex-m-rt.
+#[cfg(target_arch = "arm")]
+fn data_to_stack() -> (u32, u32) {
+    extern "C" {
+        static __sdata: u32;
+        static _stack_start: u32;
+    }
+    // Safety: only the addresses of the linker symbols are taken. This needs `unsafe` before
+    // rust 1.82.
+    #[allow(unused_unsafe)]
+    unsafe {
+        (
+            core::ptr::addr_of!(__sdata) as u32,
+            core::ptr::addr_of!(_stack_start) as u32,
+        )
+    }
+}
+
+/// The RAM of the default memory.x, on targets without the cortex-m-rt linker symbols.
+#[cfg(not(target_arch = "arm"))]
+fn data_to_stack() -> (u32, u32) {
+    (SRAM_STRIPED_BASE, SRAM_STRIPED_BASE + 4 * SRAM0_3_SIZE)
+}
+
+/// The SRAM banks holding the data, heap and stack of the program, as `MEMPOWERDOWN` bits.
+fn banks_in_use() -> u32 {
+    let (start, end) = data_to_stack();
+    banks_in_range(start, end)
+}
+
+/// Power down a memory.
+///
+/// Fails with [`Error::MemoryInUse`] if the memory is an SRAM bank holding the data, heap or
+/// stack of the program, if it is listed in `in_use`, or if the memory is the USB DPRAM and
+/// the USB controller is out of reset. The content of the memory is lost.
+///
+/// Only the range from the start of `.data` to the initial stack pointer is known to the HAL.
+/// With the default memory.x, it covers the striped banks 0 to 3. Banks used otherwise, e.g.
+/// by sections placed in SRAM4 or SRAM5, the stack of core 1, or a bank accessed directly
+/// through a pointer, must be listed in `in_use`:
+///
+/// ```no_run
+/// use rp2040_hal::{pac, power::{power_down_memory, MemoryBank}};
+/// let mut peripherals = pac::Peripherals::take().unwrap();
+/// // The stack of core 1 is placed in SRAM4.
+/// const IN_USE: &[MemoryBank] = &[MemoryBank::Sram4];
+/// for bank in [MemoryBank::Sram4, MemoryBank::Sram5] {
+///     // Only SRAM5 is powered down.
+///     let _ = power_down_memory(&mut peripherals.SYSCFG, bank, IN_USE);
+/// }
+/// ```
+pub fn power_down_memory(
+    syscfg: &mut pac::SYSCFG,
+    bank: MemoryBank,
+    in_use: &[MemoryBank],
+) -> Result<(), Error> {
+    let in_use = in_use.contains(&bank)
+        || match bank {
+            MemoryBank::UsbDpram => {
+                // Safety: only reads the reset state.
+                let resets = unsafe { &*pac::RESETS::ptr() };
+                resets.reset.read().usbctrl().bit_is_clear()
+            }
+            _ => banks_in_use() & bank.mask() != 0,
+        };
+    if in_use {
+        return Err(Error::MemoryInUse);
+    }
+    syscfg
+        .mempowerdown
+        .modify(|r, w| unsafe { w.bits(r.bits() | bank.mask()) });
+    Ok(())
+}
+
+/// Power a memory up again.
+///
+/// Its content is undefined until written.
+pub fn power_up_memory(syscfg: &mut pac::SYSCFG, bank: MemoryBank) {
+    syscfg
+        .mempowerdown
+        .modify(|r, w| unsafe { w.bits(r.bits() & !bank.mask()) });
+}
+
+/// Whether a memory is powered down.
+pub fn is_memory_powered_down(syscfg: &pac::SYSCFG, bank: MemoryBank) -> bool {
+    syscfg.mempowerdown.read().bits() & bank.mask() != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banks_in_range_striped() {
+        // The default memory.x places 256K of RAM in the striped banks.
+        assert_eq!(banks_in_range(0x2000_0000, 0x2004_0000), 0b00_1111);
+        assert_eq!(banks_in_range(0x2000_0000, 0x2000_0004), 0b00_1111);
+        assert_eq!(banks_in_range(0x2000_0000, 0x2004_2000), 0b11_1111);
+    }
+
+    #[test]
+    fn banks_in_range_unstriped() {
+        assert_eq!(banks_in_range(0x2004_0000, 0x2004_1000), 0b01_0000);
+        assert_eq!(banks_in_range(0x2004_0ffc, 0x2004_1004), 0b11_0000);
+        assert_eq!(banks_in_range(0x2101_0000, 0x2102_0000), 0b00_0010);
+        assert_eq!(banks_in_range(0x1000_0000, 0x1000_1000), 0);
+    }
+}
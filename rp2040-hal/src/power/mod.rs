@@ -0,0 +1,951 @@
+//! Low power modes
+//!
+//! The RP2040 has low power modes going further than simply waiting for an interrupt.
+//!
+//! In SLEEP mode, the processors wait for an interrupt while the clocks of every block not
+//! needed to wake them up are gated. [`sleep`] selects the clocks kept running with a
+//! [`SleepClocks`], and restores all of them as soon as an interrupt is pending.
+//! [`sleep_until`] does the same for a set of [`WakeSources`], enabling their interrupts and
+//! keeping only the clocks they need.
+//!
+//! In DORMANT mode, the oscillator running the chip is stopped, and with it every clock. Only
+//! GPIO events, or an RTC alarm if the RTC runs from an external clock, can wake it.
+//! [`dormant`] takes care of the whole sequence: it runs the chip from a single oscillator,
+//! stops the PLLs, arms the wake sources, enters DORMANT and restores the clock tree once
+//! woken up.
+//!
+//! [`deep_sleep`] goes through the whole sequence for a device sleeping between two
+//! measurements, parking core1 and lowering the core voltage as well.
+//!
+//! [`LowPower`] bundles these with a lower core voltage into ready-made profiles for common
+//! use cases.
+//!
+//! The power-on state machine brings every block of the chip out of reset. Its domains can be
+//! forced off or on with [`force_off`], [`release`] and [`force_on`], e.g. to hold an unused
+//! core in reset.
+//!
+//! Unused SRAM banks, and the USB DPRAM when USB is unused, can be powered down with
+//! [`power_down_memory`].
+//!
+//! See [Chapter 2 Section 11](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) of the datasheet for more details
+//!
+//! ## Usage
+//! ```no_run
+//! use rp2040_hal::{clocks::init_clocks_and_plls, gpio::{Interrupt, Pins}, pac, power::{self, DormantOscillator, PowerDomain, SleepClocks, WakeSources}, watchdog::Watchdog, Sio};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut watchdog = Watchdog::new(pac.WATCHDOG);
+//! let mut clocks = init_clocks_and_plls(12_000_000, pac.XOSC, pac.CLOCKS, pac.PLL_SYS, pac.PLL_USB, &mut pac.RESETS, &mut watchdog).ok().unwrap();
+//! let sio = Sio::new(pac.SIO);
+//! let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+//! let button = pins.gpio15.into_pull_up_input();
+//!
+//! // Sleep until the button is pressed.
+//! let wake = WakeSources::new().gpio(button.id(), Interrupt::EdgeLow);
+//! power::dormant(&mut clocks, DormantOscillator::Xosc, &wake).unwrap();
+//!
+//! // Wait for the next timer alarm, with everything else stopped.
+//! let mut core = pac::CorePeripherals::take().unwrap();
+//! power::sleep(&mut clocks, &mut core.SCB, &SleepClocks::new().timer());
+//!
+//! // Core1 is unused: hold it in reset.
+//! unsafe { power::force_off(&mut pac.PSM, PowerDomain::Proc1) };
+//! ```
+
+use cortex_m::peripheral::SCB;
+
+use crate::{
+    clocks::ClocksManager,
+    gpio::{DynBankId, DynPinId, Interrupt},
+    multicore::Core,
+    pac,
+    sio::CoreId,
+    timer::Alarm,
+    vreg::{Voltage, Vreg},
+    Sio,
+};
+
+mod domain;
+mod memory;
+mod profile;
+pub use domain::{force_off, force_on, is_done, release, restart, PowerDomain};
+pub use memory::{is_memory_powered_down, power_down_memory, power_up_memory, MemoryBank};
+pub use profile::LowPower;
+
+/// Value written to the `DORMANT` register of an oscillator to stop it.
+const DORMANT_VALUE: u32 = 0x636f_6d61;
+
+/// `SRC` value of `CLK_REF_CTRL` selecting the ROSC.
+const CLK_REF_SRC_ROSC: u32 = 0;
+
+/// `SRC` value of `CLK_REF_CTRL` selecting the XOSC.
+const CLK_REF_SRC_XOSC: u32 = 2;
+
+/// `SRC` field of `CLK_REF_CTRL`.
+const CLK_REF_SRC_MASK: u32 = 0b11;
+
+/// `SRC` field of `CLK_SYS_CTRL`, 0 selecting `clk_ref`.
+const CLK_SYS_SRC_MASK: u32 = 0b1;
+
+/// `ENABLE` bit of the `CTRL` register of the auxiliary clocks.
+const CLK_ENABLE: u32 = 1 << 11;
+
+/// `AUXSRC` field of `CLK_RTC_CTRL`.
+const CLK_RTC_AUXSRC_MASK: u32 = 0b111 << 5;
+
+/// `AUXSRC` values of `CLK_RTC_CTRL` selecting `clksrc_gpin0` and `clksrc_gpin1`.
+const CLK_RTC_AUXSRC_GPIN: [u32; 2] = [4 << 5, 5 << 5];
+
+/// Bits of `SLEEP_EN0`.
+const CLK_SYS_CLOCKS: u32 = 1;
+const CLK_ADC_ADC: u32 = 1 << 1;
+const CLK_SYS_ADC: u32 = 1 << 2;
+const CLK_SYS_BUSCTRL: u32 = 1 << 3;
+const CLK_SYS_BUSFABRIC: u32 = 1 << 4;
+const CLK_SYS_DMA: u32 = 1 << 5;
+const CLK_SYS_I2C0: u32 = 1 << 6;
+const CLK_SYS_I2C1: u32 = 1 << 7;
+const CLK_SYS_IO: u32 = 1 << 8;
+const CLK_SYS_VREG_AND_CHIP_RESET: u32 = 1 << 10;
+const CLK_SYS_PADS: u32 = 1 << 11;
+const CLK_SYS_PIO0: u32 = 1 << 12;
+const CLK_SYS_PIO1: u32 = 1 << 13;
+const CLK_SYS_PLL_SYS: u32 = 1 << 14;
+const CLK_SYS_PLL_USB: u32 = 1 << 15;
+const CLK_SYS_PSM: u32 = 1 << 16;
+const CLK_SYS_PWM: u32 = 1 << 17;
+const CLK_SYS_RESETS: u32 = 1 << 18;
+const CLK_SYS_ROM: u32 = 1 << 19;
+const CLK_SYS_ROSC: u32 = 1 << 20;
+const CLK_RTC_RTC: u32 = 1 << 21;
+const CLK_SYS_SIO: u32 = 1 << 23;
+const CLK_PERI_SPI0: u32 = 1 << 24;
+const CLK_SYS_SPI0: u32 = 1 << 25;
+const CLK_PERI_SPI1: u32 = 1 << 26;
+const CLK_SYS_SPI1: u32 = 1 << 27;
+const CLK_SYS_SRAM0_3: u32 = 0b1111 << 28;
+
+/// Bits of `SLEEP_EN1`.
+const CLK_SYS_SRAM4_5: u32 = 0b11;
+const CLK_SYS_SYSCFG: u32 = 1 << 2;
+const CLK_SYS_SYSINFO: u32 = 1 << 3;
+const CLK_SYS_TIMER: u32 = 1 << 5;
+const CLK_PERI_UART0: u32 = 1 << 6;
+const CLK_SYS_UART0: u32 = 1 << 7;
+const CLK_PERI_UART1: u32 = 1 << 8;
+const CLK_SYS_UART1: u32 = 1 << 9;
+const CLK_SYS_USBCTRL: u32 = 1 << 10;
+const CLK_USB_USBCTRL: u32 = 1 << 11;
+const CLK_SYS_WATCHDOG: u32 = 1 << 12;
+const CLK_SYS_XIP: u32 = 1 << 13;
+const CLK_SYS_XOSC: u32 = 1 << 14;
+
+/// Every bit of `SLEEP_EN1`.
+const SLEEP_EN1_MASK: u32 = (1 << 15) - 1;
+
+/// `AUXSRC` values of `CLK_RTC_CTRL` selecting `clksrc_pll_usb` and `clksrc_pll_sys`.
+const CLK_RTC_AUXSRC_PLL: [u32; 2] = [0, 1 << 5];
+
+/// `PWR` value of a PLL with every part powered down.
+const PLL_PWR_DOWN: u32 = 0b10_1101;
+
+/// Errors for low power operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// No wake source was given, so the chip would never wake up.
+    NoWakeSource,
+    /// The selected oscillator is not running.
+    OscillatorNotRunning,
+    /// The RTC must run from an external clock on `GPIN0` or `GPIN1` to wake the chip from
+    /// DORMANT, as the oscillators are stopped.
+    RtcNotExternallyClocked,
+    /// A wake source cannot wake the chip from the requested mode, e.g. a timer alarm from
+    /// DORMANT.
+    WakeSourceUnavailable,
+    /// The memory holds live data, and cannot be powered down.
+    MemoryInUse,
+    /// The RTC runs from a PLL, which is stopped while sleeping.
+    RtcClockedFromPll,
+    /// Core1 did not answer a pause or resume request.
+    Core1Unresponsive,
+    /// A GPIO wake source is not a pin of the RP2040.
+    InvalidPin,
+}
+
+/// Oscillator running the chip until, and stopped by, DORMANT mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DormantOscillator {
+    /// The crystal oscillator. It takes about 1 ms to restart.
+    Xosc,
+    /// The ring oscillator, which restarts immediately. The XOSC, if enabled, keeps running.
+    Rosc,
+}
+
+/// Events which wake the chip from SLEEP or DORMANT mode.
+///
+/// The interrupts of the selected sources are enabled while sleeping, and disabled again after
+/// waking up if they were not enabled before. Events stay latched as usual, e.g. a timer alarm
+/// must still be cleared after waking up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WakeSources {
+    /// Events for the bank 0 GPIOs, in the layout of `DORMANT_WAKE_INTE`.
+    bank0: [u32; 4],
+    /// Events for the QSPI GPIOs, in the layout of `DORMANT_WAKE_INTE`.
+    qspi: u32,
+    rtc: bool,
+    /// Timer alarms, in the layout of the timer's `INTE`.
+    timer: u32,
+    /// A GPIO wake source was given for a pin which does not exist.
+    invalid_pin: bool,
+}
+
+impl WakeSources {
+    /// No wake source.
+    pub const fn new() -> Self {
+        Self {
+            bank0: [0; 4],
+            qspi: 0,
+            rtc: false,
+            timer: 0,
+            invalid_pin: false,
+        }
+    }
+
+    /// Wake on the given event on a GPIO, in SLEEP or DORMANT mode.
+    ///
+    /// The pin must be configured as an input, for example with
+    /// [`into_pull_up_input`](crate::gpio::Pin::into_pull_up_input).
+    ///
+    /// A pin which does not exist, e.g. bank 0 pin 30, makes entering the low power mode fail
+    /// with [`Error::InvalidPin`].
+    pub fn gpio(mut self, pin: DynPinId, interrupt: Interrupt) -> Self {
+        let bits = interrupt.mask() << (pin.num % 8 * 4);
+        match (pin.bank, pin.num) {
+            (DynBankId::Bank0, 0..=29) => self.bank0[usize::from(pin.num / 8)] |= bits,
+            (DynBankId::Qspi, 0..=5) => self.qspi |= bits,
+            _ => self.invalid_pin = true,
+        }
+        self
+    }
+
+    /// Wake on an RTC alarm, in SLEEP or DORMANT mode.
+    ///
+    /// To wake from DORMANT mode, the RTC must run from an external clock.
+    pub fn rtc(mut self) -> Self {
+        self.rtc = true;
+        self
+    }
+
+    /// Wake on a timer alarm, in SLEEP mode only.
+    ///
+    /// The timer is stopped in DORMANT mode, along with every internal clock.
+    pub fn timer_alarm(mut self, alarm: &impl Alarm) -> Self {
+        self.timer |= 1 << (alarm.interrupt() as u16 - pac::Interrupt::TIMER_IRQ_0 as u16);
+        self
+    }
+
+    /// The clocks needed by these wake sources in SLEEP mode.
+    pub fn sleep_clocks(&self) -> SleepClocks {
+        let mut clocks = SleepClocks::new();
+        if self.bank0 != [0; 4] || self.qspi != 0 {
+            clocks = clocks.gpio();
+        }
+        if self.rtc {
+            clocks = clocks.rtc();
+        }
+        if self.timer != 0 {
+            clocks = clocks.timer();
+        }
+        clocks
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bank0 == [0; 4] && self.qspi == 0 && !self.rtc && self.timer == 0
+    }
+
+    /// Check these sources are valid and can wake the chip at all.
+    fn check(&self) -> Result<(), Error> {
+        if self.invalid_pin {
+            return Err(Error::InvalidPin);
+        }
+        if self.is_empty() {
+            return Err(Error::NoWakeSource);
+        }
+        Ok(())
+    }
+
+    /// Check these sources can wake the chip from DORMANT mode.
+    fn check_dormant(&self, clocks: &pac::clocks::RegisterBlock) -> Result<(), Error> {
+        self.check()?;
+        if self.timer != 0 {
+            return Err(Error::WakeSourceUnavailable);
+        }
+        let rtc_src = clocks.clk_rtc_ctrl.read().bits() & CLK_RTC_AUXSRC_MASK;
+        if self.rtc && !CLK_RTC_AUXSRC_GPIN.contains(&rtc_src) {
+            return Err(Error::RtcNotExternallyClocked);
+        }
+        Ok(())
+    }
+
+    /// The interrupts of these sources on the current core, and whether each one is used.
+    fn irqs(&self) -> [(pac::Interrupt, bool); 7] {
+        use pac::Interrupt::*;
+        [
+            (IO_IRQ_BANK0, self.bank0 != [0; 4]),
+            (IO_IRQ_QSPI, self.qspi != 0),
+            (RTC_IRQ, self.rtc),
+            (TIMER_IRQ_0, self.timer & 1 != 0),
+            (TIMER_IRQ_1, self.timer & 1 << 1 != 0),
+            (TIMER_IRQ_2, self.timer & 1 << 2 != 0),
+            (TIMER_IRQ_3, self.timer & 1 << 3 != 0),
+        ]
+    }
+
+    /// Enable the interrupts of these sources on the current core.
+    ///
+    /// Returns the interrupts which were not enabled yet, to be disabled with `disarm_sleep`.
+    fn arm_sleep(&self) -> ArmedInterrupts {
+        let mut added = WakeSources::new();
+        let mut nvic = 0;
+        // Safety: only the bits of the requested sources are touched, atomically. The GPIO
+        // enables of the current core are only changed by this core.
+        unsafe {
+            let (bank0_inte, qspi_inte) = gpio_proc_inte();
+            let bank0 = &*pac::IO_BANK0::ptr();
+            let qspi = &*pac::IO_QSPI::ptr();
+            for (i, reg) in bank0_inte.into_iter().enumerate() {
+                added.bank0[i] = self.bank0[i] & !reg.read_volatile();
+                set_bits(reg, added.bank0[i], true);
+                bank0.intr[i].write(|w| w.bits(self.bank0[i]));
+            }
+            added.qspi = self.qspi & !qspi_inte.read_volatile();
+            set_bits(qspi_inte, added.qspi, true);
+            qspi.intr.write(|w| w.bits(self.qspi));
+
+            let rtc = &*pac::RTC::ptr();
+            added.rtc = self.rtc && rtc.inte.read().rtc().bit_is_clear();
+            set_bits(rtc.inte.as_ptr(), added.rtc as u32, true);
+
+            let timer = &*pac::TIMER::ptr();
+            added.timer = self.timer & !timer.inte.read().bits();
+            set_bits(timer.inte.as_ptr(), added.timer, true);
+
+            for (irq, used) in self.irqs() {
+                if used && !pac::NVIC::is_enabled(irq) {
+                    nvic |= 1 << irq as u16;
+                    pac::NVIC::unmask(irq);
+                }
+            }
+        }
+        ArmedInterrupts { added, nvic }
+    }
+
+    /// Enable the DORMANT wake events of these sources.
+    ///
+    /// Returns the interrupts which were not enabled yet, to be disabled with `disarm_dormant`.
+    fn arm_dormant(&self) -> ArmedInterrupts {
+        let mut added = WakeSources::new();
+        self.set_gpio_dormant_enabled(true);
+        // Safety: only the bit of the RTC alarm is set, atomically.
+        unsafe {
+            let rtc = &*pac::RTC::ptr();
+            added.rtc = self.rtc && rtc.inte.read().rtc().bit_is_clear();
+            set_bits(rtc.inte.as_ptr(), added.rtc as u32, true);
+        }
+        ArmedInterrupts { added, nvic: 0 }
+    }
+
+    /// Disable the DORMANT wake events enabled by `arm_dormant`.
+    fn disarm_dormant(&self, armed: ArmedInterrupts) {
+        self.set_gpio_dormant_enabled(false);
+        armed.disarm();
+    }
+
+    /// Enable the GPIO DORMANT wake events, or disable them if `enable` is false.
+    fn set_gpio_dormant_enabled(&self, enable: bool) {
+        // Safety: only the bits of the requested pins are touched, atomically.
+        unsafe {
+            let bank0 = &*pac::IO_BANK0::ptr();
+            for (reg, bits) in bank0.dormant_wake_inte.iter().zip(self.bank0) {
+                set_bits(reg.as_ptr(), bits, enable);
+            }
+            let qspi = &*pac::IO_QSPI::ptr();
+            set_bits(qspi.dormant_wake_inte.as_ptr(), self.qspi, enable);
+            // Clear latched edges, which would otherwise wake the chip immediately. They are
+            // left latched after waking up, to tell which pin woke the chip.
+            if enable {
+                for (reg, bits) in bank0.intr.iter().zip(self.bank0) {
+                    reg.write(|w| w.bits(bits));
+                }
+                qspi.intr.write(|w| w.bits(self.qspi));
+            }
+        }
+    }
+}
+
+/// Interrupts enabled to wake up by [`WakeSources`].
+struct ArmedInterrupts {
+    added: WakeSources,
+    /// Interrupts unmasked in the NVIC, one bit per interrupt number.
+    nvic: u32,
+}
+
+impl ArmedInterrupts {
+    /// Disable the interrupts enabled to wake up.
+    fn disarm(self) {
+        // Safety: only the bits set when arming are cleared, atomically.
+        unsafe {
+            let (bank0_inte, qspi_inte) = gpio_proc_inte();
+            for (reg, bits) in bank0_inte.into_iter().zip(self.added.bank0) {
+                set_bits(reg, bits, false);
+            }
+            set_bits(qspi_inte, self.added.qspi, false);
+            let rtc = &*pac::RTC::ptr();
+            set_bits(rtc.inte.as_ptr(), self.added.rtc as u32, false);
+            let timer = &*pac::TIMER::ptr();
+            set_bits(timer.inte.as_ptr(), self.added.timer, false);
+        }
+        for (irq, _) in self.added.irqs() {
+            if self.nvic & 1 << irq as u16 != 0 {
+                pac::NVIC::mask(irq);
+            }
+        }
+    }
+}
+
+/// The GPIO interrupt enable registers of the current core, for bank 0 and the QSPI bank.
+fn gpio_proc_inte() -> ([*mut u32; 4], *mut u32) {
+    // Safety: only the addresses of the registers are taken.
+    let (bank0, qspi) = unsafe { (&*pac::IO_BANK0::ptr(), &*pac::IO_QSPI::ptr()) };
+    match Sio::core() {
+        CoreId::Core0 => (
+            core::array::from_fn(|i| bank0.proc0_inte[i].as_ptr()),
+            qspi.proc0_inte.as_ptr(),
+        ),
+        CoreId::Core1 => (
+            core::array::from_fn(|i| bank0.proc1_inte[i].as_ptr()),
+            qspi.proc1_inte.as_ptr(),
+        ),
+    }
+}
+
+/// Atomically set or clear `bits` in the register at `reg`.
+unsafe fn set_bits(reg: *mut u32, bits: u32, set: bool) {
+    if set {
+        crate::atomic_register_access::write_bitmask_set(reg, bits);
+    } else {
+        crate::atomic_register_access::write_bitmask_clear(reg, bits);
+    }
+}
+
+/// Clocks kept running in SLEEP mode.
+///
+/// Each block has a system clock for its registers and bus interface, and some have another
+/// clock driving their logic, e.g. `clk_peri` for the UARTs. The helpers below keep all the
+/// clocks a block needs to raise its interrupt.
+///
+/// The same set selects the clocks running while awake, see
+/// [`ClocksManager::configure_wake_enable`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SleepClocks {
+    /// Bits of `SLEEP_EN0`.
+    en0: u32,
+    /// Bits of `SLEEP_EN1`.
+    en1: u32,
+}
+
+impl SleepClocks {
+    /// Stop every clock.
+    ///
+    /// This includes the processor clocks, which also stops SysTick, so nothing can wake the
+    /// cores until the clocks of the wake sources are added, e.g. from
+    /// [`WakeSources::sleep_clocks`].
+    pub const fn new() -> Self {
+        Self { en0: 0, en1: 0 }
+    }
+
+    /// Keep every clock running, which is the same as a plain `wfi`.
+    pub const fn all() -> Self {
+        Self {
+            en0: u32::MAX,
+            en1: SLEEP_EN1_MASK,
+        }
+    }
+
+    /// Keep the clocks given in the layout of the `SLEEP_EN0` and `SLEEP_EN1` registers.
+    ///
+    /// Bits not matching a clock are ignored.
+    pub const fn from_bits(en0: u32, en1: u32) -> Self {
+        Self {
+            en0,
+            en1: en1 & SLEEP_EN1_MASK,
+        }
+    }
+
+    /// The clocks kept running, in the layout of the `SLEEP_EN0` and `SLEEP_EN1` registers.
+    pub const fn bits(&self) -> (u32, u32) {
+        (self.en0, self.en1)
+    }
+
+    const fn with(self, en0: u32, en1: u32) -> Self {
+        Self {
+            en0: self.en0 | en0,
+            en1: self.en1 | en1,
+        }
+    }
+
+    /// Keep the RTC running, to wake on an alarm.
+    pub const fn rtc(self) -> Self {
+        self.with(CLK_RTC_RTC, 0)
+    }
+
+    /// Keep the timer running, to wake on an alarm.
+    ///
+    /// The timer tick is generated by the watchdog, which is kept running as well.
+    pub const fn timer(self) -> Self {
+        self.with(0, CLK_SYS_TIMER | CLK_SYS_WATCHDOG)
+    }
+
+    /// Keep the watchdog running.
+    pub const fn watchdog(self) -> Self {
+        self.with(0, CLK_SYS_WATCHDOG)
+    }
+
+    /// Keep the GPIO banks running, to wake on a GPIO interrupt.
+    pub const fn gpio(self) -> Self {
+        self.with(CLK_SYS_IO | CLK_SYS_PADS, 0)
+    }
+
+    /// Keep UART0 running, e.g. to wake on received data.
+    pub const fn uart0(self) -> Self {
+        self.with(0, CLK_PERI_UART0 | CLK_SYS_UART0)
+    }
+
+    /// Keep UART1 running, e.g. to wake on received data.
+    pub const fn uart1(self) -> Self {
+        self.with(0, CLK_PERI_UART1 | CLK_SYS_UART1)
+    }
+
+    /// Keep SPI0 running.
+    pub const fn spi0(self) -> Self {
+        self.with(CLK_PERI_SPI0 | CLK_SYS_SPI0, 0)
+    }
+
+    /// Keep SPI1 running.
+    pub const fn spi1(self) -> Self {
+        self.with(CLK_PERI_SPI1 | CLK_SYS_SPI1, 0)
+    }
+
+    /// Keep I2C0 running.
+    pub const fn i2c0(self) -> Self {
+        self.with(CLK_SYS_I2C0, 0)
+    }
+
+    /// Keep I2C1 running.
+    pub const fn i2c1(self) -> Self {
+        self.with(CLK_SYS_I2C1, 0)
+    }
+
+    /// Keep the PWM slices running.
+    pub const fn pwm(self) -> Self {
+        self.with(CLK_SYS_PWM, 0)
+    }
+
+    /// Keep PIO0 running.
+    pub const fn pio0(self) -> Self {
+        self.with(CLK_SYS_PIO0, 0)
+    }
+
+    /// Keep PIO1 running.
+    pub const fn pio1(self) -> Self {
+        self.with(CLK_SYS_PIO1, 0)
+    }
+
+    /// Keep the ADC running.
+    pub const fn adc(self) -> Self {
+        self.with(CLK_ADC_ADC | CLK_SYS_ADC, 0)
+    }
+
+    /// Keep the USB controller running, e.g. to wake on bus activity.
+    pub const fn usb(self) -> Self {
+        self.with(0, CLK_SYS_USBCTRL | CLK_USB_USBCTRL)
+    }
+
+    /// Keep the clocks the processors need to run code: the bus fabric, the memories, the
+    /// SIO and the CLOCKS block itself.
+    pub const fn core(self) -> Self {
+        self.with(
+            CLK_SYS_CLOCKS
+                | CLK_SYS_BUSCTRL
+                | CLK_SYS_BUSFABRIC
+                | CLK_SYS_ROM
+                | CLK_SYS_SIO
+                | CLK_SYS_SRAM0_3,
+            CLK_SYS_SRAM4_5 | CLK_SYS_XIP,
+        )
+    }
+
+    /// Keep the clocks of the system control blocks: the resets, the power-on state machine,
+    /// the PLLs, the oscillators, the voltage regulator, SYSCFG, SYSINFO, the watchdog and the
+    /// GPIO banks.
+    ///
+    /// The HAL accesses their registers while running, e.g. to reset a peripheral or to change
+    /// the clock tree, and the watchdog generates the timer tick.
+    pub const fn system(self) -> Self {
+        self.with(
+            CLK_SYS_IO
+                | CLK_SYS_PADS
+                | CLK_SYS_PLL_SYS
+                | CLK_SYS_PLL_USB
+                | CLK_SYS_PSM
+                | CLK_SYS_RESETS
+                | CLK_SYS_ROSC
+                | CLK_SYS_VREG_AND_CHIP_RESET,
+            CLK_SYS_SYSCFG | CLK_SYS_SYSINFO | CLK_SYS_WATCHDOG | CLK_SYS_XOSC,
+        )
+    }
+
+    /// Keep the DMA running, along with the bus fabric and all SRAM banks it transfers from
+    /// and to.
+    pub const fn dma(self) -> Self {
+        self.with(
+            CLK_SYS_DMA | CLK_SYS_BUSFABRIC | CLK_SYS_SRAM0_3,
+            CLK_SYS_SRAM4_5,
+        )
+    }
+}
+
+/// Clock tree configuration saved before entering a low power mode.
+struct ClockState {
+    ref_ctrl: u32,
+    ref_div: u32,
+    sys_ctrl: u32,
+    sys_div: u32,
+    /// `CTRL` of `clk_peri`, `clk_usb`, `clk_adc` and `clk_rtc`.
+    aux_ctrl: [u32; 4],
+    /// `DIV` of `clk_usb`, `clk_adc` and `clk_rtc`.
+    aux_div: [u32; 3],
+    /// `PWR` of `pll_sys` and `pll_usb`.
+    pll_pwr: [u32; 2],
+}
+
+impl ClockState {
+    fn save(clocks: &pac::clocks::RegisterBlock) -> Self {
+        Self {
+            ref_ctrl: clocks.clk_ref_ctrl.read().bits(),
+            ref_div: clocks.clk_ref_div.read().bits(),
+            sys_ctrl: clocks.clk_sys_ctrl.read().bits(),
+            sys_div: clocks.clk_sys_div.read().bits(),
+            aux_ctrl: [
+                clocks.clk_peri_ctrl.read().bits(),
+                clocks.clk_usb_ctrl.read().bits(),
+                clocks.clk_adc_ctrl.read().bits(),
+                clocks.clk_rtc_ctrl.read().bits(),
+            ],
+            aux_div: [
+                clocks.clk_usb_div.read().bits(),
+                clocks.clk_adc_div.read().bits(),
+                clocks.clk_rtc_div.read().bits(),
+            ],
+            pll_pwr: plls().map(|pll| pll.pwr.read().bits()),
+        }
+    }
+
+    /// Run the whole chip from `clk_ref` driven by the given oscillator source, stopping the
+    /// auxiliary clocks (except `clk_rtc` if `keep_rtc`) and the PLLs.
+    fn reduce(&self, clocks: &pac::clocks::RegisterBlock, ref_src: u32, keep_rtc: bool) {
+        unsafe {
+            clocks
+                .clk_sys_ctrl
+                .write(|w| w.bits(self.sys_ctrl & !CLK_SYS_SRC_MASK));
+            while clocks.clk_sys_selected.read().bits() != 1 {}
+            clocks
+                .clk_ref_ctrl
+                .write(|w| w.bits(self.ref_ctrl & !CLK_REF_SRC_MASK | ref_src));
+            while clocks.clk_ref_selected.read().bits() != 1 << ref_src {}
+            clocks.clk_ref_div.write(|w| w.bits(1 << 8));
+
+            clocks
+                .clk_peri_ctrl
+                .write(|w| w.bits(self.aux_ctrl[0] & !CLK_ENABLE));
+            clocks
+                .clk_usb_ctrl
+                .write(|w| w.bits(self.aux_ctrl[1] & !CLK_ENABLE));
+            clocks
+                .clk_adc_ctrl
+                .write(|w| w.bits(self.aux_ctrl[2] & !CLK_ENABLE));
+            if !keep_rtc {
+                clocks
+                    .clk_rtc_ctrl
+                    .write(|w| w.bits(self.aux_ctrl[3] & !CLK_ENABLE));
+            }
+
+            for pll in plls() {
+                pll.pwr.write(|w| w.bits(PLL_PWR_DOWN));
+            }
+        }
+    }
+
+    /// Restore the PLLs and the clock tree.
+    fn restore(&self, clocks: &pac::clocks::RegisterBlock) {
+        unsafe {
+            for (pll, pwr) in plls().into_iter().zip(self.pll_pwr) {
+                // Power up the VCO first and wait for it to lock, then the post dividers.
+                pll.pwr.write(|w| w.bits(pwr | 1 << 3));
+                if pwr & 1 == 0 {
+                    while pll.cs.read().lock().bit_is_clear() {}
+                }
+                pll.pwr.write(|w| w.bits(pwr));
+            }
+
+            clocks.clk_ref_div.write(|w| w.bits(self.ref_div));
+            // Change the aux source while it is not selected, then switch to it.
+            let current_src = clocks.clk_ref_ctrl.read().bits() & CLK_REF_SRC_MASK;
+            clocks
+                .clk_ref_ctrl
+                .write(|w| w.bits(self.ref_ctrl & !CLK_REF_SRC_MASK | current_src));
+            clocks.clk_ref_ctrl.write(|w| w.bits(self.ref_ctrl));
+            let ref_src = self.ref_ctrl & CLK_REF_SRC_MASK;
+            while clocks.clk_ref_selected.read().bits() != 1 << ref_src {}
+
+            clocks.clk_sys_div.write(|w| w.bits(self.sys_div));
+            clocks
+                .clk_sys_ctrl
+                .write(|w| w.bits(self.sys_ctrl & !CLK_SYS_SRC_MASK));
+            clocks.clk_sys_ctrl.write(|w| w.bits(self.sys_ctrl));
+            let sys_src = self.sys_ctrl & CLK_SYS_SRC_MASK;
+            while clocks.clk_sys_selected.read().bits() != 1 << sys_src {}
+
+            clocks.clk_usb_div.write(|w| w.bits(self.aux_div[0]));
+            clocks.clk_adc_div.write(|w| w.bits(self.aux_div[1]));
+            clocks.clk_rtc_div.write(|w| w.bits(self.aux_div[2]));
+            clocks.clk_peri_ctrl.write(|w| w.bits(self.aux_ctrl[0]));
+            clocks.clk_usb_ctrl.write(|w| w.bits(self.aux_ctrl[1]));
+            clocks.clk_adc_ctrl.write(|w| w.bits(self.aux_ctrl[2]));
+            clocks.clk_rtc_ctrl.write(|w| w.bits(self.aux_ctrl[3]));
+        }
+    }
+}
+
+/// The register blocks of `pll_sys` and `pll_usb`.
+fn plls() -> [&'static pac::pll_sys::RegisterBlock; 2] {
+    // Safety: the PLLs are only reconfigured while the clocks are borrowed mutably, and
+    // restored to their previous state before returning.
+    unsafe { [&*pac::PLL_SYS::ptr(), &*pac::PLL_USB::ptr()] }
+}
+
+/// Enter DORMANT mode until one of the wake sources triggers.
+///
+/// The chip is switched to run from `oscillator` alone, the PLLs and auxiliary clocks are
+/// stopped, then the oscillator itself is stopped. Once woken up, the PLLs and clock tree are
+/// restored to their previous configuration before returning. Interrupts are disabled for the
+/// whole sequence, so pending interrupts are handled after this returns.
+///
+/// The watchdog tick and timer are driven by `clk_ref`, so they stop while dormant.
+pub fn dormant(
+    clocks: &mut ClocksManager,
+    oscillator: DormantOscillator,
+    wake: &WakeSources,
+) -> Result<(), Error> {
+    enter_dormant(clocks, oscillator, wake, None)
+}
+
+/// Enter DORMANT mode, lowering the core voltage to `voltage.1` while the clocks are reduced.
+///
+/// `_clocks` is only borrowed, so that nothing else can reconfigure the clock tree meanwhile.
+fn enter_dormant(
+    _clocks: &mut ClocksManager,
+    oscillator: DormantOscillator,
+    wake: &WakeSources,
+    mut voltage: Option<(&mut Vreg, Voltage)>,
+) -> Result<(), Error> {
+    // Safety: the clocks are borrowed mutably by the callers, and restored before returning.
+    // The oscillators are only stopped if they are running, and restart on wake up.
+    let (clocks, xosc, rosc) =
+        unsafe { (&*pac::CLOCKS::ptr(), &*pac::XOSC::ptr(), &*pac::ROSC::ptr()) };
+    let running = match oscillator {
+        DormantOscillator::Xosc => xosc.status.read().stable().bit_is_set(),
+        DormantOscillator::Rosc => rosc.status.read().stable().bit_is_set(),
+    };
+    if !running {
+        return Err(Error::OscillatorNotRunning);
+    }
+    wake.check_dormant(clocks)?;
+
+    cortex_m::interrupt::free(|_| {
+        let state = ClockState::save(clocks);
+        let armed = wake.arm_dormant();
+        let ref_src = match oscillator {
+            DormantOscillator::Xosc => CLK_REF_SRC_XOSC,
+            DormantOscillator::Rosc => CLK_REF_SRC_ROSC,
+        };
+        state.reduce(clocks, ref_src, wake.rtc);
+        // The chip now runs from an oscillator, slow enough for any voltage.
+        let previous_voltage = lower_voltage(&mut voltage);
+        match oscillator {
+            DormantOscillator::Xosc => {
+                xosc.dormant.write(|w| unsafe { w.bits(DORMANT_VALUE) });
+                while xosc.status.read().stable().bit_is_clear() {}
+            }
+            DormantOscillator::Rosc => {
+                rosc.dormant.write(|w| unsafe { w.bits(DORMANT_VALUE) });
+                while rosc.status.read().stable().bit_is_clear() {}
+            }
+        }
+        restore_voltage(voltage, previous_voltage);
+        wake.disarm_dormant(armed);
+        state.restore(clocks);
+    });
+    Ok(())
+}
+
+/// Enter SLEEP mode until an interrupt is pending.
+///
+/// Only the clocks in `keep` run while sleeping. The processor's own clock is gated as well,
+/// and every clock is running again when this returns. Interrupts are disabled while
+/// sleeping, so the interrupt waking the chip is handled after this returns, with all clocks
+/// running.
+///
+/// The clocks are only gated once both cores are sleeping. Core1 sleeps while waiting in the
+/// bootrom; otherwise, it should be sleeping too, e.g. by calling this function.
+pub fn sleep(_clocks: &mut ClocksManager, scb: &mut SCB, keep: &SleepClocks) {
+    enter_sleep(scb, keep);
+}
+
+/// Enter SLEEP mode with only the clocks in `keep` running.
+fn enter_sleep(scb: &mut SCB, keep: &SleepClocks) {
+    // Safety: the clocks are borrowed mutably by the callers, and `SLEEP_EN` only applies
+    // while sleeping.
+    let clocks = unsafe { &*pac::CLOCKS::ptr() };
+    cortex_m::interrupt::free(|_| {
+        let en0 = clocks.sleep_en0.read().bits();
+        let en1 = clocks.sleep_en1.read().bits();
+        clocks.sleep_en0.write(|w| unsafe { w.bits(keep.en0) });
+        clocks.sleep_en1.write(|w| unsafe { w.bits(keep.en1) });
+
+        scb.set_sleepdeep();
+        // A pending interrupt wakes the processor even while masked.
+        cortex_m::asm::wfi();
+        scb.clear_sleepdeep();
+
+        clocks.sleep_en0.write(|w| unsafe { w.bits(en0) });
+        clocks.sleep_en1.write(|w| unsafe { w.bits(en1) });
+    });
+}
+
+/// Enter SLEEP mode until one of the wake sources triggers.
+///
+/// The interrupts of the wake sources are enabled on the current core and only the clocks they
+/// need are kept running, see [`WakeSources::sleep_clocks`]. Once woken up, the interrupts which
+/// were not enabled before are disabled again, and the wake event is left pending. Like
+/// [`sleep`], other enabled interrupts wake the chip as well.
+pub fn sleep_until(
+    clocks: &mut ClocksManager,
+    scb: &mut SCB,
+    wake: &WakeSources,
+) -> Result<(), Error> {
+    wake.check()?;
+    let keep = wake.sleep_clocks();
+    cortex_m::interrupt::free(|_| {
+        let armed = wake.arm_sleep();
+        sleep(clocks, scb, &keep);
+        armed.disarm();
+    });
+    Ok(())
+}
+
+/// Lower the core voltage, returning the previous one to be restored with `restore_voltage`.
+///
+/// The voltage is left unchanged if it is currently below the supported range.
+fn lower_voltage(voltage: &mut Option<(&mut Vreg, Voltage)>) -> Option<Voltage> {
+    let (vreg, low) = voltage.as_mut()?;
+    let previous = vreg.voltage()?;
+    // Safety: the callers run the chip from an oscillator, slow enough for any voltage.
+    unsafe { vreg.set_voltage_unchecked(*low) };
+    Some(previous)
+}
+
+/// Restore the core voltage lowered by `lower_voltage`.
+fn restore_voltage(voltage: Option<(&mut Vreg, Voltage)>, previous: Option<Voltage>) {
+    if let (Some((vreg, _)), Some(previous)) = (voltage, previous) {
+        // Safety: this is the voltage the chip was running at before.
+        unsafe { vreg.set_voltage_unchecked(previous) };
+    }
+}
+
+/// Sleep as deeply as the wake sources allow, with core1 parked and the voltage lowered.
+///
+/// This covers the common case of a device sleeping between two measurements:
+/// - `core1`, if given, is paused with [`Core::pause`] for the whole sequence, and resumed
+///   afterwards. It must handle pause requests with
+///   [`handle_pause_request`](crate::multicore::handle_pause_request). Leave it out if core1
+///   is not running, as it then waits in the bootrom.
+/// - The chip runs from the XOSC alone, with the PLLs and auxiliary clocks stopped, as with
+///   [`dormant`].
+/// - The core voltage is lowered to `voltage.1` if given, and restored when waking up.
+/// - Without a timer alarm in `wake`, the chip enters DORMANT mode. Otherwise, or with an RTC
+///   alarm and the RTC running from an internal oscillator, it enters SLEEP mode with only the
+///   clocks needed by `wake` running. The timer then keeps counting microseconds if `clk_ref`
+///   is the undivided XOSC, as set by
+///   [`init_clocks_and_plls`](crate::clocks::init_clocks_and_plls).
+///
+/// The clock tree is restored before returning.
+pub fn deep_sleep(
+    clocks: &mut ClocksManager,
+    scb: &mut SCB,
+    core1: Option<&mut Core<'_>>,
+    voltage: Option<(&mut Vreg, Voltage)>,
+    wake: &WakeSources,
+) -> Result<(), Error> {
+    wake.check()?;
+    let core1 = match core1 {
+        Some(core1) => {
+            core1.pause().map_err(|_| Error::Core1Unresponsive)?;
+            Some(core1)
+        }
+        None => None,
+    };
+    // Safety: only reads the RTC clock source
+    let rtc_src = unsafe { &*pac::CLOCKS::ptr() }.clk_rtc_ctrl.read().bits() & CLK_RTC_AUXSRC_MASK;
+    let rtc_dormant = !wake.rtc || CLK_RTC_AUXSRC_GPIN.contains(&rtc_src);
+    let result = if wake.timer == 0 && rtc_dormant {
+        enter_dormant(clocks, DormantOscillator::Xosc, wake, voltage)
+    } else {
+        enter_reduced_sleep(scb, wake, voltage)
+    };
+    if let Some(core1) = core1 {
+        core1.resume().map_err(|_| Error::Core1Unresponsive)?;
+    }
+    result
+}
+
+/// Enter SLEEP mode running from the XOSC alone, with the PLLs stopped.
+fn enter_reduced_sleep(
+    scb: &mut SCB,
+    wake: &WakeSources,
+    mut voltage: Option<(&mut Vreg, Voltage)>,
+) -> Result<(), Error> {
+    // Safety: the clocks are borrowed mutably by the callers, and restored before returning.
+    let (clocks, xosc) = unsafe { (&*pac::CLOCKS::ptr(), &*pac::XOSC::ptr()) };
+    if xosc.status.read().stable().bit_is_clear() {
+        return Err(Error::OscillatorNotRunning);
+    }
+    let rtc_src = clocks.clk_rtc_ctrl.read().bits() & CLK_RTC_AUXSRC_MASK;
+    if wake.rtc && CLK_RTC_AUXSRC_PLL.contains(&rtc_src) {
+        return Err(Error::RtcClockedFromPll);
+    }
+
+    let keep = wake.sleep_clocks();
+    cortex_m::interrupt::free(|_| {
+        let state = ClockState::save(clocks);
+        state.reduce(clocks, CLK_REF_SRC_XOSC, wake.rtc);
+        let previous_voltage = lower_voltage(&mut voltage);
+        let armed = wake.arm_sleep();
+        enter_sleep(scb, &keep);
+        armed.disarm();
+        restore_voltage(voltage, previous_voltage);
+        state.restore(clocks);
+    });
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Ready-made low power profiles
+
+use cortex_m::peripheral::SCB;
+
+use super::{enter_dormant, sleep_until, DormantOscillator, Error, SleepClocks, WakeSources};
+use crate::{
+    clocks::ClocksManager,
+    gpio::{DynPinId, Interrupt},
+    vreg::{Voltage, Vreg},
+};
+
+/// A ready-made low power configuration.
+///
+/// Each profile bundles the clock tree changes, core voltage and clock gating of a common use
+/// case, and restores everything once woken up. The expected savings of each profile are
+/// given relative to the others, as the absolute current depends on the board, supply and
+/// temperature. They follow from the register state checked by the tests of this module: the
+/// clocks left enabled in `SLEEP_EN0`/`SLEEP_EN1` and the core voltage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowPower {
+    /// DORMANT mode until the pin is high, with the core voltage lowered to 0.85 V.
+    ///
+    /// Every clock and oscillator is stopped, so the chip only draws leakage current, further
+    /// reduced by the lower voltage. This is the lowest power mode short of cutting the supply,
+    /// and draws the least of the profiles: no clock is kept running, and the voltage is 0.85V
+    /// instead of the default 1.10V.
+    /// The XOSC must be running, which it is after
+    /// [`init_clocks_and_plls`](crate::clocks::init_clocks_and_plls).
+    DormantUntilPinHigh(DynPinId),
+    /// DORMANT mode until the pin is low, with the core voltage lowered to 0.85 V.
+    ///
+    /// See [`LowPower::DormantUntilPinHigh`].
+    DormantUntilPinLow(DynPinId),
+    /// SLEEP mode until an RTC alarm, with only the RTC clock running.
+    ///
+    /// The processors, bus fabric, SRAM and every other block have their clocks gated, only
+    /// `clk_rtc_rtc` is left enabled. This saves most of the dynamic power of a busy wait or a
+    /// WFI with the default clock gating, but draws clearly more than the DORMANT profiles:
+    /// the oscillators and PLLs keep running, so the chip is back to full speed as soon as it
+    /// wakes up, and the core voltage is left unchanged.
+    SleepWithRtc,
+}
+
+impl LowPower {
+    /// The events waking the chip up from this profile.
+    pub fn wake_sources(&self) -> WakeSources {
+        match *self {
+            LowPower::DormantUntilPinHigh(pin) => {
+                WakeSources::new().gpio(pin, Interrupt::LevelHigh)
+            }
+            LowPower::DormantUntilPinLow(pin) => WakeSources::new().gpio(pin, Interrupt::LevelLow),
+            LowPower::SleepWithRtc => WakeSources::new().rtc(),
+        }
+    }
+
+    /// The clocks kept running, for the profiles using SLEEP mode.
+    pub fn sleep_clocks(&self) -> Option<SleepClocks> {
+        match self {
+            LowPower::DormantUntilPinHigh(_) | LowPower::DormantUntilPinLow(_) => None,
+            LowPower::SleepWithRtc => Some(self.wake_sources().sleep_clocks()),
+        }
+    }
+
+    /// The core voltage while in low power mode, if it is lowered.
+    pub fn voltage(&self) -> Option<Voltage> {
+        match self {
+            LowPower::DormantUntilPinHigh(_) | LowPower::DormantUntilPinLow(_) => {
+                Some(Voltage::V0_85)
+            }
+            LowPower::SleepWithRtc => None,
+        }
+    }
+
+    /// Enter low power mode until woken up.
+    ///
+    /// `clocks` is borrowed by every profile, as the clock tree is reduced and restored.
+    /// `scb` is only used by [`LowPower::SleepWithRtc`], and `vreg` by the DORMANT profiles.
+    pub fn enter(
+        &self,
+        clocks: &mut ClocksManager,
+        scb: &mut SCB,
+        vreg: &mut Vreg,
+    ) -> Result<(), Error> {
+        let wake = self.wake_sources();
+        let voltage = self.voltage().map(|voltage| (vreg, voltage));
+        match self {
+            LowPower::DormantUntilPinHigh(_) | LowPower::DormantUntilPinLow(_) => {
+                enter_dormant(clocks, DormantOscillator::Xosc, &wake, voltage)
+            }
+            LowPower::SleepWithRtc => sleep_until(clocks, scb, &wake),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::DynBankId;
+
+    const GPIO15: DynPinId = DynPinId {
+        bank: DynBankId::Bank0,
+        num: 15,
+    };
+
+    #[test]
+    fn dormant_until_pin() {
+        // GPIO15 is the last pin of the second DORMANT_WAKE_INTE register.
+        let high = LowPower::DormantUntilPinHigh(GPIO15);
+        assert_eq!(high.wake_sources().bank0, [0, 0b0010 << 28, 0, 0]);
+        assert_eq!(high.sleep_clocks(), None);
+        assert_eq!(high.voltage(), Some(Voltage::V0_85));
+
+        let low = LowPower::DormantUntilPinLow(GPIO15);
+        assert_eq!(low.wake_sources().bank0, [0, 0b0001 << 28, 0, 0]);
+        assert_eq!(low.voltage(), Some(Voltage::V0_85));
+    }
+
+    #[test]
+    fn sleep_with_rtc() {
+        let profile = LowPower::SleepWithRtc;
+        assert!(profile.wake_sources().rtc);
+        // Only clk_rtc_rtc in SLEEP_EN0, nothing in SLEEP_EN1.
+        assert_eq!(
+            profile.sleep_clocks().map(|clocks| clocks.bits()),
+            Some((1 << 21, 0))
+        );
+        assert_eq!(profile.voltage(), None);
+    }
+}
@@ -2,6 +2,7 @@
 
 /// Value-level `struct` representing slice IDs
 #[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DynSliceId {
     /// Slice id
     pub num: u8,
@@ -9,6 +10,7 @@ pub struct DynSliceId {
 
 /// Slice modes
 #[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DynSliceMode {
     /// Count continuously whenever the slice is enabled
     FreeRunning,
@@ -22,6 +24,7 @@ pub enum DynSliceMode {
 
 /// Channel ids
 #[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DynChannelId {
     /// Channel A
     A,
@@ -72,6 +72,28 @@
 //!
 //! ```
 //!
+//! Each slice can raise the shared `PWM_IRQ_WRAP` interrupt when its counter wraps. Changes
+//! to the duty cycle and TOP only take effect at the next wrap, so the interrupt is the place to
+//! update them once per period, and it can also serve as a periodic tick:
+//!
+//! ```no_run
+//! # use rp2040_hal::{prelude::*, pwm::Slices};
+//! # let mut pac = rp2040_pac::Peripherals::take().unwrap();
+//! # let pwm_slices = Slices::new(pac.PWM, &mut pac.RESETS);
+//! let mut pwm = pwm_slices.pwm4;
+//! pwm.enable_interrupt();
+//! pwm.enable();
+//! // Then, in the PWM_IRQ_WRAP handler:
+//! if pwm.has_overflown() {
+//!     pwm.clear_interrupt();
+//!     // update the duty cycle for the next period...
+//! }
+//! ```
+//!
+//! The input modes can also be used to measure an external signal on the B pin, with
+//! [`Slice::measure_duty_cycle`] in [`InputHighRunning`] mode or [`Slice::measure_frequency`] in
+//! [`CountRisingEdge`] mode.
+//!
 //! default_config() sets ph_correct to false, the clock divider to 1, does not invert the output, sets top to 65535, and resets the counter.
 //! min_config() leaves those registers in the state they were before it was called (Careful, this can lead to unexpected behavior)
 //! It's recommended to only call min_config() after calling default_config() on a pin that shares a PWM block.
@@ -79,7 +101,8 @@
 use core::marker::PhantomData;
 
 use embedded_dma::Word;
-use embedded_hal::PwmPin;
+use embedded_hal::{blocking::delay::DelayUs, PwmPin};
+use fugit::{HertzU32, MicrosDurationU32};
 
 use crate::{
     atomic_register_access::{write_bitmask_clear, write_bitmask_set},
@@ -347,21 +370,65 @@ where
 
     /// Advance the phase with one count
     ///
-    /// Counter must be running at less than full speed (div_int + div_frac / 16 > 1)
+    /// Blocks until the counter was advanced, and returns `true`. The counter must be running
+    /// in [`FreeRunning`] mode at less than full speed (div_int + div_frac / 16 > 1), so this
+    /// does nothing and returns `false` if the slice is disabled, gated or counting edges, or
+    /// if its clock is not divided, e.g. after [`Slice::default_config`]. The phase of a
+    /// disabled slice can be set with [`Slice::set_counter`].
     #[inline]
-    pub fn advance_phase(&mut self) {
+    pub fn advance_phase(&mut self) -> bool {
         self.regs.advance_phase()
     }
 
     /// Retard the phase with one count
     ///
-    /// Counter must be running at less than full speed (div_int + div_frac / 16 > 1)
+    /// Blocks until the counter was retarded, and returns `true`. Like
+    /// [`Slice::advance_phase`], this does nothing and returns `false` if the slice is disabled
+    /// or not in [`FreeRunning`] mode, but it also works at full speed.
     #[inline]
-    pub fn retard_phase(&mut self) {
+    pub fn retard_phase(&mut self) -> bool {
         self.regs.retard_phase()
     }
 
+    /// Shift the phase of a running slice by `counts`
+    ///
+    /// Positive values advance the phase, negative values retard it, one count at a time.
+    /// This can be used to shift slices relative to each other, e.g. after starting them with
+    /// [`Slices::enable_simultaneously`].
+    ///
+    /// Returns `false` without shifting the phase if the slice is disabled or not in
+    /// [`FreeRunning`] mode, or when advancing at full speed, see [`Slice::advance_phase`]:
+    ///
+    /// ```no_run
+    /// # use rp2040_hal::pwm::Slices;
+    /// # let mut pac = rp2040_pac::Peripherals::take().unwrap();
+    /// # let pwm_slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    /// let mut pwm = pwm_slices.pwm4;
+    /// pwm.default_config();
+    /// pwm.enable();
+    /// // The clock is not divided, so the phase can't be shifted
+    /// assert!(!pwm.shift_phase(100));
+    /// pwm.set_div_int(2);
+    /// assert!(pwm.shift_phase(100));
+    /// ```
+    pub fn shift_phase(&mut self, counts: i32) -> bool {
+        for _ in 0..counts.unsigned_abs() {
+            let shifted = if counts > 0 {
+                self.regs.advance_phase()
+            } else {
+                self.regs.retard_phase()
+            };
+            if !shifted {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Enable phase correct mode
+    ///
+    /// The counter counts up to TOP and then back down to 0, instead of wrapping to 0. This
+    /// produces center-aligned pulses, and halves the output frequency.
     #[inline]
     pub fn set_ph_correct(&mut self) {
         self.regs.write_ph_correct(true)
@@ -373,6 +440,12 @@ where
         self.regs.write_ph_correct(false)
     }
 
+    /// Returns `true` if phase correct mode is enabled
+    #[inline]
+    pub fn is_ph_correct(&self) -> bool {
+        self.regs.read_ph_correct()
+    }
+
     /// Enable slice
     #[inline]
     pub fn enable(&mut self) {
@@ -458,6 +531,16 @@ where
         unsafe { (*pac::PWM::ptr()).intr.read().bits() & mask == mask }
     }
 
+    /// Is the interrupt of this slice pending?
+    ///
+    /// Unlike [`Slice::has_overflown`], this takes the enable and force bits into account, so
+    /// it tells whether this slice is asserting PWM_IRQ_WRAP.
+    #[inline]
+    pub fn is_interrupt_pending(&self) -> bool {
+        let mask = self.bitmask();
+        unsafe { (*pac::PWM::ptr()).ints.read().bits() & mask == mask }
+    }
+
     /// Mark the interrupt handled for this slice.
     #[inline]
     pub fn clear_interrupt(&mut self) {
@@ -554,17 +637,45 @@ impl Slices {
         self._pwm
     }
 
-    //     /// Enable multiple slices at the same time to make their counters sync up.
-    //     ///
-    //     /// You still need to call `slice` to get an actual slice
-    //     pub fn enable_simultaneous<S: SliceId>(&mut self, bits: u8) {
-    //         // Enable all slices at the same time
-    //         unsafe {
-    //             &(*pac::PWM::ptr())
-    //                 .en
-    //                 .modify(|r, w| w.bits(((r.bits() as u8) | bits) as u32));
-    //         }
-    //     }
+    /// Enable several slices on the same clock cycle, so that their counters stay in sync.
+    ///
+    /// Takes a tuple of slices, like `(&mut pwm0, &mut pwm1)`. The slices should be disabled,
+    /// with their counters set to the desired phases (e.g. all to 0), as a running slice just
+    /// keeps running. The slices must have the same clock divider and TOP to stay in sync.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{pac, pwm::Slices};
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    /// let mut pwm0 = slices.pwm0;
+    /// let mut pwm1 = slices.pwm1;
+    /// pwm0.default_config();
+    /// pwm1.default_config();
+    /// // Start pwm1 a quarter period later
+    /// pwm1.set_counter(0xffff - 0x4000);
+    /// Slices::enable_simultaneously((&mut pwm0, &mut pwm1));
+    /// ```
+    pub fn enable_simultaneously<T: Into<SliceMask>>(slices: T) {
+        let SliceMask(mask) = slices.into();
+        // Safety: the atomic set alias only affects the slices in `mask`, which are owned by
+        // the caller.
+        unsafe {
+            let pwm = &(*pac::PWM::ptr());
+            write_bitmask_set(pwm.en.as_ptr(), u32::from(mask));
+        }
+    }
+
+    /// Disable several slices on the same clock cycle.
+    ///
+    /// See [`Slices::enable_simultaneously`].
+    pub fn disable_simultaneously<T: Into<SliceMask>>(slices: T) {
+        let SliceMask(mask) = slices.into();
+        // Safety: see `enable_simultaneously`
+        unsafe {
+            let pwm = &(*pac::PWM::ptr());
+            write_bitmask_clear(pwm.en.as_ptr(), u32::from(mask));
+        }
+    }
 
     // /// Get pwm slice based on gpio pin
     // pub fn borrow_mut_from_pin<
@@ -588,6 +699,28 @@ impl Slices {
     // }
 }
 
+/// Set of slices for [`Slices::enable_simultaneously`], built from a tuple of slices.
+pub struct SliceMask(u8);
+
+macro_rules! slice_mask {
+    ($($S:ident),+) => {
+        impl<$($S: AnySlice),+> From<($(&mut $S,)+)> for SliceMask {
+            fn from(_: ($(&mut $S,)+)) -> Self {
+                Self(0 $(| 1 << <$S::Id as SliceId>::DYN.num)+)
+            }
+        }
+    };
+}
+
+slice_mask!(S0);
+slice_mask!(S0, S1);
+slice_mask!(S0, S1, S2);
+slice_mask!(S0, S1, S2, S3);
+slice_mask!(S0, S1, S2, S3, S4);
+slice_mask!(S0, S1, S2, S3, S4, S5);
+slice_mask!(S0, S1, S2, S3, S4, S5, S6);
+slice_mask!(S0, S1, S2, S3, S4, S5, S6, S7);
+
 /// A Channel from the Pwm subsystem.
 ///
 /// Its attached to one of the eight slices and can be an A or B side channel
@@ -693,6 +826,34 @@ impl<S: AnySlice> PwmPin for Channel<S, B> {
     }
 }
 
+#[cfg(feature = "eh1")]
+mod eh1 {
+    use core::convert::Infallible;
+
+    use eh1_0::pwm::{ErrorType, SetDutyCycle};
+    use embedded_hal::PwmPin;
+
+    use super::{AnySlice, Channel, ChannelId};
+
+    impl<S: AnySlice, C: ChannelId> ErrorType for Channel<S, C> {
+        type Error = Infallible;
+    }
+
+    impl<S: AnySlice, C: ChannelId> SetDutyCycle for Channel<S, C>
+    where
+        Self: PwmPin<Duty = u16>,
+    {
+        fn max_duty_cycle(&self) -> u16 {
+            self.get_max_duty()
+        }
+
+        fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+            self.set_duty(duty);
+            Ok(())
+        }
+    }
+}
+
 impl<S: AnySlice> Channel<S, A> {
     /// Capture a gpio pin and use it as pwm output for channel A
     pub fn output_to<P: AnyPin>(&mut self, pin: P) -> Pin<P::Id, FunctionPwm, P::Pull>
@@ -770,6 +931,99 @@ impl<S: SliceId, M: ValidSliceInputMode<S>> Slice<S, M> {
     }
 }
 
+impl<S: SliceId> Slice<S, InputHighRunning> {
+    /// Measure the duty cycle of the signal on the B pin
+    ///
+    /// The counter runs while the input is high, for an interval of `gate`, so the duty cycle is
+    /// the fraction of the interval the counter was running. The result is between 0.0 and 1.0.
+    /// The signal should have many periods per interval, for example a gate of 10ms is enough
+    /// for signals of several kHz.
+    ///
+    /// `sys_clk` is the frequency of the system clock, which drives the counter. The clock
+    /// divider is chosen so that the counter can't wrap, which limits `gate` to
+    /// `255 * 65535 / sys_clk`, i.e. 133ms with the default 125MHz. Returns `None` if the
+    /// counter wrapped during a longer gate.
+    ///
+    /// This overwrites the clock divider, TOP and the counter, clears the interrupt flag of
+    /// the slice, and leaves the slice disabled.
+    ///
+    /// ```no_run
+    /// use fugit::{ExtU32, RateExtU32};
+    /// use rp2040_hal::{gpio::Pins, pac, pwm::{InputHighRunning, Slices}, Sio, Timer};
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let sio = Sio::new(pac.SIO);
+    /// let pins = Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+    /// let mut timer: Timer = todo!();
+    /// let slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    /// let mut pwm = slices.pwm4.into_mode::<InputHighRunning>();
+    /// let _pin = pwm.input_from(pins.gpio25);
+    /// let duty = pwm.measure_duty_cycle(&mut timer, 10.millis(), 125.MHz());
+    /// ```
+    pub fn measure_duty_cycle<D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+        gate: MicrosDurationU32,
+        sys_clk: HertzU32,
+    ) -> Option<f32> {
+        let max_count = u64::from(sys_clk.to_Hz()) * u64::from(gate.to_micros()) / 1_000_000;
+        // Round up, so that the counter can't wrap
+        let div = ((max_count + 0xfffe) / 0xffff).clamp(1, 255);
+        self.regs.write_div_int(div as u8);
+        self.regs.write_div_frac(0);
+        self.clear_interrupt();
+        let count = self.run_counter(delay, gate);
+        if self.has_overflown() {
+            self.clear_interrupt();
+            return None;
+        }
+        if max_count == 0 {
+            return Some(0.0);
+        }
+        Some((count as f32 * div as f32 / max_count as f32).min(1.0))
+    }
+}
+
+impl<S: SliceId> Slice<S, CountRisingEdge> {
+    /// Measure the frequency of the signal on the B pin
+    ///
+    /// This counts the rising edges in an interval of `gate`. The resolution is `1 / gate`,
+    /// e.g. 100Hz for a gate of 10ms. Returns `None` if there were more than 65535 edges,
+    /// which would wrap the counter.
+    ///
+    /// The signal is sampled with the system clock, so its frequency must be below half of it.
+    ///
+    /// This overwrites the clock divider, TOP and the counter, clears the interrupt flag of
+    /// the slice, and leaves the slice disabled.
+    pub fn measure_frequency<D: DelayUs<u32>>(
+        &mut self,
+        delay: &mut D,
+        gate: MicrosDurationU32,
+    ) -> Option<HertzU32> {
+        self.regs.write_div_int(1);
+        self.regs.write_div_frac(0);
+        self.clear_interrupt();
+        let count = self.run_counter(delay, gate);
+        if self.has_overflown() {
+            self.clear_interrupt();
+            return None;
+        }
+        let hz = u64::from(count) * 1_000_000 / u64::from(gate.to_micros().max(1));
+        Some(HertzU32::from_raw(hz as u32))
+    }
+}
+
+impl<S: SliceId, M: ValidSliceInputMode<S>> Slice<S, M> {
+    /// Run the counter from 0 for an interval of `gate`, and return the count.
+    fn run_counter<D: DelayUs<u32>>(&mut self, delay: &mut D, gate: MicrosDurationU32) -> u16 {
+        self.regs.write_top(0xffff);
+        self.regs.write_ctr(0);
+        self.enable();
+        delay.delay_us(gate.to_micros());
+        self.disable();
+        self.regs.read_ctr()
+    }
+}
+
 /// Type representing DMA access to PWM cc register.
 ///
 /// Both channels are accessed together, because of narrow write replication.
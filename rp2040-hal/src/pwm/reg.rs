@@ -20,14 +20,38 @@ pub(super) unsafe trait RegisterInterface {
         unsafe { &(*pac::PWM::ptr()).ch[num] }
     }
 
+    /// Whether the counter is enabled and counting in free-running mode.
     #[inline]
-    fn advance_phase(&mut self) {
-        self.ch().csr.modify(|_, w| w.ph_adv().set_bit())
+    fn is_free_running(&self) -> bool {
+        let csr = self.ch().csr.read();
+        csr.en().bit_is_set() && csr.divmode().is_div()
     }
 
     #[inline]
-    fn retard_phase(&mut self) {
-        self.ch().csr.modify(|_, w| w.ph_ret().set_bit())
+    fn advance_phase(&mut self) -> bool {
+        let div = self.ch().div.read();
+        if !can_advance_phase(self.is_free_running(), div.int().bits(), div.frac().bits()) {
+            return false;
+        }
+        self.ch().csr.modify(|_, w| w.ph_adv().set_bit());
+        while self.ch().csr.read().ph_adv().bit_is_set() {}
+        true
+    }
+
+    #[inline]
+    fn retard_phase(&mut self) -> bool {
+        // PH_RET pauses the counter for one count, which only needs it to be running.
+        if !self.is_free_running() {
+            return false;
+        }
+        self.ch().csr.modify(|_, w| w.ph_ret().set_bit());
+        while self.ch().csr.read().ph_ret().bit_is_set() {}
+        true
+    }
+
+    #[inline]
+    fn read_ph_correct(&self) -> bool {
+        self.ch().csr.read().ph_correct().bit_is_set()
     }
 
     #[inline]
@@ -105,3 +129,25 @@ pub(super) unsafe trait RegisterInterface {
         self.ch().top.read().top().bits()
     }
 }
+
+/// PH_ADV and PH_RET are only cleared by a running counter, which in the gated and edge modes
+/// may never count again. PH_ADV also needs the counter to run at less than full speed. A
+/// `div_int` of 0 divides by 256.
+fn can_advance_phase(free_running: bool, div_int: u8, div_frac: u8) -> bool {
+    free_running && (div_int != 1 || div_frac != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::can_advance_phase;
+
+    #[test]
+    fn phase_advance_needs_divided_clock() {
+        // `Slice::default_config` runs at full speed
+        assert!(!can_advance_phase(true, 1, 0));
+        assert!(!can_advance_phase(false, 2, 0));
+        assert!(can_advance_phase(true, 1, 1));
+        assert!(can_advance_phase(true, 2, 0));
+        assert!(can_advance_phase(true, 0, 0));
+    }
+}
@@ -1,5 +1,21 @@
 //! Subsystem Resets
+//!
+//! [`Resets`] wraps the RESETS block, to put peripherals in and out of reset at runtime, e.g.
+//! to recover an I2C block wedged by a bus fault. It dereferences to the PAC block, so it can be
+//! passed to the drivers taking `&mut pac::RESETS`.
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, resets::{Peripheral, Resets}};
+//! let peripherals = pac::Peripherals::take().unwrap();
+//! let mut resets = Resets::new(peripherals.RESETS);
+//! resets.reset(Peripheral::I2c0);
+//! resets.unreset_wait(Peripheral::I2c0);
+//! ```
 // See [Chapter 2 Section 14](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) for more details
+use core::ops::{Deref, DerefMut};
+
+use crate::pac::RESETS;
+
 mod private {
     pub trait SubsystemReset {
         fn reset_bring_up(&self, resets: &mut crate::pac::RESETS);
@@ -9,43 +25,194 @@ mod private {
 
 pub(crate) use private::SubsystemReset;
 
+/// A peripheral with a reset controlled by the RESETS block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Peripheral {
+    /// The ADC.
+    Adc = 0,
+    /// The bus fabric control registers.
+    Busctrl = 1,
+    /// The DMA controller.
+    Dma = 2,
+    /// I2C controller 0.
+    I2c0 = 3,
+    /// I2C controller 1.
+    I2c1 = 4,
+    /// The bank0 IO registers.
+    IoBank0 = 5,
+    /// The QSPI bank IO registers.
+    IoQspi = 6,
+    /// The JTAG controller.
+    Jtag = 7,
+    /// The bank0 pad registers.
+    PadsBank0 = 8,
+    /// The QSPI bank pad registers.
+    PadsQspi = 9,
+    /// PIO block 0.
+    Pio0 = 10,
+    /// PIO block 1.
+    Pio1 = 11,
+    /// The system PLL.
+    PllSys = 12,
+    /// The USB PLL.
+    PllUsb = 13,
+    /// The PWM slices.
+    Pwm = 14,
+    /// The real time clock.
+    Rtc = 15,
+    /// SPI controller 0.
+    Spi0 = 16,
+    /// SPI controller 1.
+    Spi1 = 17,
+    /// The system configuration registers.
+    Syscfg = 18,
+    /// The system information registers.
+    Sysinfo = 19,
+    /// The testbench manager.
+    Tbman = 20,
+    /// The timer.
+    Timer = 21,
+    /// UART 0.
+    Uart0 = 22,
+    /// UART 1.
+    Uart1 = 23,
+    /// The USB controller.
+    Usbctrl = 24,
+}
+
+impl Peripheral {
+    /// Bit of the peripheral in the RESETS registers.
+    pub const fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// The RESETS bits of the given peripherals.
+fn mask_of(peripherals: &[Peripheral]) -> u32 {
+    peripherals.iter().fold(0, |mask, p| mask | p.mask())
+}
+
+fn reset(resets: &RESETS, mask: u32) {
+    resets
+        .reset
+        .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+}
+
+fn unreset_wait(resets: &RESETS, mask: u32) {
+    resets
+        .reset
+        .modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+    while resets.reset_done.read().bits() & mask != mask {}
+}
+
+/// The RESETS block.
+pub struct Resets {
+    device: RESETS,
+}
+
+impl Resets {
+    /// Create a new [`Resets`]
+    pub fn new(device: RESETS) -> Self {
+        Self { device }
+    }
+
+    /// Releases the RESETS block
+    pub fn free(self) -> RESETS {
+        self.device
+    }
+
+    /// Put a peripheral in reset.
+    ///
+    /// The driver owning the peripheral, if any, must be created again once the peripheral is
+    /// out of reset.
+    pub fn reset(&mut self, peripheral: Peripheral) {
+        reset(&self.device, peripheral.mask());
+    }
+
+    /// Take a peripheral out of reset, and wait until it is ready.
+    pub fn unreset_wait(&mut self, peripheral: Peripheral) {
+        unreset_wait(&self.device, peripheral.mask());
+    }
+
+    /// Put a peripheral in reset and take it out again, returning it to its power-on state.
+    pub fn reset_cycle(&mut self, peripheral: Peripheral) {
+        self.reset(peripheral);
+        self.unreset_wait(peripheral);
+    }
+
+    /// Put several peripherals in reset at once.
+    pub fn reset_all(&mut self, peripherals: &[Peripheral]) {
+        reset(&self.device, mask_of(peripherals));
+    }
+
+    /// Take several peripherals out of reset at once, and wait until they are all ready.
+    pub fn unreset_wait_all(&mut self, peripherals: &[Peripheral]) {
+        unreset_wait(&self.device, mask_of(peripherals));
+    }
+
+    /// Whether the peripheral is held in reset.
+    pub fn is_reset(&self, peripheral: Peripheral) -> bool {
+        self.device.reset.read().bits() & peripheral.mask() != 0
+    }
+
+    /// Whether the peripheral is out of reset and ready.
+    pub fn is_ready(&self, peripheral: Peripheral) -> bool {
+        self.device.reset_done.read().bits() & peripheral.mask() != 0
+    }
+}
+
+impl Deref for Resets {
+    type Target = RESETS;
+
+    fn deref(&self) -> &RESETS {
+        &self.device
+    }
+}
+
+impl DerefMut for Resets {
+    fn deref_mut(&mut self) -> &mut RESETS {
+        &mut self.device
+    }
+}
+
 macro_rules! generate_reset {
-    ($MODULE:ident, $module:ident) => {
+    ($MODULE:ident, $peripheral:ident) => {
         impl SubsystemReset for $crate::pac::$MODULE {
             fn reset_bring_up(&self, resets: &mut $crate::pac::RESETS) {
-                resets.reset.modify(|_, w| w.$module().clear_bit());
-                while resets.reset_done.read().$module().bit_is_clear() {}
+                unreset_wait(resets, Peripheral::$peripheral.mask());
             }
             fn reset_bring_down(&self, resets: &mut $crate::pac::RESETS) {
-                resets.reset.modify(|_, w| w.$module().set_bit());
+                reset(resets, Peripheral::$peripheral.mask());
             }
         }
     };
 }
 
 // In datasheet order
-generate_reset!(USBCTRL_REGS, usbctrl);
-generate_reset!(UART1, uart1);
-generate_reset!(UART0, uart0);
-generate_reset!(TIMER, timer);
-generate_reset!(TBMAN, tbman);
-generate_reset!(SYSINFO, sysinfo);
-generate_reset!(SYSCFG, syscfg);
-generate_reset!(SPI1, spi1);
-generate_reset!(SPI0, spi0);
-generate_reset!(RTC, rtc);
-generate_reset!(PWM, pwm);
-generate_reset!(PLL_USB, pll_usb);
-generate_reset!(PLL_SYS, pll_sys);
-generate_reset!(PIO1, pio1);
-generate_reset!(PIO0, pio0);
-generate_reset!(PADS_QSPI, pads_qspi);
-generate_reset!(PADS_BANK0, pads_bank0);
+generate_reset!(USBCTRL_REGS, Usbctrl);
+generate_reset!(UART1, Uart1);
+generate_reset!(UART0, Uart0);
+generate_reset!(TIMER, Timer);
+generate_reset!(TBMAN, Tbman);
+generate_reset!(SYSINFO, Sysinfo);
+generate_reset!(SYSCFG, Syscfg);
+generate_reset!(SPI1, Spi1);
+generate_reset!(SPI0, Spi0);
+generate_reset!(RTC, Rtc);
+generate_reset!(PWM, Pwm);
+generate_reset!(PLL_USB, PllUsb);
+generate_reset!(PLL_SYS, PllSys);
+generate_reset!(PIO1, Pio1);
+generate_reset!(PIO0, Pio0);
+generate_reset!(PADS_QSPI, PadsQspi);
+generate_reset!(PADS_BANK0, PadsBank0);
 //generate_reset!(JTAG,jtag); // This doesn't seem to have an item in the pac
-generate_reset!(IO_QSPI, io_qspi);
-generate_reset!(IO_BANK0, io_bank0);
-generate_reset!(I2C1, i2c1);
-generate_reset!(I2C0, i2c0);
-generate_reset!(DMA, dma);
-generate_reset!(BUSCTRL, busctrl);
-generate_reset!(ADC, adc);
+generate_reset!(IO_QSPI, IoQspi);
+generate_reset!(IO_BANK0, IoBank0);
+generate_reset!(I2C1, I2c1);
+generate_reset!(I2C0, I2c0);
+generate_reset!(DMA, Dma);
+generate_reset!(BUSCTRL, Busctrl);
+generate_reset!(ADC, Adc);
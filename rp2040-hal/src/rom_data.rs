@@ -24,9 +24,15 @@ const FUNC_TABLE: *const u16 = 0x0000_0014 as _;
 /// Pointer to the public data lookup table.
 const DATA_TABLE: *const u16 = 0x0000_0016 as _;
 
+/// Address of the magic bytes identifying the ROM.
+const MAGIC: *const [u8; 3] = 0x0000_0010 as _;
+
 /// Address of the version number of the ROM.
 const VERSION_NUMBER: *const u8 = 0x0000_0013 as _;
 
+/// Expected value of the magic bytes: `'M'`, `'u'` and the magic version 1.
+const EXPECTED_MAGIC: [u8; 3] = [b'M', b'u', 1];
+
 /// Retrieve rom content from a table using a code.
 fn rom_table_lookup<T>(table: *const u16, tag: RomFnTableCode) -> T {
     unsafe {
@@ -39,6 +45,39 @@ fn rom_table_lookup<T>(table: *const u16, tag: RomFnTableCode) -> T {
     }
 }
 
+/// Check that the bootrom carries the expected magic bytes.
+///
+/// The lookup functions rely on the table pointers at fixed ROM addresses, which are only
+/// meaningful if this returns `true`. This is always the case on a real RP2040, but can be
+/// used as a sanity check by code which may run on other targets or in emulators.
+pub fn rom_magic_valid() -> bool {
+    unsafe { core::ptr::read_volatile(MAGIC) == EXPECTED_MAGIC }
+}
+
+/// Look up a function in the bootrom function table.
+///
+/// Returns `None` if the ROM magic is not valid, or the ROM does not provide the function.
+/// The returned pointer must be transmuted to the correct function type before calling it;
+/// prefer the typed wrappers in this module where they exist.
+pub fn rom_func_lookup(tag: RomFnTableCode) -> Option<*const ()> {
+    lookup_checked(FUNC_TABLE, tag)
+}
+
+/// Look up an entry in the bootrom data table.
+///
+/// Returns `None` if the ROM magic is not valid, or the ROM does not provide the entry.
+pub fn rom_data_lookup(tag: RomFnTableCode) -> Option<*const ()> {
+    lookup_checked(DATA_TABLE, tag)
+}
+
+fn lookup_checked(table: *const u16, tag: RomFnTableCode) -> Option<*const ()> {
+    if !rom_magic_valid() {
+        return None;
+    }
+    let p: *const () = rom_table_lookup(table, tag);
+    (!p.is_null()).then_some(p)
+}
+
 /// To save space, the ROM likes to store memory pointers (which are 32-bit on
 /// the Cortex-M0+) using only the bottom 16-bits. The assumption is that the
 /// values they point at live in the first 64 KiB of ROM, and the ROM is mapped
@@ -194,6 +233,8 @@ macro_rules! rom_functions {
 
 rom_functions! {
     /// Return a count of the number of 1 bits in value.
+    ///
+    /// This must only be called on the RP2040, see [`bits::popcount32`] for a portable wrapper.
     b"P3" fn popcount32(value: u32) -> u32;
 
     /// Return the bits of value in the reverse order.
@@ -279,6 +320,50 @@ rom_functions! {
     b"WV" unsafe fn wait_for_vector() -> !;
 }
 
+/// Bit manipulation with the ROM routines, falling back to the core implementations
+///
+/// These call the ROM on the RP2040, and use [`u32::count_ones`], [`u32::leading_zeros`],
+/// [`u32::trailing_zeros`] and [`u32::reverse_bits`] on other targets, e.g. in host tests.
+/// The core methods are not guaranteed to use the ROM on the RP2040, as the compiler may
+/// expand them inline instead.
+pub mod bits {
+    /// Return a count of the number of 1 bits in value.
+    #[inline]
+    pub fn popcount32(value: u32) -> u32 {
+        #[cfg(all(target_arch = "arm", target_os = "none"))]
+        return super::popcount32(value);
+        #[cfg(not(all(target_arch = "arm", target_os = "none")))]
+        return value.count_ones();
+    }
+
+    /// Return the bits of value in the reverse order.
+    #[inline]
+    pub fn reverse32(value: u32) -> u32 {
+        #[cfg(all(target_arch = "arm", target_os = "none"))]
+        return super::reverse32(value);
+        #[cfg(not(all(target_arch = "arm", target_os = "none")))]
+        return value.reverse_bits();
+    }
+
+    /// Return the number of consecutive high order 0 bits of value. If value is zero, returns 32.
+    #[inline]
+    pub fn clz32(value: u32) -> u32 {
+        #[cfg(all(target_arch = "arm", target_os = "none"))]
+        return super::clz32(value);
+        #[cfg(not(all(target_arch = "arm", target_os = "none")))]
+        return value.leading_zeros();
+    }
+
+    /// Return the number of consecutive low order 0 bits of value. If value is zero, returns 32.
+    #[inline]
+    pub fn ctz32(value: u32) -> u32 {
+        #[cfg(all(target_arch = "arm", target_os = "none"))]
+        return super::ctz32(value);
+        #[cfg(not(all(target_arch = "arm", target_os = "none")))]
+        return value.trailing_zeros();
+    }
+}
+
 // Various C intrinsics in the ROM
 intrinsics! {
     #[alias = __popcountdi2]
@@ -333,6 +418,96 @@ intrinsics! {
     }
 }
 
+/// Copy `src` into `dest` with the ROM's optimized copy routines.
+///
+/// The faster word-wise [`memcpy44`] is used when both slices are word aligned and their
+/// length is a multiple of 4 bytes.
+///
+/// # Panics
+///
+/// Panics if the two slices have different lengths, like [`slice::copy_from_slice`].
+///
+/// ```no_run
+/// let src = [1u8; 1024];
+/// let mut dest = [0u8; 1024];
+/// rp2040_hal::rom_data::memcpy_slice(&mut dest, &src);
+/// ```
+pub fn memcpy_slice(dest: &mut [u8], src: &[u8]) {
+    assert_eq!(dest.len(), src.len());
+    let len = dest.len() as u32;
+    // Safety: the slices have the same length and cannot overlap, as `dest` is borrowed
+    // mutably.
+    unsafe {
+        if is_word_aligned(dest.as_ptr(), dest.len()) && is_word_aligned(src.as_ptr(), 0) {
+            memcpy44(
+                dest.as_mut_ptr() as *mut u32,
+                src.as_ptr() as *const u32,
+                len,
+            );
+        } else {
+            memcpy(dest.as_mut_ptr(), src.as_ptr(), len);
+        }
+    }
+}
+
+/// Fill `dest` with `value` with the ROM's optimized set routines.
+///
+/// The faster word-wise [`memset4`] is used when the slice is word aligned and its length is
+/// a multiple of 4 bytes.
+pub fn memset_slice(dest: &mut [u8], value: u8) {
+    let len = dest.len() as u32;
+    // Safety: the slice is valid for writes of its length.
+    unsafe {
+        if is_word_aligned(dest.as_ptr(), dest.len()) {
+            memset4(dest.as_mut_ptr() as *mut u32, value, len);
+        } else {
+            memset(dest.as_mut_ptr(), value, len);
+        }
+    }
+}
+
+/// Check that `ptr` is word aligned and `len` is a multiple of the word size.
+fn is_word_aligned(ptr: *const u8, len: usize) -> bool {
+    ptr as usize % 4 == 0 && len % 4 == 0
+}
+
+/// USB interfaces exposed by the bootrom in BOOTSEL mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UsbBootInterfaces {
+    /// Both the mass storage (UF2 drag and drop) and the PICOBOOT interfaces, as per cold boot.
+    All,
+    /// Only the mass storage interface.
+    MassStorageOnly,
+    /// Only the PICOBOOT interface, as used by `picotool`.
+    PicobootOnly,
+}
+
+/// Reboot into BOOTSEL mode, as if the BOOTSEL button was held at power on.
+///
+/// `activity_pin` is an optional GPIO number to drive an activity LED, which is lit while
+/// the host accesses the mass storage interface.
+///
+/// ```no_run
+/// use rp2040_hal::rom_data::{reboot_to_usb_boot, UsbBootInterfaces};
+/// // Reboot with the activity LED of the Pico, on GPIO 25.
+/// reboot_to_usb_boot(Some(25), UsbBootInterfaces::All);
+/// ```
+pub fn reboot_to_usb_boot(activity_pin: Option<u8>, interfaces: UsbBootInterfaces) -> ! {
+    let gpio_activity_pin_mask = activity_pin
+        .and_then(|pin| 1u32.checked_shl(pin.into()))
+        .unwrap_or(0);
+    let disable_interface_mask = match interfaces {
+        UsbBootInterfaces::All => 0,
+        UsbBootInterfaces::PicobootOnly => 1,
+        UsbBootInterfaces::MassStorageOnly => 2,
+    };
+    reset_to_usb_boot(gpio_activity_pin_mask, disable_interface_mask);
+    // The ROM function resets the chip through the watchdog, which may take a few cycles.
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
+
 unsafe fn convert_str(s: *const u8) -> &'static str {
     let mut end = s;
     while *end != 0 {
@@ -347,6 +522,40 @@ pub fn rom_version_number() -> u8 {
     unsafe { *VERSION_NUMBER }
 }
 
+/// Revision of the RP2040, as identified by its bootrom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChipRevision {
+    /// RP2040-B0, with the V1 bootrom.
+    B0,
+    /// RP2040-B1, with the V2 bootrom.
+    B1,
+    /// RP2040-B2, with the V3 bootrom.
+    B2,
+    /// A later revision, with the given bootrom version.
+    Unknown(u8),
+}
+
+/// The revision of the chip, derived from the [version number of the
+/// rom](rom_version_number).
+///
+/// This can be used to only enable workarounds on the affected revisions:
+///
+/// ```no_run
+/// use rp2040_hal::rom_data::{chip_revision, ChipRevision};
+/// if chip_revision() <= ChipRevision::B1 {
+///     // Apply the workaround for RP2040-E5
+/// }
+/// ```
+pub fn chip_revision() -> ChipRevision {
+    match rom_version_number() {
+        1 => ChipRevision::B0,
+        2 => ChipRevision::B1,
+        3 => ChipRevision::B2,
+        version => ChipRevision::Unknown(version),
+    }
+}
+
 /// The Raspberry Pi Trading Ltd copyright string.
 pub fn copyright_string() -> &'static str {
     let s: *const u8 = rom_table_lookup(DATA_TABLE, *b"CR");
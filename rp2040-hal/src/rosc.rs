@@ -1,9 +1,25 @@
 //! Ring Oscillator (ROSC)
+//!
+//! Once enabled, the frequency of the ROSC can be tuned with its frequency range, the drive
+//! strength of its stages and its output divider.
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, rosc::{FrequencyRange, RingOscillator}};
+//! let peripherals = pac::Peripherals::take().unwrap();
+//! let mut rosc = RingOscillator::new(peripherals.ROSC).initialize();
+//! rosc.set_frequency_range(FrequencyRange::Medium);
+//! rosc.set_divider(8).unwrap();
+//! let estimate = rosc.operating_frequency();
+//! ```
 // See [Chapter 2 Section 17](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) for more details
 
 use fugit::HertzU32;
 
-use crate::{pac::ROSC, typelevel::Sealed};
+use crate::{
+    clocks::{ClocksManager, FC0Source},
+    pac::ROSC,
+    typelevel::Sealed,
+};
 
 /// State of the Ring Oscillator (typestate trait)
 pub trait State: Sealed {}
@@ -19,6 +35,87 @@ pub struct Enabled {
 /// ROSC is in dormant mode (see Chapter 2, Section 17, §7)
 pub struct Dormant;
 
+/// Possible errors when configuring the RingOscillator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Argument is out of range of the register.
+    BadArgument,
+    /// No configuration of the ROSC is within the tolerance of the calibration target.
+    CalibrationFailed,
+}
+
+/// Frequency range of the ROSC, selecting the number of delay stages in the ring.
+///
+/// Fewer stages make a faster oscillator. The ranges are listed from the slowest to the fastest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrequencyRange {
+    /// 8 stages, the reset configuration.
+    Low,
+    /// 6 stages.
+    Medium,
+    /// 4 stages.
+    High,
+    /// 2 stages. This may run faster than the chip can handle, and is not recommended.
+    TooHigh,
+}
+
+impl FrequencyRange {
+    const ALL: [FrequencyRange; 4] = [Self::Low, Self::Medium, Self::High, Self::TooHigh];
+
+    /// Number of delay stages in the ring.
+    pub fn stages(self) -> u32 {
+        match self {
+            FrequencyRange::Low => 8,
+            FrequencyRange::Medium => 6,
+            FrequencyRange::High => 4,
+            FrequencyRange::TooHigh => 2,
+        }
+    }
+}
+
+/// Maximum value of the output divider.
+pub const MAX_DIVIDER: u8 = 32;
+
+/// Maximum drive strength of a stage. Each set bit adds drive, speeding up the stage.
+pub const MAX_DRIVE_STRENGTH: u8 = 0b111;
+
+/// Value added to the divider in the DIV register.
+const DIV_PASS: u16 = 0xaa0;
+
+/// Drive strengths used by the calibration, each adding one bit of drive.
+const DRIVE_LEVELS: [u8; 4] = [0b000, 0b001, 0b011, 0b111];
+
+/// Frequency ranges used by the calibration.
+const CALIBRATION_RANGES: [FrequencyRange; 3] = [
+    FrequencyRange::Low,
+    FrequencyRange::Medium,
+    FrequencyRange::High,
+];
+
+/// The frequency range and drive strengths of a calibration setting.
+///
+/// The settings are ordered from the slowest to the fastest ring: within each range, the drive
+/// of the stages is increased one bit at a time.
+fn calibration_setting(mut setting: u32) -> Option<(FrequencyRange, [u8; 8])> {
+    for range in CALIBRATION_RANGES {
+        let stages = range.stages();
+        let steps = 3 * stages + 1;
+        if setting >= steps {
+            setting -= steps;
+            continue;
+        }
+        let mut drive = [0; 8];
+        for (stage, d) in drive.iter_mut().enumerate().take(stages as usize) {
+            let extra = u32::from((stage as u32) < setting % stages);
+            *d = DRIVE_LEVELS[(setting / stages + extra) as usize];
+        }
+        return Some((range, drive));
+    }
+    None
+}
+
 impl State for Disabled {}
 impl Sealed for Disabled {}
 impl State for Enabled {}
@@ -80,6 +177,10 @@ impl RingOscillator<Disabled> {
 
 impl RingOscillator<Enabled> {
     /// Approx operating frequency of the ROSC in hertz
+    ///
+    /// This is the frequency given when initializing the ROSC, scaled by the changes of
+    /// frequency range and divider since. The actual frequency varies with the chip, voltage and
+    /// temperature.
     pub fn operating_frequency(&self) -> HertzU32 {
         self.state.freq_hz
     }
@@ -91,12 +192,207 @@ impl RingOscillator<Enabled> {
         self.transition(Disabled)
     }
 
+    /// The current frequency range.
+    pub fn frequency_range(&self) -> FrequencyRange {
+        use crate::pac::rosc::ctrl::FREQ_RANGE_A;
+        match self.device.ctrl.read().freq_range().variant() {
+            Some(FREQ_RANGE_A::MEDIUM) => FrequencyRange::Medium,
+            Some(FREQ_RANGE_A::HIGH) => FrequencyRange::High,
+            Some(FREQ_RANGE_A::TOOHIGH) => FrequencyRange::TooHigh,
+            // Any other value behaves like LOW.
+            _ => FrequencyRange::Low,
+        }
+    }
+
+    /// Set the frequency range.
+    ///
+    /// The range is changed one step at a time, as recommended by the datasheet. The estimated
+    /// [`operating_frequency`](Self::operating_frequency) is scaled by the change of the number
+    /// of stages.
+    pub fn set_frequency_range(&mut self, range: FrequencyRange) {
+        let current = self.frequency_range();
+        let mut step = current as usize;
+        while step != range as usize {
+            step = if range as usize > step {
+                step + 1
+            } else {
+                step - 1
+            };
+            self.device
+                .ctrl
+                .modify(|_, w| match FrequencyRange::ALL[step] {
+                    FrequencyRange::Low => w.freq_range().low(),
+                    FrequencyRange::Medium => w.freq_range().medium(),
+                    FrequencyRange::High => w.freq_range().high(),
+                    FrequencyRange::TooHigh => w.freq_range().toohigh(),
+                });
+        }
+        let hz = u64::from(self.state.freq_hz.to_Hz()) * u64::from(current.stages())
+            / u64::from(range.stages());
+        self.state.freq_hz = HertzU32::from_raw(hz as u32);
+    }
+
+    /// Set the drive strength of each of the 8 stages, from 0 to [`MAX_DRIVE_STRENGTH`].
+    ///
+    /// Only the stages used by the [`FrequencyRange`] matter. The effect of the drive strength
+    /// on the frequency varies between chips, so the estimated
+    /// [`operating_frequency`](Self::operating_frequency) is left unchanged: measure it, e.g.
+    /// with the frequency counter, and use [`set_operating_frequency`](Self::set_operating_frequency).
+    pub fn set_drive_strengths(&mut self, drive: [u8; 8]) -> Result<(), Error> {
+        if drive.iter().any(|&d| d > MAX_DRIVE_STRENGTH) {
+            return Err(Error::BadArgument);
+        }
+        self.device.freqa.write(|w| unsafe {
+            w.passwd().pass();
+            w.ds0().bits(drive[0]);
+            w.ds1().bits(drive[1]);
+            w.ds2().bits(drive[2]);
+            w.ds3().bits(drive[3])
+        });
+        self.device.freqb.write(|w| unsafe {
+            w.passwd().pass();
+            w.ds4().bits(drive[4]);
+            w.ds5().bits(drive[5]);
+            w.ds6().bits(drive[6]);
+            w.ds7().bits(drive[7])
+        });
+        Ok(())
+    }
+
+    /// The drive strength of each of the 8 stages.
+    pub fn drive_strengths(&self) -> [u8; 8] {
+        let a = self.device.freqa.read();
+        let b = self.device.freqb.read();
+        [
+            a.ds0().bits(),
+            a.ds1().bits(),
+            a.ds2().bits(),
+            a.ds3().bits(),
+            b.ds4().bits(),
+            b.ds5().bits(),
+            b.ds6().bits(),
+            b.ds7().bits(),
+        ]
+    }
+
+    /// The output divider, from 1 to [`MAX_DIVIDER`].
+    pub fn divider(&self) -> u8 {
+        match self.device.div.read().bits() as u16 {
+            DIV_PASS => MAX_DIVIDER,
+            div @ 0xaa1..=0xabf => (div - DIV_PASS) as u8,
+            // The register reads as 0 until written, with the divider at 16.
+            _ => 16,
+        }
+    }
+
+    /// Set the output divider, from 1 to [`MAX_DIVIDER`].
+    ///
+    /// The estimated [`operating_frequency`](Self::operating_frequency) is scaled accordingly.
+    pub fn set_divider(&mut self, div: u8) -> Result<(), Error> {
+        if !(1..=MAX_DIVIDER).contains(&div) {
+            return Err(Error::BadArgument);
+        }
+        let previous = self.divider();
+        let value = DIV_PASS + u16::from(div % MAX_DIVIDER);
+        self.device.div.write(|w| unsafe { w.bits(value.into()) });
+        let hz = u64::from(self.state.freq_hz.to_Hz()) * u64::from(previous) / u64::from(div);
+        self.state.freq_hz = HertzU32::from_raw(hz as u32);
+        Ok(())
+    }
+
+    /// Set the operating frequency, e.g. after measuring it with the frequency counter.
+    pub fn set_operating_frequency(&mut self, freq: HertzU32) {
+        self.state.freq_hz = freq;
+    }
+
+    /// Calibrate the ROSC to run within `tolerance` of `target`, using the frequency counter.
+    ///
+    /// The frequency range and drive strengths are swept from the slowest to the fastest ring,
+    /// excluding [`FrequencyRange::TooHigh`], with the output divided by [`MAX_DIVIDER`]. The
+    /// setting and divider closest to the target are then applied, and the resulting frequency is
+    /// measured and returned. It is also used as the
+    /// [`operating_frequency`](Self::operating_frequency) from then on.
+    ///
+    /// The frequency counter measures against clk_ref, which should run from the XOSC for the
+    /// result to be accurate. The clocks driven by the ROSC run slower than the final frequency
+    /// during the sweep.
+    ///
+    /// ```no_run
+    /// use fugit::RateExtU32;
+    /// # let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+    /// # let mut rosc: rp2040_hal::rosc::RingOscillator<rp2040_hal::rosc::Enabled> = todo!();
+    /// let freq = rosc.calibrate(&clocks, 48.MHz(), 500.kHz()).unwrap();
+    /// ```
+    pub fn calibrate(
+        &mut self,
+        clocks: &ClocksManager,
+        target: HertzU32,
+        tolerance: HertzU32,
+    ) -> Result<HertzU32, Error> {
+        let target_khz = target.to_kHz();
+        if target_khz == 0 {
+            return Err(Error::BadArgument);
+        }
+        self.set_divider(MAX_DIVIDER)?;
+
+        // (error, setting, divider) of the best candidate.
+        let mut best: Option<(u32, u32, u8)> = None;
+        let mut setting = 0;
+        while let Some((range, drive)) = calibration_setting(setting) {
+            self.apply_calibration_setting(range, drive);
+            let ring_khz = clocks.frequency_count_khz(FC0Source::Rosc) * u32::from(MAX_DIVIDER);
+            let div = ((ring_khz + target_khz / 2) / target_khz).clamp(1, MAX_DIVIDER.into());
+            let error = (ring_khz / div).abs_diff(target_khz);
+            if best.map_or(true, |(best_error, _, _)| error < best_error) {
+                best = Some((error, setting, div as u8));
+            }
+            setting += 1;
+        }
+
+        let (_, setting, div) = best.ok_or(Error::CalibrationFailed)?;
+        if let Some((range, drive)) = calibration_setting(setting) {
+            self.apply_calibration_setting(range, drive);
+        }
+        self.set_divider(div)?;
+        let freq = clocks.measure_frequency(FC0Source::Rosc);
+        self.state.freq_hz = freq;
+        if freq.to_Hz().abs_diff(target.to_Hz()) > tolerance.to_Hz() {
+            return Err(Error::CalibrationFailed);
+        }
+        Ok(freq)
+    }
+
+    /// Apply a frequency range and drive strengths, never going through a faster setting.
+    fn apply_calibration_setting(&mut self, range: FrequencyRange, drive: [u8; 8]) {
+        if range > self.frequency_range() {
+            let _ = self.set_drive_strengths([0; 8]);
+        }
+        self.set_frequency_range(range);
+        let _ = self.set_drive_strengths(drive);
+    }
+
     /// Generate random bit based on the Ring oscillator
     /// This is not suited for security purposes
     pub fn get_random_bit(&self) -> bool {
         self.device.randombit.read().randombit().bit()
     }
 
+    /// Generate a random bit with von Neumann whitening.
+    ///
+    /// Pairs of random bits are sampled until they differ, and the first of them is returned.
+    /// This removes the bias of the random bit, but not the correlation between samples, so the
+    /// result should still be fed into a proper generator, like the
+    /// [`EntropyPool`](crate::entropy::EntropyPool), for security purposes.
+    pub fn get_whitened_random_bit(&self) -> bool {
+        loop {
+            let a = self.get_random_bit();
+            let b = self.get_random_bit();
+            if a != b {
+                return a;
+            }
+        }
+    }
+
     /// Put the ROSC in DORMANT state.
     ///
     /// # Safety
@@ -114,6 +410,7 @@ impl RingOscillator<Enabled> {
     }
 }
 
+/// Random bytes made of [whitened random bits](RingOscillator::get_whitened_random_bit).
 impl rand_core::RngCore for RingOscillator<Enabled> {
     fn next_u32(&mut self) -> u32 {
         rand_core::impls::next_u32_via_fill(self)
@@ -128,7 +425,7 @@ impl rand_core::RngCore for RingOscillator<Enabled> {
             *chunk = 0_u8;
             for _ in 0..8 {
                 *chunk <<= 1;
-                *chunk ^= self.get_random_bit() as u8;
+                *chunk ^= self.get_whitened_random_bit() as u8;
             }
         }
     }
@@ -138,3 +435,27 @@ impl rand_core::RngCore for RingOscillator<Enabled> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibration_settings() {
+        assert_eq!(calibration_setting(0), Some((FrequencyRange::Low, [0; 8])));
+        assert_eq!(
+            calibration_setting(1),
+            Some((FrequencyRange::Low, [1, 0, 0, 0, 0, 0, 0, 0]))
+        );
+        assert_eq!(calibration_setting(24), Some((FrequencyRange::Low, [7; 8])));
+        assert_eq!(
+            calibration_setting(25),
+            Some((FrequencyRange::Medium, [0; 8]))
+        );
+        assert_eq!(
+            calibration_setting(56),
+            Some((FrequencyRange::High, [7, 7, 7, 7, 0, 0, 0, 0]))
+        );
+        assert_eq!(calibration_setting(57), None);
+    }
+}
@@ -48,6 +48,7 @@ pub struct DateTime {
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DayOfWeek {
     Sunday = 0,
     Monday = 1,
@@ -5,6 +5,7 @@ use crate::pac::rtc::{irq_setup_0, irq_setup_1};
 ///
 /// [`RealTimeClock::schedule_alarm`]: struct.RealTimeClock.html#method.schedule_alarm
 #[derive(Default)]
+#[cfg_attr(all(feature = "defmt", not(feature = "chrono")), derive(defmt::Format))]
 pub struct DateTimeFilter {
     /// The year that this alarm should trigger on, `None` if the RTC alarm should not trigger on a year value.
     pub year: Option<u16>,
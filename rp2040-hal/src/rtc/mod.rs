@@ -16,6 +16,36 @@
 //!   - There are cases where this is incorrect, e.g. century years have no leap day, but the chip will still add a Feb 29th.
 //!   - To disable leap year checking and never have a Feb 29th, call `RealTimeClock::set_leap_year_check(false)`.
 //!
+//! # Waking up from low power modes
+//!
+//! An RTC alarm can wake the chip from the low power modes of the [`power`](crate::power)
+//! module, as long as `clk_rtc` keeps running: it must not run from a PLL, which are stopped.
+//! Running it from the XOSC allows [`power::deep_sleep`](crate::power::deep_sleep) to sleep
+//! between two measurements of a data logger:
+//!
+//! ```no_run
+//! # #[cfg(feature = "chrono")]
+//! # fn main() { }
+//! # #[cfg(not(feature = "chrono"))]
+//! # fn main() {
+//! use fugit::RateExtU32;
+//! use rp2040_hal::{clocks::{Clock, ClocksManager}, pac, power::{self, WakeSources}, rtc::{DateTimeFilter, RealTimeClock}, xosc::{CrystalOscillator, Stable}};
+//! let mut core = pac::CorePeripherals::take().unwrap();
+//! let mut clocks: ClocksManager = todo!();
+//! let xosc: CrystalOscillator<Stable> = todo!();
+//! let mut rtc: RealTimeClock = todo!();
+//! // 12MHz / 256, the RTC keeps running while the PLLs are stopped.
+//! clocks.rtc_clock.configure_clock(&xosc, 46875u32.Hz()).unwrap();
+//! rtc.set_clock(&clocks.rtc_clock);
+//! rtc.schedule_repeating_alarm(DateTimeFilter::default().second(0));
+//! loop {
+//!     // Take a measurement, then sleep until the next minute.
+//!     power::deep_sleep(&mut clocks, &mut core.SCB, None, None, &WakeSources::new().rtc()).unwrap();
+//!     rtc.clear_interrupt();
+//! }
+//! # }
+//! ```
+//!
 //! Other limitations:
 //!
 //! - **Leap seconds**: The rp2040 will not take leap seconds into account
@@ -24,6 +54,7 @@
 use crate::clocks::Clock;
 use crate::clocks::RtcClock;
 use crate::pac::{RESETS, RTC};
+use crate::resets::SubsystemReset;
 
 mod filter;
 
@@ -38,6 +69,7 @@ pub use self::datetime::{DateTime, DayOfWeek, Error as DateTimeError};
 /// A reference to the real time clock of the system
 pub struct RealTimeClock {
     rtc: RTC,
+    repeating: bool,
 }
 
 impl RealTimeClock {
@@ -55,27 +87,50 @@ impl RealTimeClock {
     #[allow(clippy::needless_pass_by_ref_mut)]
     pub fn new(
         rtc: RTC,
-        clock: RtcClock,
+        clock: &RtcClock,
         resets: &mut RESETS,
         initial_date: DateTime,
     ) -> Result<Self, RtcError> {
         // Toggle the RTC reset
-        resets.reset.modify(|_, w| w.rtc().set_bit());
-        resets.reset.modify(|_, w| w.rtc().clear_bit());
-        while resets.reset_done.read().rtc().bit_is_clear() {
-            core::hint::spin_loop();
-        }
+        rtc.reset_bring_down(resets);
+        rtc.reset_bring_up(resets);
 
         // Set the RTC divider
-        let freq = clock.freq().to_Hz() - 1;
-        rtc.clkdiv_m1.write(|w| unsafe { w.bits(freq) });
+        rtc.clkdiv_m1
+            .write(|w| unsafe { w.bits(clock.freq().to_Hz() - 1) });
 
-        let mut result = Self { rtc };
+        let mut result = Self {
+            rtc,
+            repeating: false,
+        };
         result.set_leap_year_check(true); // should be on by default, make sure this is the case.
         result.set_datetime(initial_date)?;
         Ok(result)
     }
 
+    /// Releases the RTC peripheral. The clock keeps running.
+    pub fn free(self) -> RTC {
+        self.rtc
+    }
+
+    /// Update the RTC divider after `clk_rtc` was reconfigured, keeping the current time.
+    ///
+    /// The RTC is stopped while the divider is changed, which can make it lag by a fraction
+    /// of a second.
+    pub fn set_clock(&mut self, clock: &RtcClock) {
+        self.rtc.ctrl.modify(|_, w| w.rtc_enable().clear_bit());
+        while self.rtc.ctrl.read().rtc_active().bit_is_set() {
+            core::hint::spin_loop();
+        }
+        self.rtc
+            .clkdiv_m1
+            .write(|w| unsafe { w.bits(clock.freq().to_Hz() - 1) });
+        self.rtc.ctrl.modify(|_, w| w.rtc_enable().set_bit());
+        while self.rtc.ctrl.read().rtc_active().bit_is_clear() {
+            core::hint::spin_loop();
+        }
+    }
+
     /// Enable or disable the leap year check. The rp2040 chip will always add a Feb 29th on every year that is divisable by 4, but this may be incorrect (e.g. on century years). This function allows you to disable this check.
     ///
     /// Leap year checking is enabled by default.
@@ -113,9 +168,9 @@ impl RealTimeClock {
             w
         });
 
-        // Load the new datetime and re-enable RTC
-        self.rtc.ctrl.write(|w| w.load().set_bit());
-        self.rtc.ctrl.write(|w| w.rtc_enable().set_bit());
+        // Load the new datetime and re-enable RTC, keeping the leap year setting
+        self.rtc.ctrl.modify(|_, w| w.load().set_bit());
+        self.rtc.ctrl.modify(|_, w| w.rtc_enable().set_bit());
         while self.rtc.ctrl.read().rtc_active().bit_is_clear() {
             core::hint::spin_loop();
         }
@@ -133,6 +188,7 @@ impl RealTimeClock {
             return Err(RtcError::NotRunning);
         }
 
+        // Reading RTC_0 latches the value of RTC_1, so that both are coherent.
         let rtc_0 = self.rtc.rtc_0.read();
         let rtc_1 = self.rtc.rtc_1.read();
 
@@ -143,6 +199,11 @@ impl RealTimeClock {
     ///
     /// [`schedule_alarm`]: #method.schedule_alarm
     pub fn disable_alarm(&mut self) {
+        self.disable_match();
+        self.repeating = false;
+    }
+
+    fn disable_match(&mut self) {
         self.rtc
             .irq_setup_0
             .modify(|_, s| s.match_ena().clear_bit());
@@ -170,7 +231,31 @@ impl RealTimeClock {
     /// # }
     /// ```
     pub fn schedule_alarm(&mut self, filter: DateTimeFilter) {
-        self.disable_alarm();
+        self.repeating = false;
+        self.write_alarm(filter);
+    }
+
+    /// Schedule an alarm firing every time the fields set in `filter` match, e.g. every day at
+    /// 06:30 with:
+    ///
+    /// ```no_run
+    /// # use rp2040_hal::rtc::{RealTimeClock, DateTimeFilter};
+    /// # let mut real_time_clock: RealTimeClock = unsafe { core::mem::zeroed() };
+    /// real_time_clock.schedule_repeating_alarm(
+    ///     DateTimeFilter::default().hour(6).minute(30).second(0)
+    /// );
+    /// real_time_clock.enable_interrupt();
+    /// ```
+    ///
+    /// Unlike [`schedule_alarm`](Self::schedule_alarm), the alarm is enabled again by
+    /// [`clear_interrupt`](Self::clear_interrupt).
+    pub fn schedule_repeating_alarm(&mut self, filter: DateTimeFilter) {
+        self.repeating = true;
+        self.write_alarm(filter);
+    }
+
+    fn write_alarm(&mut self, filter: DateTimeFilter) {
+        self.disable_match();
 
         self.rtc.irq_setup_0.write(|w| {
             filter.write_setup_0(w);
@@ -181,6 +266,10 @@ impl RealTimeClock {
             w
         });
 
+        self.enable_alarm();
+    }
+
+    fn enable_alarm(&mut self) {
         // Set the enable bit and check if it is set
         self.rtc.irq_setup_0.modify(|_, w| w.match_ena().set_bit());
         while self.rtc.irq_setup_0.read().match_active().bit_is_clear() {
@@ -191,14 +280,37 @@ impl RealTimeClock {
     /// Clear the interrupt. This should be called every time the `RTC_IRQ` interrupt is triggered,
     /// or the next [`schedule_alarm`] will never fire.
     ///
+    /// The alarm is disabled, unless it was scheduled with [`schedule_repeating_alarm`].
+    ///
     /// [`schedule_alarm`]: #method.schedule_alarm
+    /// [`schedule_repeating_alarm`]: #method.schedule_repeating_alarm
     pub fn clear_interrupt(&mut self) {
-        self.disable_alarm();
+        self.disable_match();
+        if self.repeating {
+            self.enable_alarm();
+        }
+    }
+
+    /// Enable the `RTC_IRQ` interrupt, raised when the alarm fires.
+    pub fn enable_interrupt(&mut self) {
+        self.rtc.inte.modify(|_, w| w.rtc().set_bit());
+    }
+
+    /// Disable the `RTC_IRQ` interrupt.
+    pub fn disable_interrupt(&mut self) {
+        self.rtc.inte.modify(|_, w| w.rtc().clear_bit());
+    }
+
+    /// Whether the alarm fired and its interrupt is pending, regardless of the interrupt being
+    /// enabled.
+    pub fn is_alarm_pending(&self) -> bool {
+        self.rtc.intr.read().rtc().bit_is_set()
     }
 }
 
 /// Errors that can occur on methods on [RtcClock]
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum RtcError {
     /// An invalid DateTime was given or stored on the hardware.
     InvalidDateTime(DateTimeError),
@@ -23,6 +23,9 @@ use crate::typelevel::Sealed;
 use super::*;
 use core::convert::Infallible;
 
+mod channel;
+pub use channel::{Channel, FifoMessage};
+
 /// Id of the core.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -55,6 +58,8 @@ pub struct HwDivider {
 }
 
 /// Result of divide/modulo operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DivResult<T> {
     /// The quotient of divide/modulo operation
     pub quotient: T,
@@ -62,6 +67,18 @@ pub struct DivResult<T> {
     pub remainder: T,
 }
 
+/// Saved state of the hardware divider.
+///
+/// See [`HwDivider::save_state`] and [`HwDivider::restore_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DividerState {
+    dividend: u32,
+    divisor: u32,
+    remainder: u32,
+    quotient: u32,
+}
+
 /// Struct containing ownership markers for managing ownership of the SIO registers.
 pub struct Sio {
     _sio: pac::SIO,
@@ -110,7 +127,7 @@ impl Sio {
         match unsafe { (*pac::SIO::ptr()).cpuid.read().bits() as u8 } {
             0 => CoreId::Core0,
             1 => CoreId::Core1,
-            _ => unreachable!("This MCU only has 2 cores."),
+            _ => hw_unreachable!("This MCU only has 2 cores."),
         }
     }
 }
@@ -200,6 +217,88 @@ impl SioFifo {
             }
         }
     }
+
+    /// Push to the FIFO if there is space, for use with the `nb` combinators.
+    ///
+    /// Returns `WouldBlock` if the FIFO is full.
+    pub fn try_write(&mut self, value: u32) -> nb::Result<(), Infallible> {
+        if self.is_write_ready() {
+            self.write(value);
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Pop from the FIFO, for use with the `nb` combinators.
+    ///
+    /// Returns `WouldBlock` if the FIFO is empty.
+    pub fn try_read(&mut self) -> nb::Result<u32, Infallible> {
+        self.read().ok_or(nb::Error::WouldBlock)
+    }
+
+    /// Check if the FIFO was written to while it was full.
+    ///
+    /// This flag is sticky and stays set until [`clear_interrupt`](Self::clear_interrupt)
+    /// is called.
+    pub fn is_write_overflow(&self) -> bool {
+        let sio = unsafe { &(*pac::SIO::ptr()) };
+        sio.fifo_st.read().wof().bit_is_set()
+    }
+
+    /// Check if the FIFO was read from while it was empty.
+    ///
+    /// This flag is sticky and stays set until [`clear_interrupt`](Self::clear_interrupt)
+    /// is called.
+    pub fn is_read_underflow(&self) -> bool {
+        let sio = unsafe { &(*pac::SIO::ptr()) };
+        sio.fifo_st.read().roe().bit_is_set()
+    }
+
+    /// The interrupt raised on the current core by this FIFO.
+    ///
+    /// Each core has its own FIFO interrupt (`SIO_IRQ_PROC0` on core 0, `SIO_IRQ_PROC1` on
+    /// core 1). It is asserted whenever the FIFO holds data or one of the error flags is set.
+    /// There is no enable bit in the SIO itself, so the interrupt is enabled by unmasking it
+    /// in the NVIC:
+    ///
+    /// ```no_run
+    /// # use rp2040_hal::{pac, sio::Sio};
+    /// # let mut peripherals = pac::Peripherals::take().unwrap();
+    /// # let sio = Sio::new(peripherals.SIO);
+    /// unsafe { pac::NVIC::unmask(sio.fifo.interrupt()) };
+    /// // The core can now `wfi` until the other core sends a message.
+    /// cortex_m::asm::wfi();
+    /// ```
+    pub fn interrupt(&self) -> pac::Interrupt {
+        match Sio::core() {
+            CoreId::Core0 => pac::Interrupt::SIO_IRQ_PROC0,
+            CoreId::Core1 => pac::Interrupt::SIO_IRQ_PROC1,
+        }
+    }
+
+    /// Clear the sticky error flags (`ROE` and `WOF`).
+    ///
+    /// The interrupt stays asserted as long as there is data to be read, so the FIFO also needs
+    /// to be drained for the interrupt to be deasserted.
+    pub fn clear_interrupt(&mut self) {
+        let sio = unsafe { &(*pac::SIO::ptr()) };
+        // Writing any value to FIFO_ST clears both flags.
+        sio.fifo_st
+            .write(|w| w.wof().clear_bit_by_one().roe().clear_bit_by_one());
+    }
+
+    /// Service the FIFO interrupt.
+    ///
+    /// Calls `on_message` for every value currently in the FIFO, then clears the error flags so
+    /// the interrupt is deasserted. This is intended to be called from the `SIO_IRQ_PROCx`
+    /// interrupt handler.
+    pub fn handle_interrupt<F: FnMut(u32)>(&mut self, mut on_message: F) {
+        while let Some(value) = self.read() {
+            on_message(value);
+        }
+        self.clear_interrupt();
+    }
 }
 
 // This takes advantage of how AAPCS defines a 64-bit return on 32-bit registers
@@ -367,6 +466,98 @@ impl HwDivider {
     pub fn signed(&self, dividend: i32, divisor: i32) -> DivResult<i32> {
         divider_signed(dividend, divisor)
     }
+
+    /// Divide `dividend` by `divisor`, returning `(quotient, remainder)`.
+    ///
+    /// Like [`signed`](Self::signed), this is safe to use from interrupt handlers: a division
+    /// interrupting another one saves and restores the divider state around it.
+    pub fn div_rem(&self, dividend: i32, divisor: i32) -> (i32, i32) {
+        let DivResult {
+            quotient,
+            remainder,
+        } = divider_signed(dividend, divisor);
+        (quotient, remainder)
+    }
+
+    /// Unsigned variant of [`div_rem`](Self::div_rem).
+    pub fn div_rem_unsigned(&self, dividend: u32, divisor: u32) -> (u32, u32) {
+        let DivResult {
+            quotient,
+            remainder,
+        } = divider_unsigned(dividend, divisor);
+        (quotient, remainder)
+    }
+
+    /// Perform hardware unsigned divide/modulo operation, checking for division by zero.
+    ///
+    /// Returns `None` if `divisor` is zero.
+    pub fn checked_unsigned(&self, dividend: u32, divisor: u32) -> Option<DivResult<u32>> {
+        if divisor == 0 {
+            None
+        } else {
+            Some(divider_unsigned(dividend, divisor))
+        }
+    }
+
+    /// Perform hardware signed divide/modulo operation, checking for division by zero and
+    /// overflow.
+    ///
+    /// Returns `None` if `divisor` is zero, or if the quotient would overflow
+    /// (`i32::MIN / -1`).
+    pub fn checked_signed(&self, dividend: i32, divisor: i32) -> Option<DivResult<i32>> {
+        if divisor == 0 || (dividend == i32::MIN && divisor == -1) {
+            None
+        } else {
+            Some(divider_signed(dividend, divisor))
+        }
+    }
+
+    /// Save the current state of the divider.
+    ///
+    /// The division operations provided by this module (and the division intrinsics) already
+    /// save and restore the divider state if they interrupt another division. This is only
+    /// needed by code driving the divider registers directly, which can use it to make
+    /// an interrupt handler leave an in-progress division untouched:
+    ///
+    /// ```no_run
+    /// # use rp2040_hal::{pac, sio::Sio};
+    /// # let mut peripherals = pac::Peripherals::take().unwrap();
+    /// # let mut sio = Sio::new(peripherals.SIO);
+    /// let state = sio.hwdivider.save_state();
+    /// // use the divider registers
+    /// sio.hwdivider.restore_state(&state);
+    /// ```
+    pub fn save_state(&mut self) -> DividerState {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        // The result is only valid 8 cycles after the last write to the operands.
+        while sio.div_csr.read().ready().bit_is_clear() {
+            cortex_m::asm::nop();
+        }
+        // Read the quotient last, since that's what clears the dirty flag.
+        DividerState {
+            dividend: sio.div_udividend.read().bits(),
+            divisor: sio.div_udivisor.read().bits(),
+            remainder: sio.div_remainder.read().bits(),
+            quotient: sio.div_quotient.read().bits(),
+        }
+    }
+
+    /// Restore a divider state previously saved with [`save_state`](Self::save_state).
+    ///
+    /// Writing the operands starts a new calculation, which is stopped by writing the
+    /// results. After this returns, the result registers hold the saved values and the
+    /// divider reports them as ready.
+    pub fn restore_state(&mut self, state: &DividerState) {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        sio.div_udividend
+            .write(|w| unsafe { w.bits(state.dividend) });
+        sio.div_udivisor.write(|w| unsafe { w.bits(state.divisor) });
+        sio.div_remainder
+            .write(|w| unsafe { w.bits(state.remainder) });
+        // Writing the quotient last sets both DIRTY and READY.
+        sio.div_quotient
+            .write(|w| unsafe { w.bits(state.quotient) });
+    }
 }
 
 intrinsics! {
@@ -401,6 +592,11 @@ intrinsics! {
     }
 }
 
+/// The spinlocks reserved for the HAL and for libraries, see [`Spinlock`].
+///
+/// Spinlock 31 is used by the `critical-section` implementation.
+pub const RESERVED_SPINLOCKS: core::ops::RangeInclusive<usize> = 24..=31;
+
 /// This type is just used to limit us to Spinlocks `0..=31`
 pub trait SpinlockValid: Sealed {}
 
@@ -442,9 +638,16 @@ pub trait SpinlockValid: Sealed {}
 ///
 /// **Note:** The `critical-section` implementation uses Spinlock 31.
 ///
+/// # Reserved spinlocks
+///
+/// As any code can claim any spinlock, the spinlocks in [`RESERVED_SPINLOCKS`] are reserved for
+/// the HAL and for libraries built on it, so that they never contend with application code by
+/// accident. Applications should use the spinlocks below that range, and libraries should make
+/// the spinlock they use configurable, e.g. with a const generic.
+///
 /// [`claim`]: #method.claim
 /// [`try_claim`]: #method.try_claim
-/// [`claim_async`]: #method.claim_asyncs
+/// [`claim_async`]: #method.claim_async
 pub struct Spinlock<const N: usize>(core::marker::PhantomData<()>)
 where
     Spinlock<N>: SpinlockValid;
@@ -482,6 +685,24 @@ where
         Self::try_claim().ok_or(nb::Error::WouldBlock)
     }
 
+    /// Claim the spinlock, run `f` and release the spinlock again.
+    ///
+    /// This blocks until the lock is available, in the same way as [`claim`](Self::claim).
+    ///
+    /// ```no_run
+    /// use rp2040_hal::sio::Spinlock0;
+    /// static mut SOME_GLOBAL_VAR: u32 = 0;
+    ///
+    /// let value = Spinlock0::with(|| unsafe {
+    ///     SOME_GLOBAL_VAR += 1;
+    ///     SOME_GLOBAL_VAR
+    /// });
+    /// ```
+    pub fn with<R>(f: impl FnOnce() -> R) -> R {
+        let _lock = Self::claim();
+        f()
+    }
+
     /// Clear a locked spin-lock.
     ///
     /// # Safety
@@ -571,12 +792,14 @@ pub unsafe fn spinlock_reset() {
 
 /// Configuration struct for one lane of the interpolator
 pub struct LaneCtrl {
-    /// Bit 22 - Only present on INTERP1 on each core. If CLAMP mode is enabled:  
+    /// Bit 22 - Only present in lane 0 of INTERP1 on each core, see
+    /// [`Interp1::set_clamp_enabled`]. If CLAMP mode is enabled:  
     /// - LANE0 result is shifted and masked ACCUM0, clamped by a lower bound of  
     /// BASE0 and an upper bound of BASE1.  
     /// - Signedness of these comparisons is determined by LANE0_CTRL_SIGNED
     pub clamp: bool,
-    /// Bit 21 - Only present on INTERP0 on each core. If BLEND mode is enabled:
+    /// Bit 21 - Only present in lane 0 of INTERP0 on each core, see
+    /// [`Interp0::set_blend_enabled`]. If BLEND mode is enabled:
     /// - LANE1 result is a linear interpolation between BASE0 and BASE1, controlled
     /// by the 8 LSBs of lane 1 shift and mask value (a fractional number between
     /// 0 and 255/256ths)
@@ -625,7 +848,7 @@ impl LaneCtrl {
         }
     }
 
-    /// encode the configuration to be loaded in the ctrl register of one lane of an interpolator
+    /// Encode the configuration to be loaded in the ctrl register of one lane of an interpolator
     pub const fn encode(&self) -> u32 {
         assert!(!(self.blend && self.clamp));
         assert!(self.force_msb < 0b100);
@@ -646,6 +869,12 @@ impl LaneCtrl {
     }
 }
 
+impl Default for LaneCtrl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 ///Trait representing the functionnality of a single lane of an interpolator.
 pub trait Lane: Sealed {
     ///Read the lane result, and simultaneously write lane results to both accumulators.
@@ -668,6 +897,10 @@ pub trait Lane: Sealed {
     fn add_accum(&mut self, v: u32);
     ///Read the raw shift and mask value (BASE register not added)
     fn read_raw(&self) -> u32;
+    ///Encode the configuration and write it to the control register
+    fn configure(&mut self, ctrl: &LaneCtrl) {
+        self.set_ctrl(ctrl.encode());
+    }
 }
 
 ///Trait representing the functionnality of an interpolator.
@@ -810,3 +1043,35 @@ interpolators!(
     Interp0 : ([Lane0,0],[Lane1,1]),
     Interp1 : ([Lane0,0],[Lane1,1])
 );
+
+impl Interp0 {
+    /// Enable or disable the blend mode, see [`LaneCtrl::blend`].
+    ///
+    /// This only changes the BLEND bit of lane 0, the rest of the lane configuration is kept.
+    pub fn set_blend_enabled(&mut self, enabled: bool) {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        sio.interp0_ctrl_lane0.modify(|_, w| w.blend().bit(enabled));
+    }
+
+    /// Whether the blend mode is enabled.
+    pub fn is_blend_enabled(&self) -> bool {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        sio.interp0_ctrl_lane0.read().blend().bit_is_set()
+    }
+}
+
+impl Interp1 {
+    /// Enable or disable the clamp mode, see [`LaneCtrl::clamp`].
+    ///
+    /// This only changes the CLAMP bit of lane 0, the rest of the lane configuration is kept.
+    pub fn set_clamp_enabled(&mut self, enabled: bool) {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        sio.interp1_ctrl_lane0.modify(|_, w| w.clamp().bit(enabled));
+    }
+
+    /// Whether the clamp mode is enabled.
+    pub fn is_clamp_enabled(&self) -> bool {
+        let sio = unsafe { &*pac::SIO::ptr() };
+        sio.interp1_ctrl_lane0.read().clamp().bit_is_set()
+    }
+}
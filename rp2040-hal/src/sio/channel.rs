@@ -0,0 +1,182 @@
+//! Typed message passing over the inter-core FIFO
+//!
+//! [`Channel`] wraps a [`SioFifo`] and sends or receives values of a single type
+//! implementing [`FifoMessage`]. Messages are never dropped: the sender waits for
+//! room in the FIFO and the receiver waits for every word of a message.
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, sio::{Channel, FifoMessage, Sio}};
+//!
+//! #[derive(Clone, Copy)]
+//! enum Command {
+//!     SetLed(bool),
+//!     Blink(u16),
+//! }
+//!
+//! impl FifoMessage for Command {
+//!     const WORDS: usize = 1;
+//!
+//!     fn encode(&self, write: &mut dyn FnMut(u32)) {
+//!         match *self {
+//!             Command::SetLed(on) => write(on as u32),
+//!             Command::Blink(period) => write(1 << 16 | period as u32),
+//!         }
+//!     }
+//!
+//!     fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+//!         let word = read();
+//!         match word >> 16 {
+//!             0 => Command::SetLed(word & 1 != 0),
+//!             _ => Command::Blink(word as u16),
+//!         }
+//!     }
+//! }
+//!
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut sio = Sio::new(pac.SIO);
+//! let mut channel = Channel::<Command>::new(&mut sio.fifo);
+//! channel.send(&Command::Blink(500));
+//! ```
+
+use core::{convert::Infallible, marker::PhantomData};
+
+use super::SioFifo;
+
+/// A value which can be sent over the inter-core FIFO.
+pub trait FifoMessage: Sized {
+    /// Number of 32-bit words used to encode one message.
+    ///
+    /// `encode` must call `write` and `decode` must call `read` exactly this many times.
+    const WORDS: usize;
+
+    /// Encode the message, passing each word to `write`.
+    fn encode(&self, write: &mut dyn FnMut(u32));
+
+    /// Decode a message, reading each word with `read`.
+    fn decode(read: &mut dyn FnMut() -> u32) -> Self;
+}
+
+macro_rules! fifo_message_word {
+    ($($t:ty),+) => {
+        $(
+            impl FifoMessage for $t {
+                const WORDS: usize = 1;
+
+                fn encode(&self, write: &mut dyn FnMut(u32)) {
+                    write(*self as u32);
+                }
+
+                fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+                    read() as $t
+                }
+            }
+        )+
+    };
+}
+fifo_message_word!(u8, u16, u32, i8, i16, i32);
+
+macro_rules! fifo_message_double_word {
+    ($($t:ty),+) => {
+        $(
+            impl FifoMessage for $t {
+                const WORDS: usize = 2;
+
+                fn encode(&self, write: &mut dyn FnMut(u32)) {
+                    write(*self as u32);
+                    write((*self >> 32) as u32);
+                }
+
+                fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+                    let low = read() as u64;
+                    let high = read() as u64;
+                    (high << 32 | low) as $t
+                }
+            }
+        )+
+    };
+}
+fifo_message_double_word!(u64, i64);
+
+impl FifoMessage for bool {
+    const WORDS: usize = 1;
+
+    fn encode(&self, write: &mut dyn FnMut(u32)) {
+        write(*self as u32);
+    }
+
+    fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+        read() != 0
+    }
+}
+
+impl FifoMessage for f32 {
+    const WORDS: usize = 1;
+
+    fn encode(&self, write: &mut dyn FnMut(u32)) {
+        write(self.to_bits());
+    }
+
+    fn decode(read: &mut dyn FnMut() -> u32) -> Self {
+        f32::from_bits(read())
+    }
+}
+
+/// Typed, non-lossy message channel over the inter-core FIFO.
+///
+/// Each core has its own end of the FIFO, so both cores create their own `Channel`
+/// over their own [`SioFifo`]. Both ends must agree on the message type.
+pub struct Channel<'a, T: FifoMessage> {
+    fifo: &'a mut SioFifo,
+    _message: PhantomData<T>,
+}
+
+impl<'a, T: FifoMessage> Channel<'a, T> {
+    /// Create a new channel using this core's end of the FIFO.
+    pub fn new(fifo: &'a mut SioFifo) -> Self {
+        Self {
+            fifo,
+            _message: PhantomData,
+        }
+    }
+
+    /// Send a message, waiting for room in the FIFO.
+    pub fn send(&mut self, message: &T) {
+        let fifo = &mut *self.fifo;
+        message.encode(&mut |word| fifo.write_blocking(word));
+    }
+
+    /// Try to send a message.
+    ///
+    /// Returns `WouldBlock` if the FIFO is full. Once the first word has been written, the
+    /// remaining words of a multi-word message are written blocking, so a message is never
+    /// partially sent.
+    pub fn try_send(&mut self, message: &T) -> nb::Result<(), Infallible> {
+        if !self.fifo.is_write_ready() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.send(message);
+        Ok(())
+    }
+
+    /// Receive a message, waiting for it to arrive.
+    pub fn recv(&mut self) -> T {
+        let fifo = &mut *self.fifo;
+        T::decode(&mut || fifo.read_blocking())
+    }
+
+    /// Try to receive a message.
+    ///
+    /// Returns `WouldBlock` if the FIFO is empty. Once the first word has been read, the
+    /// remaining words of a multi-word message are read blocking.
+    pub fn try_recv(&mut self) -> nb::Result<T, Infallible> {
+        if !self.fifo.is_read_ready() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.recv())
+    }
+
+    /// Release the underlying FIFO.
+    pub fn free(self) -> &'a mut SioFifo {
+        self.fifo
+    }
+}
@@ -21,10 +21,10 @@
 
 use core::{convert::Infallible, marker::PhantomData, ops::Deref};
 
-#[cfg(feature = "eh1_0_alpha")]
-use eh1_0_alpha::spi as eh1;
-#[cfg(feature = "eh1_0_alpha")]
-use eh_nb_1_0_alpha::spi as eh1nb;
+#[cfg(feature = "eh1")]
+use eh1_0::spi as eh1;
+#[cfg(feature = "eh1")]
+use eh_nb_1_0::spi as eh1nb;
 use embedded_hal::{
     blocking::spi,
     spi::{FullDuplex, Phase, Polarity},
@@ -38,7 +38,11 @@ use crate::{
     typelevel::Sealed,
 };
 
+#[cfg(feature = "async")]
+mod asynch;
 mod pins;
+#[cfg(feature = "async")]
+pub use asynch::on_interrupt;
 pub use pins::*;
 
 impl From<embedded_hal::spi::Mode> for FrameFormat {
@@ -64,27 +68,43 @@ pub enum FrameFormat {
     NationalSemiconductorMicrowire,
 }
 
-#[cfg(feature = "eh1_0_alpha")]
-impl From<eh1_0_alpha::spi::Mode> for FrameFormat {
-    fn from(f: eh1_0_alpha::spi::Mode) -> Self {
-        let eh1_0_alpha::spi::Mode { polarity, phase } = f;
+#[cfg(feature = "defmt")]
+impl defmt::Format for FrameFormat {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            FrameFormat::MotorolaSpi(mode) => defmt::write!(
+                fmt,
+                "MotorolaSpi(CPOL={=bool}, CPHA={=bool})",
+                mode.polarity == Polarity::IdleHigh,
+                mode.phase == Phase::CaptureOnSecondTransition
+            ),
+            FrameFormat::TexasInstrumentsSynchronousSerial => {
+                defmt::write!(fmt, "TexasInstrumentsSynchronousSerial")
+            }
+            FrameFormat::NationalSemiconductorMicrowire => {
+                defmt::write!(fmt, "NationalSemiconductorMicrowire")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl From<eh1_0::spi::Mode> for FrameFormat {
+    fn from(f: eh1_0::spi::Mode) -> Self {
+        let eh1_0::spi::Mode { polarity, phase } = f;
         match (polarity, phase) {
-            (
-                eh1_0_alpha::spi::Polarity::IdleLow,
-                eh1_0_alpha::spi::Phase::CaptureOnFirstTransition,
-            ) => FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_0),
-            (
-                eh1_0_alpha::spi::Polarity::IdleLow,
-                eh1_0_alpha::spi::Phase::CaptureOnSecondTransition,
-            ) => FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_1),
-            (
-                eh1_0_alpha::spi::Polarity::IdleHigh,
-                eh1_0_alpha::spi::Phase::CaptureOnFirstTransition,
-            ) => FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_2),
-            (
-                eh1_0_alpha::spi::Polarity::IdleHigh,
-                eh1_0_alpha::spi::Phase::CaptureOnSecondTransition,
-            ) => FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_3),
+            (eh1_0::spi::Polarity::IdleLow, eh1_0::spi::Phase::CaptureOnFirstTransition) => {
+                FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_0)
+            }
+            (eh1_0::spi::Polarity::IdleLow, eh1_0::spi::Phase::CaptureOnSecondTransition) => {
+                FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_1)
+            }
+            (eh1_0::spi::Polarity::IdleHigh, eh1_0::spi::Phase::CaptureOnFirstTransition) => {
+                FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_2)
+            }
+            (eh1_0::spi::Polarity::IdleHigh, eh1_0::spi::Phase::CaptureOnSecondTransition) => {
+                FrameFormat::MotorolaSpi(embedded_hal::spi::MODE_3)
+            }
         }
     }
 }
@@ -368,12 +388,12 @@ macro_rules! impl_write {
         impl<D: SpiDevice, P: ValidSpiPinout<D>> spi::transfer::Default<$type> for Spi<Enabled, D, P, $nr> {}
         impl<D: SpiDevice, P: ValidSpiPinout<D>> spi::write_iter::Default<$type> for Spi<Enabled, D, P, $nr> {}
 
-        #[cfg(feature = "eh1_0_alpha")]
+        #[cfg(feature = "eh1")]
         impl<D: SpiDevice, P: ValidSpiPinout<D>> eh1::ErrorType for Spi<Enabled, D, P, $nr> {
             type Error = Infallible;
         }
 
-        #[cfg(feature = "eh1_0_alpha")]
+        #[cfg(feature = "eh1")]
         impl<D: SpiDevice, P: ValidSpiPinout<D>> eh1::SpiBus<$type> for Spi<Enabled, D, P, $nr> {
             fn read(&mut self, words: &mut [$type]) -> Result<(), Self::Error> {
                 for word in words.iter_mut() {
@@ -448,7 +468,7 @@ macro_rules! impl_write {
             }
         }
 
-        #[cfg(feature = "eh1_0_alpha")]
+        #[cfg(feature = "eh1")]
         impl<D: SpiDevice, P: ValidSpiPinout<D>> eh1nb::FullDuplex<$type> for Spi<Enabled, D, P, $nr> {
             fn read(&mut self) -> Result<$type, nb::Error<Infallible>> {
                 if !self.is_readable() {
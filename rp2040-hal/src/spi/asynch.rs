@@ -0,0 +1,148 @@
+//! Serial Peripheral Interface (SPI) - Async Code
+//!
+//! This module implements the `embedded-hal-async` SPI bus trait, waiting for the SPI receive
+//! interrupts instead of polling. See [`async_utils`](crate::async_utils).
+//!
+//! The receive interrupt fires once the receive FIFO is half full. The last few words of a
+//! transfer are picked up by the receive timeout interrupt instead, which fires 32 SPI clock
+//! periods after the FIFO stopped filling.
+
+use core::cell::Cell;
+
+use embedded_hal_async::spi::SpiBus;
+
+use super::{Enabled, Spi, SpiDevice, ValidSpiPinout};
+use crate::{
+    async_utils::{wait_for, WakerSlot},
+    pac::{self, spi0::RegisterBlock},
+};
+
+static WAKERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+
+/// Wake the task transferring over the SPI bus.
+///
+/// Call this from the `SPIx_IRQ` interrupt handler, e.g. `on_interrupt::<pac::SPI0>()`. It
+/// disables the receive interrupts which fired, so the SPI should not be used with other
+/// interrupt handling at the same time.
+pub fn on_interrupt<D: SpiDevice>() {
+    // Safety: only the interrupt masks are modified, from the interrupt handler which cannot be
+    // preempted by the task modifying them.
+    let rb: &RegisterBlock = unsafe {
+        match D::ID {
+            0 => &*pac::SPI0::ptr(),
+            _ => &*pac::SPI1::ptr(),
+        }
+    };
+    let mis = rb.sspmis.read();
+    if mis.rxmis().bit_is_set() || mis.rtmis().bit_is_set() {
+        rb.sspimsc
+            .modify(|_, w| w.rxim().clear_bit().rtim().clear_bit());
+        rb.sspicr.write(|w| w.rtic().clear_bit_by_one());
+        WAKERS[D::ID].wake();
+    }
+}
+
+/// Exchange `len` words, sending `write(i)` and passing the received words to `read(i, word)`.
+async fn transfer<D: SpiDevice>(
+    device: &D,
+    len: usize,
+    mut write: impl FnMut(usize) -> u16,
+    mut read: impl FnMut(usize, u16),
+) {
+    let (mut sent, mut received) = (0, 0);
+    while received < len {
+        // The receive FIFO holds 8 words, so no more may be in flight for none to be lost.
+        while sent < len && sent - received < 8 && device.sspsr.read().tnf().bit_is_set() {
+            let word = write(sent);
+            device.sspdr.write(|w| unsafe { w.data().bits(word) });
+            sent += 1;
+        }
+        while device.sspsr.read().rne().bit_is_set() {
+            read(received, device.sspdr.read().data().bits());
+            received += 1;
+        }
+        if received < len {
+            device.sspicr.write(|w| w.rtic().clear_bit_by_one());
+            wait_for(
+                &WAKERS[D::ID],
+                || {
+                    let ris = device.sspris.read();
+                    (ris.rxris().bit_is_set() || ris.rtris().bit_is_set()).then_some(())
+                },
+                || {
+                    critical_section::with(|_| {
+                        device
+                            .sspimsc
+                            .modify(|_, w| w.rxim().set_bit().rtim().set_bit())
+                    })
+                },
+            )
+            .await;
+        }
+    }
+}
+
+macro_rules! impl_async {
+    ($type:ident, [$($nr:expr),+]) => {
+
+        $(
+        impl<D: SpiDevice, P: ValidSpiPinout<D>> SpiBus<$type> for Spi<Enabled, D, P, $nr> {
+            async fn read(&mut self, words: &mut [$type]) -> Result<(), Self::Error> {
+                let len = words.len();
+                transfer(&self.device, len, |_| 0, |i, word| words[i] = word as $type).await;
+                Ok(())
+            }
+
+            async fn write(&mut self, words: &[$type]) -> Result<(), Self::Error> {
+                transfer(&self.device, words.len(), |i| words[i] as u16, |_, _| {}).await;
+                Ok(())
+            }
+
+            async fn transfer(
+                &mut self,
+                read: &mut [$type],
+                write: &[$type],
+            ) -> Result<(), Self::Error> {
+                // Send empty words once the write buffer is empty, and drop the extra words once
+                // the read buffer is full.
+                let len = read.len().max(write.len());
+                transfer(
+                    &self.device,
+                    len,
+                    |i| write.get(i).copied().unwrap_or(0) as u16,
+                    |i, word| {
+                        if let Some(r) = read.get_mut(i) {
+                            *r = word as $type;
+                        }
+                    },
+                )
+                .await;
+                Ok(())
+            }
+
+            async fn transfer_in_place(&mut self, words: &mut [$type]) -> Result<(), Self::Error> {
+                let words = Cell::from_mut(words).as_slice_of_cells();
+                transfer(
+                    &self.device,
+                    words.len(),
+                    |i| words[i].get() as u16,
+                    |i, word| words[i].set(word as $type),
+                )
+                .await;
+                Ok(())
+            }
+
+            /// The other methods only return once every word was received, so this only waits
+            /// for the busy flag to clear right after the last frame.
+            async fn flush(&mut self) -> Result<(), Self::Error> {
+                while self.is_busy() {}
+                Ok(())
+            }
+        }
+        )+
+
+    };
+}
+
+impl_async!(u8, [4, 5, 6, 7, 8]);
+impl_async!(u16, [9, 10, 11, 12, 13, 14, 15, 16]);
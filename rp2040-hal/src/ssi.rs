@@ -1,3 +1,290 @@
 //! Synchronous Serial Interface (SSI)
-// See [Chapter 4 Section 10](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) for more details
-// TODO
+//!
+//! The SSI drives the QSPI bus to the external flash, and is configured by the second stage
+//! bootloader to execute code in place. The bootloader uses a conservative clock divider, as it
+//! runs before the system clock is configured; once `clk_sys` is known, [`Ssi`] can change the
+//! divider to access the flash faster.
+//!
+//! [`Ssi::set_read_mode`] replaces the read command and bus width configured by the second
+//! stage bootloader, e.g. to use a faster command than a generic bootloader does, or a command
+//! which supports a higher clock frequency.
+//!
+//! The functions of the [`flash`](crate::flash) module re-run the second stage bootloader to
+//! return to XIP mode, which restores its divider and read command.
+//!
+//! See [Chapter 4 Section 10](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) of the datasheet for more details
+//!
+//! ## Usage
+//! ```no_run
+//! use fugit::RateExtU32;
+//! use rp2040_hal::{pac, ssi::Ssi};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut ssi = Ssi::new(pac.XIP_SSI);
+//! // clk_sys runs at 125 MHz, the flash supports up to 133 MHz.
+//! let sck = unsafe { ssi.set_max_frequency(125.MHz(), 133.MHz()) }.unwrap();
+//! ```
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, ssi::{ReadMode, Ssi}};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut ssi = Ssi::new(pac.XIP_SSI);
+//! // The flash chip supports the dual output fast read command.
+//! unsafe { ssi.set_read_mode(ReadMode::DualOutput) };
+//! ```
+
+use fugit::HertzU32;
+
+use crate::{pac::XIP_SSI, rom_data};
+
+/// Smallest clock divider supported by the SSI.
+pub const MIN_CLOCK_DIVIDER: u8 = 2;
+
+/// Errors for SSI configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The clock divider is odd or below [`MIN_CLOCK_DIVIDER`].
+    InvalidDivider,
+}
+
+/// Command used to read the flash in XIP mode.
+///
+/// All of them send the command and a 24-bit address on one data line; they differ in the
+/// number of lines the data is read on and in the dummy cycles before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadMode {
+    /// Read Data (`0x03`), without dummy cycles. Supported by every flash chip, but usually
+    /// limited to a lower clock frequency than the other commands.
+    Standard,
+    /// Fast Read (`0x0b`), with 8 dummy cycles.
+    Fast,
+    /// Fast Read Dual Output (`0x3b`), reading the data on 2 lines after 8 dummy cycles.
+    DualOutput,
+    /// Fast Read Quad Output (`0x6b`), reading the data on 4 lines after 8 dummy cycles.
+    ///
+    /// Most flash chips only support this once the Quad Enable bit of their status register
+    /// is set.
+    QuadOutput,
+}
+
+impl ReadMode {
+    /// Value of the `CTRLR0` register: frame format, 32-bit frames and EEPROM read mode.
+    fn ctrlr0(self) -> u32 {
+        let frame_format = match self {
+            ReadMode::Standard | ReadMode::Fast => 0,
+            ReadMode::DualOutput => 1,
+            ReadMode::QuadOutput => 2,
+        };
+        frame_format << 21 | 31 << 16 | 3 << 8
+    }
+
+    /// Value of the `SPI_CTRLR0` register: command, dummy cycles, 8-bit instruction and 24-bit
+    /// address, both sent on one line.
+    fn spi_ctrlr0(self) -> u32 {
+        let (command, wait_cycles) = match self {
+            ReadMode::Standard => (0x03, 0),
+            ReadMode::Fast => (0x0b, 8),
+            ReadMode::DualOutput => (0x3b, 8),
+            ReadMode::QuadOutput => (0x6b, 8),
+        };
+        command << 24 | wait_cycles << 11 | 2 << 8 | 6 << 2
+    }
+}
+
+/// SSI peripheral, used for execute-in-place access to the flash
+pub struct Ssi {
+    ssi: XIP_SSI,
+}
+
+impl Ssi {
+    /// Create a new [`Ssi`]
+    pub fn new(ssi: XIP_SSI) -> Self {
+        Self { ssi }
+    }
+
+    /// The current divider from `clk_sys` to the flash clock.
+    pub fn clock_divider(&self) -> u8 {
+        self.ssi.baudr.read().sckdv().bits() as u8
+    }
+
+    /// Change the divider from `clk_sys` to the flash clock.
+    ///
+    /// The divider must be even and at least [`MIN_CLOCK_DIVIDER`].
+    ///
+    /// # Safety
+    ///
+    /// The SSI is briefly disabled, so nothing else may access the flash while this runs: the
+    /// same requirements as for [`flash::erase`](crate::flash::erase) apply. The flash chip must
+    /// support the resulting clock frequency.
+    pub unsafe fn set_clock_divider(&mut self, divider: u8) -> Result<(), Error> {
+        if divider < MIN_CLOCK_DIVIDER || divider % 2 != 0 {
+            return Err(Error::InvalidDivider);
+        }
+        let ssi: *const _ = &*self.ssi;
+        #[cfg(target_arch = "arm")]
+        debug_assert!(crate::is_in_ram(
+            set_divider_in_ram as unsafe fn(usize, u32) as usize
+        ));
+        cortex_m::interrupt::free(|_| set_divider_in_ram(ssi as usize, divider as u32));
+        Ok(())
+    }
+
+    /// Use the smallest divider for which the flash clock does not exceed `max`.
+    ///
+    /// Returns the resulting flash clock frequency.
+    ///
+    /// # Safety
+    ///
+    /// See [`set_clock_divider`](Self::set_clock_divider).
+    pub unsafe fn set_max_frequency(
+        &mut self,
+        clk_sys: HertzU32,
+        max: HertzU32,
+    ) -> Result<HertzU32, Error> {
+        let divider =
+            clock_divider_for(clk_sys.to_Hz(), max.to_Hz()).ok_or(Error::InvalidDivider)?;
+        self.set_clock_divider(divider)?;
+        Ok(HertzU32::from_raw(clk_sys.to_Hz() / divider as u32))
+    }
+
+    /// Change the command used to read the flash, keeping the current clock divider.
+    ///
+    /// The flash is first taken out of any continuous read mode the second stage bootloader may
+    /// have set up, so this works with every bootloader.
+    ///
+    /// # Safety
+    ///
+    /// See [`set_clock_divider`](Self::set_clock_divider). The flash chip must support the
+    /// command at the current clock frequency.
+    pub unsafe fn set_read_mode(&mut self, mode: ReadMode) {
+        let sequence = ReadModeSequence {
+            connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+            flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+            flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+            ssi: &*self.ssi as *const _ as usize,
+            baudr: self.ssi.baudr.read().bits(),
+            ctrlr0: mode.ctrlr0(),
+            spi_ctrlr0: mode.spi_ctrlr0(),
+        };
+        #[cfg(target_arch = "arm")]
+        debug_assert!(crate::is_in_ram(
+            set_read_mode_in_ram as unsafe fn(&ReadModeSequence) as usize
+        ));
+        cortex_m::interrupt::free(|_| set_read_mode_in_ram(&sequence));
+    }
+
+    /// Release the underlying device.
+    pub fn free(self) -> XIP_SSI {
+        self.ssi
+    }
+}
+
+/// Smallest valid divider bringing `clk_sys` down to at most `max`.
+fn clock_divider_for(clk_sys: u32, max: u32) -> Option<u8> {
+    if max == 0 {
+        return None;
+    }
+    let divider = clk_sys / max + (clk_sys % max != 0) as u32;
+    let divider = divider.max(MIN_CLOCK_DIVIDER as u32);
+    let divider = divider + divider % 2;
+    u8::try_from(divider).ok()
+}
+
+/// Write the new divider to `BAUDR`, which requires disabling the SSI.
+///
+/// This must not access the flash in any way until the SSI is enabled again, see
+/// [`flash`](crate::flash) for why it is written in assembly.
+#[crate::ram_function]
+#[allow(unused_variables)]
+unsafe fn set_divider_in_ram(ssi: usize, divider: u32) {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "1:",
+        "ldr r2, [r0, #0x28]", // SR
+        "lsrs r2, r2, #1", // BUSY into carry
+        "bcs 1b",
+        "movs r2, #0",
+        "str r2, [r0, #0x08]", // SSIENR = 0
+        "str r1, [r0, #0x14]", // BAUDR = divider
+        "movs r2, #1",
+        "str r2, [r0, #0x08]", // SSIENR = 1
+        in("r0") ssi,
+        in("r1") divider,
+        out("r2") _,
+    );
+}
+
+/// Everything needed while the flash is not accessible.
+#[repr(C)]
+struct ReadModeSequence {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_flush_cache: unsafe extern "C" fn(),
+    ssi: usize,
+    baudr: u32,
+    ctrlr0: u32,
+    spi_ctrlr0: u32,
+}
+
+/// Leave XIP mode through the bootrom, and enter it again with the new read command.
+///
+/// The bootrom functions reconfigure the SSI for serial access, so the divider is restored along
+/// with the new configuration.
+/// This must not access the flash in any way until the SSI is enabled again, see
+/// [`flash`](crate::flash) for why it is written in assembly.
+#[crate::ram_function]
+#[allow(unused_variables)]
+unsafe fn set_read_mode_in_ram(sequence: &ReadModeSequence) {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "ldr r4, [r5, #0]",
+        "blx r4", // connect_internal_flash()
+        "ldr r4, [r5, #4]",
+        "blx r4", // flash_exit_xip()
+        "ldr r4, [r5, #8]",
+        "blx r4", // flash_flush_cache()
+        "ldr r0, [r5, #12]", // SSI
+        "movs r1, #0",
+        "str r1, [r0, #0x08]", // SSIENR = 0
+        "ldr r1, [r5, #16]",
+        "str r1, [r0, #0x14]", // BAUDR
+        "ldr r1, [r5, #20]",
+        "str r1, [r0, #0x00]", // CTRLR0
+        "movs r1, #0",
+        "str r1, [r0, #0x04]", // CTRLR1 = 0, a single frame per read
+        "ldr r1, [r5, #24]",
+        "movs r2, #0xf4",
+        "str r1, [r0, r2]", // SPI_CTRLR0
+        "movs r1, #1",
+        "str r1, [r0, #0x08]", // SSIENR = 1
+        // r5 is callee-saved, so it still holds `sequence` after each call.
+        in("r5") sequence,
+        out("r4") _,
+        clobber_abi("C"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_divider_for() {
+        assert_eq!(clock_divider_for(125_000_000, 133_000_000), Some(2));
+        assert_eq!(clock_divider_for(125_000_000, 50_000_000), Some(4));
+        assert_eq!(clock_divider_for(125_000_000, 62_500_000), Some(2));
+        assert_eq!(clock_divider_for(125_000_000, 40_000_000), Some(4));
+        assert_eq!(clock_divider_for(125_000_000, 400_000), None);
+        assert_eq!(clock_divider_for(125_000_000, 0), None);
+    }
+
+    #[test]
+    fn test_read_mode_registers() {
+        // The values written by the generic 0x03 second stage bootloader.
+        assert_eq!(ReadMode::Standard.ctrlr0(), 0x001f_0300);
+        assert_eq!(ReadMode::Standard.spi_ctrlr0(), 0x0300_0218);
+        assert_eq!(ReadMode::QuadOutput.ctrlr0(), 0x005f_0300);
+        assert_eq!(ReadMode::QuadOutput.spi_ctrlr0(), 0x6b00_4218);
+    }
+}
@@ -0,0 +1,155 @@
+//! MPU stack guards
+//!
+//! The stacks grow down into other data: the stack of core0 into `.bss` and `.data`, and the
+//! stack of core1 into whatever precedes its [`Stack`](crate::multicore::Stack). A stack guard
+//! uses MPU region 0 to make the 32 bytes at the bottom of a stack inaccessible, so that an
+//! overflow raises a HardFault right away instead of silently corrupting memory.
+//!
+//! Each core has its own MPU, so a guard only protects the core which installed it.
+//! [`Core::spawn`](crate::multicore::Core::spawn) installs a guard at the bottom of the stack of
+//! core1, and `install_core0` does the same for the stack of core0. The guard can be queried
+//! and removed by the core which owns it, e.g. before handing the MPU to an RTOS:
+//!
+//! ```no_run
+//! use rp2040_hal::stack_guard;
+//! if let Some(guard) = stack_guard::guarded_range() {
+//!     // The 32 bytes in `guard` are inaccessible.
+//! }
+//! stack_guard::remove();
+//! ```
+
+use core::ops::Range;
+
+use cortex_m::peripheral::MPU;
+
+/// Errors when installing a stack guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The MPU of this core is already enabled, e.g. by another stack guard.
+    MpuInUse,
+}
+
+/// Size of the guarded area, the smallest subregion of the MPU.
+pub const GUARD_SIZE: usize = 32;
+
+const CTRL_ENABLE: u32 = 1;
+/// Use the default memory map outside of the regions.
+const CTRL_PRIVDEFENA: u32 = 1 << 2;
+const RBAR_VALID: u32 = 1 << 4;
+const RASR_ENABLE: u32 = 1;
+/// Size of 2^(7 + 1) = 256 bytes, 8 subregions of 32 bytes.
+const RASR_SIZE_256: u32 = 7 << 1;
+/// Disable instruction fetch. No access permission bits means no access at all.
+const RASR_XN: u32 = 1 << 28;
+
+/// The RBAR and RASR values of region 0 guarding the 32 bytes from `addr`.
+fn region_for(addr: u32) -> (u32, u32) {
+    // Only keep the subregion containing `addr` enabled.
+    let subregion_disable = 0xff ^ (1 << ((addr >> 5) & 7));
+    (
+        (addr & !0xff) | RBAR_VALID,
+        RASR_ENABLE | RASR_SIZE_256 | (subregion_disable << 8) | RASR_XN,
+    )
+}
+
+/// The start of the area guarded by region 0, if it is configured as a stack guard.
+fn guard_start(rbar: u32, rasr: u32) -> Option<u32> {
+    let subregion_disable = (rasr >> 8) & 0xff;
+    let enabled = !subregion_disable & 0xff;
+    if rasr & !(0xff << 8) != RASR_ENABLE | RASR_SIZE_256 | RASR_XN || enabled.count_ones() != 1 {
+        return None;
+    }
+    Some((rbar & !0xff) + enabled.trailing_zeros() * GUARD_SIZE as u32)
+}
+
+/// Install a stack guard on the current core, for the stack whose lowest address is
+/// `stack_limit`.
+///
+/// The guard covers [`GUARD_SIZE`] bytes, starting at `stack_limit` rounded up to a multiple
+/// of 32 bytes, which just shortens the usable stack a little.
+///
+/// Returns [`Error::MpuInUse`] if the MPU of this core is already enabled.
+///
+/// # Safety
+///
+/// Nothing but the stack of the current core may be located in the guarded area, as any other
+/// access to it will fault.
+pub unsafe fn install(stack_limit: *const usize) -> Result<(), Error> {
+    let mpu = &*MPU::PTR;
+    if mpu.ctrl.read() != 0 {
+        return Err(Error::MpuInUse);
+    }
+    let addr = (stack_limit as u32 + 31) & !31;
+    let (rbar, rasr) = region_for(addr);
+    mpu.rbar.write(rbar);
+    mpu.rasr.write(rasr);
+    mpu.ctrl.write(CTRL_ENABLE | CTRL_PRIVDEFENA);
+    cortex_m::asm::dsb();
+    cortex_m::asm::isb();
+    Ok(())
+}
+
+/// Install a stack guard on core0, at the bottom of the stack defined by the cortex-m-rt
+/// linker script.
+///
+/// This guards the 32 bytes following `.bss` and `.uninit`, which must not be used by a heap.
+///
+/// # Safety
+///
+/// This must be called from core0, and no heap may start at `_stack_end`.
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+pub unsafe fn install_core0() -> Result<(), Error> {
+    extern "C" {
+        static mut _stack_end: usize;
+    }
+    install(core::ptr::addr_of!(_stack_end))
+}
+
+/// The area protected by the stack guard of the current core, if one is installed.
+pub fn guarded_range() -> Option<Range<usize>> {
+    // Safety: only reads the registers of region 0. The other cores do not have access to the
+    // MPU of this core, and interrupts must not reconfigure the MPU.
+    unsafe {
+        let mpu = &*MPU::PTR;
+        if mpu.ctrl.read() & CTRL_ENABLE == 0 {
+            return None;
+        }
+        mpu.rnr.write(0);
+        let start = guard_start(mpu.rbar.read(), mpu.rasr.read())? as usize;
+        Some(start..start + GUARD_SIZE)
+    }
+}
+
+/// Remove the stack guard of the current core, disabling its MPU.
+///
+/// This does nothing if no guard is installed, so that an MPU configured for something else is
+/// left alone.
+pub fn remove() {
+    if guarded_range().is_none() {
+        return;
+    }
+    // Safety: disabling the MPU only allows more accesses.
+    unsafe {
+        let mpu = &*MPU::PTR;
+        mpu.ctrl.write(0);
+        mpu.rnr.write(0);
+        mpu.rasr.write(0);
+    }
+    cortex_m::asm::dsb();
+    cortex_m::asm::isb();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_round_trip() {
+        for addr in [0x2000_0000, 0x2000_1020, 0x2003_ffe0] {
+            let (rbar, rasr) = region_for(addr);
+            assert_eq!(guard_start(rbar, rasr), Some(addr));
+        }
+        assert_eq!(guard_start(0, 0), None);
+    }
+}
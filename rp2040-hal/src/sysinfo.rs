@@ -0,0 +1,78 @@
+//! Chip and board identity
+//!
+//! [`DeviceInfo`] gathers the identification registers of the SYSINFO block, the chip revision
+//! reported by the bootrom and the unique ID of the flash chip, for logging the hardware
+//! revision or deriving a per-device identifier.
+//!
+//! ```no_run
+//! use rp2040_hal::{pac, sysinfo::DeviceInfo};
+//! let peripherals = pac::Peripherals::take().unwrap();
+//! // Safety: nothing else accesses the flash at this point.
+//! let info = unsafe { DeviceInfo::read(&peripherals.SYSINFO) };
+//! let serial_number = info.flash_unique_id;
+//! ```
+
+use crate::{
+    flash,
+    pac::SYSINFO,
+    rom_data::{self, ChipRevision},
+};
+
+/// Content of the `CHIP_ID` register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChipId {
+    /// JEDEC JEP-106 manufacturer ID, `0x927` for Raspberry Pi.
+    pub manufacturer: u16,
+    /// Part number, `0x2` for the RP2040.
+    pub part: u16,
+    /// Silicon revision.
+    pub revision: u8,
+}
+
+impl ChipId {
+    /// Read the `CHIP_ID` register.
+    pub fn read(sysinfo: &SYSINFO) -> Self {
+        let chip_id = sysinfo.chip_id.read();
+        ChipId {
+            manufacturer: chip_id.manufacturer().bits(),
+            part: chip_id.part().bits(),
+            revision: chip_id.revision().bits(),
+        }
+    }
+}
+
+/// Identity of the chip and of the board's flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceInfo {
+    /// Content of the `CHIP_ID` register.
+    pub chip_id: ChipId,
+    /// Revision of the chip, as identified by its bootrom.
+    pub revision: ChipRevision,
+    /// Git hash of the chip source, from the `GITREF_RP2040` register.
+    pub gitref: u32,
+    /// Whether this is running on an FPGA rather than on an RP2040.
+    pub is_fpga: bool,
+    /// The 64-bit unique ID of the flash chip, see [`flash::unique_id`].
+    ///
+    /// The RP2040 has no unique ID of its own, so this is the usual per-device identifier.
+    pub flash_unique_id: u64,
+}
+
+impl DeviceInfo {
+    /// Read the identity of the chip and of the flash.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may access the flash while this runs, see [`flash::unique_id`].
+    pub unsafe fn read(sysinfo: &SYSINFO) -> Self {
+        DeviceInfo {
+            chip_id: ChipId::read(sysinfo),
+            revision: rom_data::chip_revision(),
+            gitref: sysinfo.gitref_rp2040.read().bits(),
+            is_fpga: sysinfo.platform.read().fpga().bit_is_set(),
+            flash_unique_id: flash::unique_id(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! SysTick based delays and cycle counting
+//!
+//! The Cortex-M0+ SysTick counts down processor clock cycles. [`SysTickDelay`] uses it to
+//! implement the delay traits, leaving the [`Timer`](crate::Timer) free for alarms, and
+//! [`CycleCounter`] measures the cycles taken by some code, as the Cortex-M0+ has no DWT cycle
+//! counter.
+//!
+//! Each of them takes ownership of the SysTick, which is left free running: the counter wraps
+//! every 2^24 cycles, about 134 ms at 125 MHz.
+//!
+//! ```no_run
+//! use embedded_hal::blocking::delay::DelayMs;
+//! use rp2040_hal::systick::SysTickDelay;
+//! let core = rp2040_hal::pac::CorePeripherals::take().unwrap();
+//! let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+//! let mut delay = SysTickDelay::new(core.SYST, &clocks);
+//! delay.delay_ms(500u32);
+//! ```
+
+use cortex_m::peripheral::{syst::SystClkSource, SYST};
+use fugit::HertzU32;
+
+use crate::clocks::{Clock, ClocksManager};
+
+/// The SysTick counter is 24 bits wide.
+const SYST_MASK: u32 = 0x00ff_ffff;
+
+fn start_free_running(syst: &mut SYST) {
+    syst.disable_interrupt();
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(SYST_MASK);
+    syst.clear_current();
+    syst.enable_counter();
+}
+
+/// Busy wait for `cycles` processor clock cycles.
+fn wait_cycles(mut cycles: u64) {
+    let mut last = SYST::get_current();
+    loop {
+        let now = SYST::get_current();
+        // The counter counts down.
+        let elapsed = u64::from(last.wrapping_sub(now) & SYST_MASK);
+        if elapsed >= cycles {
+            break;
+        }
+        cycles -= elapsed;
+        last = now;
+    }
+}
+
+/// Number of `freq` cycles lasting at least `time` units of `1 / per_second` seconds.
+fn cycles_for(time: u64, per_second: u64, freq: HertzU32) -> u64 {
+    (time * u64::from(freq.to_Hz()) + per_second - 1) / per_second
+}
+
+/// Delays based on the SysTick, counting cycles of clk_sys.
+pub struct SysTickDelay {
+    syst: SYST,
+    sys_freq: HertzU32,
+}
+
+impl SysTickDelay {
+    /// Create a new [`SysTickDelay`]
+    ///
+    /// The delays are computed from the current frequency of clk_sys, so this must be created
+    /// again if the system clock changes.
+    pub fn new(mut syst: SYST, clocks: &ClocksManager) -> Self {
+        start_free_running(&mut syst);
+        Self {
+            syst,
+            sys_freq: clocks.system_clock.freq(),
+        }
+    }
+
+    /// Releases the SysTick
+    pub fn free(mut self) -> SYST {
+        self.syst.disable_counter();
+        self.syst
+    }
+
+    fn delay_us_internal(&mut self, us: u32) {
+        wait_cycles(cycles_for(u64::from(us), 1_000_000, self.sys_freq));
+    }
+}
+
+macro_rules! impl_delay_traits {
+    ($($t:ty),+) => {
+        $(
+        impl embedded_hal::blocking::delay::DelayUs<$t> for SysTickDelay {
+            fn delay_us(&mut self, us: $t) {
+                #![allow(unused_comparisons)]
+                assert!(us >= 0); // Only meaningful for i32
+                self.delay_us_internal(us as u32)
+            }
+        }
+        impl embedded_hal::blocking::delay::DelayMs<$t> for SysTickDelay {
+            fn delay_ms(&mut self, ms: $t) {
+                #![allow(unused_comparisons)]
+                assert!(ms >= 0); // Only meaningful for i32
+                for _ in 0..ms {
+                    self.delay_us_internal(1000);
+                }
+            }
+        }
+        )*
+    }
+}
+
+// The implementation for i32 is a workaround to allow `delay_ms(42)` construction without specifying a type.
+impl_delay_traits!(u8, u16, u32, i32);
+
+#[cfg(feature = "eh1")]
+impl eh1_0::delay::DelayNs for SysTickDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        wait_cycles(cycles_for(u64::from(ns), 1_000_000_000, self.sys_freq));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.delay_us_internal(us)
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        for _ in 0..ms {
+            self.delay_us_internal(1000);
+        }
+    }
+}
+
+/// Measures the processor clock cycles taken by some code.
+///
+/// The cost of reading the counter is measured once when creating the counter and subtracted
+/// from each measurement. Measurements are limited to 2^24 cycles.
+///
+/// ```no_run
+/// use rp2040_hal::systick::CycleCounter;
+/// let core = rp2040_hal::pac::CorePeripherals::take().unwrap();
+/// let mut counter = CycleCounter::new(core.SYST);
+/// let (sum, cycles) = counter.measure(|| (0..100u32).sum::<u32>());
+/// ```
+pub struct CycleCounter {
+    syst: SYST,
+    overhead: u32,
+}
+
+impl CycleCounter {
+    /// Create a new [`CycleCounter`]
+    pub fn new(mut syst: SYST) -> Self {
+        start_free_running(&mut syst);
+        let mut counter = Self { syst, overhead: 0 };
+        let (_, overhead) = counter.measure(|| ());
+        counter.overhead = overhead;
+        counter
+    }
+
+    /// Releases the SysTick
+    pub fn free(mut self) -> SYST {
+        self.syst.disable_counter();
+        self.syst
+    }
+
+    /// The measurement overhead subtracted from each measurement, in cycles.
+    pub fn overhead(&self) -> u32 {
+        self.overhead
+    }
+
+    /// Run `f`, returning its result and the number of cycles it took.
+    ///
+    /// The result is wrong if `f` takes more than 2^24 cycles, or is interrupted by code
+    /// taking that long.
+    #[inline(always)]
+    pub fn measure<R>(&mut self, f: impl FnOnce() -> R) -> (R, u32) {
+        let start = SYST::get_current();
+        let result = f();
+        let end = SYST::get_current();
+        let cycles = start.wrapping_sub(end) & SYST_MASK;
+        (result, cycles.saturating_sub(self.overhead))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cycles_for;
+    use fugit::HertzU32;
+
+    #[test]
+    fn delays_round_up() {
+        assert_eq!(cycles_for(1, 1_000_000, HertzU32::MHz(125)), 125);
+        // 133.33 MHz: 1000 us are 133_333.33 cycles
+        assert_eq!(
+            cycles_for(1000, 1_000_000, HertzU32::Hz(133_333_333)),
+            133_334
+        );
+        // Below 1 MHz, short delays still wait for a cycle
+        assert_eq!(cycles_for(1, 1_000_000, HertzU32::kHz(32)), 1);
+        assert_eq!(cycles_for(0, 1_000_000, HertzU32::kHz(32)), 0);
+    }
+}
@@ -6,6 +6,21 @@
 //!
 //! Each of the 4 alarms can match on the lower 32 bits of Counter and trigger an interrupt.
 //!
+//! ```no_run
+//! use fugit::ExtU32;
+//! use rp2040_hal::{pac, timer::Alarm, Timer};
+//! let mut peripherals = pac::Peripherals::take().unwrap();
+//! let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+//! let mut timer = Timer::new(peripherals.TIMER, &mut peripherals.RESETS, &clocks);
+//! let mut alarm = timer.alarm_0().unwrap();
+//! // Raise TIMER_IRQ_0 in 10ms, whose handler must call `alarm.clear_interrupt()`
+//! alarm.schedule(10.millis()).unwrap();
+//! alarm.enable_interrupt();
+//! unsafe { pac::NVIC::unmask(alarm.interrupt()) };
+//! // Changed our mind
+//! alarm.cancel().unwrap();
+//! ```
+//!
 //! See [Chapter 4 Section 6](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) of the datasheet for more details.
 
 use core::sync::atomic::{AtomicU8, Ordering};
@@ -16,6 +31,7 @@ use crate::{
     clocks::ClocksManager,
     pac::{self, RESETS, TIMER},
     resets::SubsystemReset,
+    sio::CoreId,
     typelevel::Sealed,
 };
 
@@ -43,6 +59,9 @@ fn release_alarm(mask: u8) {
 // As after initialization all accesses are read-only anyways, the `pac::TIMER` can
 // be summoned unsafely instead. This allows timer to be cloned.
 //
+// (The debug pause configuration is the exception, but it is a plain setting which
+// any copy may change.)
+//
 // (Alarms do use write operations, but they are local to the respective alarm, and
 // those are still owned singletons.)
 //
@@ -87,6 +106,30 @@ impl Timer {
         unsafe { &*pac::TIMER::PTR }.timerawl.read().bits()
     }
 
+    /// Defines whether the counter is paused while `core` is halted by a debugger.
+    ///
+    /// The counter is paused while either core is halted by default, which keeps the time
+    /// seen by the program consistent while single stepping, but makes the timings differ
+    /// from a run without debugger.
+    pub fn pause_on_debug(&mut self, core: CoreId, pause: bool) {
+        // Safety: DBGPAUSE is only written here, and the bits of the other core are kept
+        let timer = unsafe { &*pac::TIMER::PTR };
+        timer.dbgpause.modify(|_, w| match core {
+            CoreId::Core0 => w.dbg0().bit(pause),
+            CoreId::Core1 => w.dbg1().bit(pause),
+        });
+    }
+
+    /// Whether the counter is paused while `core` is halted by a debugger.
+    pub fn is_paused_on_debug(&self, core: CoreId) -> bool {
+        // Safety: Only used for reading
+        let dbgpause = unsafe { &*pac::TIMER::PTR }.dbgpause.read();
+        match core {
+            CoreId::Core0 => dbgpause.dbg0().bit_is_set(),
+            CoreId::Core1 => dbgpause.dbg1().bit_is_set(),
+        }
+    }
+
     /// Initialized a Count Down instance without starting it.
     pub fn count_down(&self) -> CountDown<'_> {
         CountDown {
@@ -166,15 +209,32 @@ macro_rules! impl_delay_traits {
 // The implementation for i32 is a workaround to allow `delay_ms(42)` construction without specifying a type.
 impl_delay_traits!(u8, u16, u32, i32);
 
-#[cfg(feature = "eh1_0_alpha")]
-impl eh1_0_alpha::delay::DelayUs for Timer {
+#[cfg(feature = "eh1")]
+impl eh1_0::delay::DelayNs for Timer {
+    fn delay_ns(&mut self, ns: u32) {
+        // The timer ticks every microsecond: round up to whole ticks.
+        self.delay_us_internal(ns / 1000 + u32::from(ns % 1000 != 0))
+    }
+
     fn delay_us(&mut self, us: u32) {
         self.delay_us_internal(us)
     }
+
+    fn delay_ms(&mut self, ms: u32) {
+        for _ in 0..ms {
+            self.delay_us_internal(1000);
+        }
+    }
 }
 
 /// Implementation of the embedded_hal::Timer traits using rp2040_hal::timer counter
 ///
+/// The count down is periodic: once `wait` returns, the next period starts at the end of the
+/// previous one rather than at the call to `wait`, so that the periods do not drift. If `wait`
+/// is called late, the missed periods are reported by the following calls.
+///
+/// `wait` panics if the count down is not started.
+///
 /// ## Usage
 /// ```no_run
 /// use embedded_hal::timer::{CountDown, Cancel};
@@ -284,12 +344,23 @@ pub trait Alarm: Sealed {
     /// has not been scheduled yet.
     fn finished(&self) -> bool;
 
-    /// Cancel an activated alarm.
+    /// Cancel an activated alarm, clearing its interrupt if it already fired.
     fn cancel(&mut self) -> Result<(), ScheduleAlarmError>;
+
+    /// The interrupt raised by this alarm, which must be unmasked in the NVIC to be handled.
+    fn interrupt(&self) -> pac::Interrupt;
 }
 
+#[cfg(feature = "async")]
+static ALARM_WAKERS: [crate::async_utils::WakerSlot; 4] = [
+    crate::async_utils::WakerSlot::new(),
+    crate::async_utils::WakerSlot::new(),
+    crate::async_utils::WakerSlot::new(),
+    crate::async_utils::WakerSlot::new(),
+];
+
 macro_rules! impl_alarm {
-    ($name:ident  { rb: $timer_alarm:ident, int: $int_alarm:ident, int_name: $int_name:tt, armed_bit_mask: $armed_bit_mask: expr }) => {
+    ($name:ident  { rb: $timer_alarm:ident, int: $int_alarm:ident, int_name: $int_name:tt, irq: $irq:ident, armed_bit_mask: $armed_bit_mask: expr }) => {
         /// An alarm that can be used to schedule events in the future. Alarms can also be configured to trigger interrupts.
         pub struct $name(Timer);
         impl $name {
@@ -325,6 +396,85 @@ macro_rules! impl_alarm {
             }
         }
 
+        #[cfg(feature = "async")]
+        impl $name {
+            /// Wait until the alarm is finished.
+            ///
+            /// The interrupt handler of `
+            #[doc = $int_name]
+            /// ` must call [`Self::on_interrupt`]. The interrupt flag is cleared before waiting.
+            pub async fn wait(&mut self) {
+                self.clear_interrupt();
+                let waker = &ALARM_WAKERS[($armed_bit_mask as u32).trailing_zeros() as usize];
+                crate::async_utils::wait_for(
+                    waker,
+                    || self.finished().then_some(()),
+                    // Safety: using the atomic set alias, only this alarm interacts with this bit
+                    // of the TIMER.inte register
+                    || unsafe {
+                        write_bitmask_set((*pac::TIMER::ptr()).inte.as_ptr(), $armed_bit_mask)
+                    },
+                )
+                .await
+            }
+
+            /// Schedule the alarm to be finished after `countdown` and wait until it is.
+            ///
+            /// See [`Self::wait`].
+            pub async fn delay(
+                &mut self,
+                countdown: MicrosDurationU32,
+            ) -> Result<(), ScheduleAlarmError> {
+                self.schedule(countdown)?;
+                self.wait().await;
+                Ok(())
+            }
+
+            /// Wait for `us` microseconds, split in delays the alarm can schedule.
+            async fn delay_us_async(&mut self, us: u64) {
+                let mut remaining = us;
+                while remaining > 0 {
+                    let step = remaining.min(u64::from(u32::MAX)) as u32;
+                    // Cannot fail: the countdown fits in 32 bits.
+                    let _ = self.schedule(MicrosDurationU32::micros(step));
+                    self.wait().await;
+                    remaining -= u64::from(step);
+                }
+            }
+
+            /// Wake the task waiting for this alarm.
+            ///
+            /// Call this from the interrupt handler of `
+            #[doc = $int_name]
+            /// `. It disables and clears the interrupt.
+            pub fn on_interrupt() {
+                // Safety: using the atomic clear alias and the write-clear register, only this
+                // alarm interacts with these bits
+                unsafe {
+                    let timer = &*pac::TIMER::ptr();
+                    write_bitmask_clear(timer.inte.as_ptr(), $armed_bit_mask);
+                    timer.intr.write_with_zero(|w| w.bits($armed_bit_mask));
+                }
+                ALARM_WAKERS[($armed_bit_mask as u32).trailing_zeros() as usize].wake();
+            }
+        }
+
+        #[cfg(feature = "async")]
+        impl embedded_hal_async::delay::DelayNs for $name {
+            async fn delay_ns(&mut self, ns: u32) {
+                // The timer ticks every microsecond: round up to whole ticks.
+                self.delay_us_async((u64::from(ns) + 999) / 1000).await
+            }
+
+            async fn delay_us(&mut self, us: u32) {
+                self.delay_us_async(u64::from(us)).await
+            }
+
+            async fn delay_ms(&mut self, ms: u32) {
+                self.delay_us_async(u64::from(ms) * 1000).await
+            }
+        }
+
         impl Alarm for $name {
             /// Clear the interrupt flag. This should be called after interrupt `
             #[doc = $int_name]
@@ -419,6 +569,8 @@ macro_rules! impl_alarm {
             /// Cancel an activated Alarm. No negative effects if it's already disabled.
             /// Unlike `timer::cancel` trait, this only cancels the alarm and keeps the timer running
             /// if it's already active.
+            ///
+            /// If the alarm already fired, its pending interrupt is cleared as well.
             fn cancel(&mut self) -> Result<(), ScheduleAlarmError> {
                 unsafe {
                     let timer = &*TIMER::ptr();
@@ -427,10 +579,18 @@ macro_rules! impl_alarm {
                         timer.intf.as_ptr(),
                         $armed_bit_mask,
                     );
+                    timer.intr.write_with_zero(|w| w.bits($armed_bit_mask));
                 }
 
                 Ok(())
             }
+
+            /// The interrupt raised by this alarm, `
+            #[doc = $int_name]
+            /// `.
+            fn interrupt(&self) -> pac::Interrupt {
+                pac::Interrupt::$irq
+            }
         }
 
         impl Sealed for $name {}
@@ -446,6 +606,7 @@ macro_rules! impl_alarm {
 
 /// Errors that can be returned from any of the `AlarmX::schedule` methods.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ScheduleAlarmError {
     /// Alarm time is too high. Should not be more than `u32::max_value()` in the future.
     AlarmTooLate,
@@ -455,6 +616,7 @@ impl_alarm!(Alarm0 {
     rb: alarm0,
     int: alarm_0,
     int_name: "TIMER_IRQ_0",
+    irq: TIMER_IRQ_0,
     armed_bit_mask: 0b0001
 });
 
@@ -462,6 +624,7 @@ impl_alarm!(Alarm1 {
     rb: alarm1,
     int: alarm_1,
     int_name: "TIMER_IRQ_1",
+    irq: TIMER_IRQ_1,
     armed_bit_mask: 0b0010
 });
 
@@ -469,6 +632,7 @@ impl_alarm!(Alarm2 {
     rb: alarm2,
     int: alarm_2,
     int_name: "TIMER_IRQ_2",
+    irq: TIMER_IRQ_2,
     armed_bit_mask: 0b0100
 });
 
@@ -476,6 +640,7 @@ impl_alarm!(Alarm3 {
     rb: alarm3,
     int: alarm_3,
     int_name: "TIMER_IRQ_3",
+    irq: TIMER_IRQ_3,
     armed_bit_mask: 0b1000
 });
 
@@ -486,6 +651,25 @@ pub mod monotonic {
     use fugit::ExtU32;
 
     /// RTIC Monotonic Implementation
+    ///
+    /// The instants have a resolution of 1µs, taken from the 64-bit counter. Instants more
+    /// than `u32::MAX` microseconds away, which the 32-bit alarms cannot match, are reached
+    /// by rescheduling the alarm as many times as needed.
+    ///
+    /// The interrupt of the alarm must be bound to the monotonic, e.g. `TIMER_IRQ_0` for
+    /// [`Alarm0`](super::Alarm0):
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{pac, timer::{monotonic::Monotonic, Alarm0}, Timer};
+    /// // In the RTIC app: `#[monotonic(binds = TIMER_IRQ_0, default = true)]`
+    /// type MyMono = Monotonic<Alarm0>;
+    ///
+    /// let mut peripherals = pac::Peripherals::take().unwrap();
+    /// let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+    /// let mut timer = Timer::new(peripherals.TIMER, &mut peripherals.RESETS, &clocks);
+    /// let alarm = timer.alarm_0().unwrap();
+    /// let mono: MyMono = Monotonic::new(timer, alarm);
+    /// ```
     pub struct Monotonic<A>(pub Timer, A);
     impl<A: Alarm> Monotonic<A> {
         /// Creates a new monotonic.
@@ -522,6 +706,115 @@ pub mod monotonic {
             Instant::from_ticks(0)
         }
 
-        unsafe fn reset(&mut self) {}
+        unsafe fn reset(&mut self) {
+            // The counter cannot be reset, but drop any alarm left over from before a soft reset.
+            let _ = self.1.cancel();
+        }
+    }
+}
+
+/// Implementation of the `embassy-time` driver.
+///
+/// The driver reads the 64-bit counter, with a tick rate of 1MHz, and uses alarm 0 to wake the
+/// tasks. The timer queue is provided by `embassy-time-queue-utils`, configured through its
+/// features, like the `generic-queue-N` ones.
+///
+/// The timer must be started by [`Timer::new`], the alarm handed over to [`init`] and the
+/// interrupt handler of `TIMER_IRQ_0` must call [`on_interrupt`]:
+///
+/// ```no_run
+/// use rp2040_hal::{pac, pac::interrupt, timer::embassy, Timer};
+///
+/// let mut peripherals = pac::Peripherals::take().unwrap();
+/// let clocks: rp2040_hal::clocks::ClocksManager = todo!();
+/// let mut timer = Timer::new(peripherals.TIMER, &mut peripherals.RESETS, &clocks);
+/// embassy::init(timer.alarm_0().unwrap());
+/// unsafe { pac::NVIC::unmask(pac::Interrupt::TIMER_IRQ_0) };
+///
+/// #[interrupt]
+/// fn TIMER_IRQ_0() {
+///     embassy::on_interrupt();
+/// }
+/// ```
+#[cfg(feature = "embassy-time-driver")]
+pub mod embassy {
+    use core::cell::RefCell;
+    use core::task::Waker;
+
+    use critical_section::{CriticalSection, Mutex};
+    use embassy_time_queue_utils::Queue;
+
+    use super::{Alarm, Alarm0, Timer};
+    use crate::pac;
+
+    struct TimerDriver {
+        queue: Mutex<RefCell<Queue>>,
+    }
+
+    embassy_time_driver::time_driver_impl!(static DRIVER: TimerDriver = TimerDriver {
+        queue: Mutex::new(RefCell::new(Queue::new())),
+    });
+
+    impl TimerDriver {
+        /// Arm alarm 0 for `timestamp`, returning `false` if it is already reached.
+        ///
+        /// The alarm only matches the low word of the counter, so a timestamp more than
+        /// `u32::MAX` microseconds away fires early, and is rescheduled by `on_interrupt`.
+        fn set_alarm(&self, _cs: CriticalSection, timestamp: u64) -> bool {
+            // Safety: alarm 0 is owned by the driver since `init`
+            let timer = unsafe { &*pac::TIMER::ptr() };
+            timer.alarm0.write(|w| unsafe { w.bits(timestamp as u32) });
+            if timestamp <= embassy_time_driver::Driver::now(self) {
+                // Safety: TIMER.armed is a write-clear register
+                unsafe { timer.armed.write_with_zero(|w| w.bits(1)) };
+                false
+            } else {
+                true
+            }
+        }
+
+        fn wake_expired(&self, cs: CriticalSection) {
+            let mut queue = self.queue.borrow(cs).borrow_mut();
+            let mut next = queue.next_expiration(embassy_time_driver::Driver::now(self));
+            while !self.set_alarm(cs, next) {
+                next = queue.next_expiration(embassy_time_driver::Driver::now(self));
+            }
+        }
+    }
+
+    impl embassy_time_driver::Driver for TimerDriver {
+        fn now(&self) -> u64 {
+            Timer { _private: () }.get_counter().ticks()
+        }
+
+        fn schedule_wake(&self, at: u64, waker: &Waker) {
+            critical_section::with(|cs| {
+                let scheduled = self.queue.borrow(cs).borrow_mut().schedule_wake(at, waker);
+                if scheduled {
+                    self.wake_expired(cs);
+                }
+            })
+        }
+    }
+
+    /// Hand alarm 0 over to the driver, and enable its interrupt.
+    ///
+    /// The alarm is kept by the driver for the rest of the program.
+    pub fn init(mut alarm: Alarm0) {
+        alarm.enable_interrupt();
+        core::mem::forget(alarm);
+    }
+
+    /// Wake the tasks whose timers expired.
+    ///
+    /// Call this from the interrupt handler of `TIMER_IRQ_0`.
+    pub fn on_interrupt() {
+        // Safety: TIMER.intr is a write-clear register, and alarm 0 is owned by the driver
+        unsafe {
+            (*pac::TIMER::ptr())
+                .intr
+                .write_with_zero(|w| w.alarm_0().clear_bit_by_one());
+        }
+        critical_section::with(|cs| DRIVER.wake_expired(cs));
     }
 }
@@ -0,0 +1,181 @@
+//! Universal Asynchronous Receiver Transmitter - Async Code
+//!
+//! This module implements the `embedded-io-async` traits, waiting for the UART interrupts
+//! instead of polling. See [`async_utils`](crate::async_utils).
+
+use core::convert::Infallible;
+
+use embedded_io::ErrorKind;
+use nb::Error::{Other, WouldBlock};
+
+use super::{
+    reader::{is_readable, read_raw},
+    writer::{uart_is_writable, write_raw},
+    Enabled, ReadErrorType, Reader, UartDevice, UartPeripheral, ValidUartPinout, Writer,
+};
+use crate::{
+    async_utils::{wait_for, WakerSlot},
+    pac::{self, uart0::RegisterBlock},
+};
+
+static RX_WAKERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+static TX_WAKERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+
+/// Wake the tasks reading from or writing to the UART.
+///
+/// Call this from the `UARTx_IRQ` interrupt handler, e.g. `on_interrupt::<pac::UART0>()`. It
+/// disables the receive and transmit interrupts which fired, so the UART should not be used
+/// with other interrupt handling at the same time.
+pub fn on_interrupt<D: UartDevice>() {
+    // Safety: only the interrupt masks are modified, from the interrupt handler which cannot be
+    // preempted by the tasks modifying them.
+    let rb: &RegisterBlock = unsafe {
+        match D::ID {
+            0 => &*pac::UART0::ptr(),
+            _ => &*pac::UART1::ptr(),
+        }
+    };
+    let mis = rb.uartmis.read();
+    let rx = mis.rxmis().bit_is_set() || mis.rtmis().bit_is_set();
+    let tx = mis.txmis().bit_is_set();
+    rb.uartimsc.modify(|_, w| {
+        if rx {
+            w.rxim().clear_bit().rtim().clear_bit();
+        }
+        if tx {
+            w.txim().clear_bit();
+        }
+        w
+    });
+    if rx {
+        RX_WAKERS[D::ID].wake();
+    }
+    if tx {
+        TX_WAKERS[D::ID].wake();
+    }
+}
+
+impl embedded_io::Error for ReadErrorType {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ReadErrorType::Parity | ReadErrorType::Framing => ErrorKind::InvalidData,
+            ReadErrorType::Overrun | ReadErrorType::Break => ErrorKind::Other,
+        }
+    }
+}
+
+async fn read<D: UartDevice>(device: &D, buffer: &mut [u8]) -> Result<usize, ReadErrorType> {
+    if buffer.is_empty() {
+        return Ok(0);
+    }
+    loop {
+        wait_for(
+            &RX_WAKERS[D::ID],
+            || is_readable(device).then_some(()),
+            || {
+                critical_section::with(|_| {
+                    device
+                        .uartimsc
+                        .modify(|_, w| w.rxim().set_bit().rtim().set_bit())
+                })
+            },
+        )
+        .await;
+        match read_raw(device, buffer) {
+            Ok(bytes_read) => return Ok(bytes_read),
+            Err(Other(e)) => return Err(e.err_type),
+            Err(WouldBlock) => continue,
+        }
+    }
+}
+
+async fn write<D: UartDevice>(device: &D, data: &[u8]) -> usize {
+    if data.is_empty() {
+        return 0;
+    }
+    loop {
+        wait_for(
+            &TX_WAKERS[D::ID],
+            || uart_is_writable(device).then_some(()),
+            || critical_section::with(|_| device.uartimsc.modify(|_, w| w.txim().set_bit())),
+        )
+        .await;
+        match write_raw(device, data) {
+            Ok(remaining) => return data.len() - remaining.len(),
+            Err(_) => continue,
+        }
+    }
+}
+
+/// The UART raises no interrupt once the last bit is sent. The transmit interrupt is moved to
+/// its lowest trigger level meanwhile, so that the task sleeps until at most 4 characters are
+/// left in the FIFO, and only these and the shift register are then waited for on the busy flag.
+async fn flush<D: UartDevice>(device: &D) {
+    let txiflsel = device.uartifls.read().txiflsel().bits();
+    // 0 means '<= 1/8 full'.
+    device
+        .uartifls
+        .modify(|_, w| unsafe { w.txiflsel().bits(0) });
+    wait_for(
+        &TX_WAKERS[D::ID],
+        || device.uartris.read().txris().bit_is_set().then_some(()),
+        || critical_section::with(|_| device.uartimsc.modify(|_, w| w.txim().set_bit())),
+    )
+    .await;
+    device
+        .uartifls
+        .modify(|_, w| unsafe { w.txiflsel().bits(txiflsel) });
+    while device.uartfr.read().busy().bit_is_set() {}
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io::ErrorType
+    for UartPeripheral<Enabled, D, P>
+{
+    type Error = ReadErrorType;
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io_async::Read
+    for UartPeripheral<Enabled, D, P>
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        read(&self.device, buf).await
+    }
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io_async::Write
+    for UartPeripheral<Enabled, D, P>
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(write(&self.device, buf).await)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        flush(&self.device).await;
+        Ok(())
+    }
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io::ErrorType for Reader<D, P> {
+    type Error = ReadErrorType;
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io_async::Read for Reader<D, P> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        read(&self.device, buf).await
+    }
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io::ErrorType for Writer<D, P> {
+    type Error = Infallible;
+}
+
+impl<D: UartDevice, P: ValidUartPinout<D>> embedded_io_async::Write for Writer<D, P> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(write(&self.device, buf).await)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        flush(&self.device).await;
+        Ok(())
+    }
+}
@@ -32,12 +32,16 @@
 //! uart.write_full_blocking(b"Hello World!\r\n");
 //! ```
 
+#[cfg(feature = "async")]
+mod asynch;
 mod peripheral;
 mod pins;
 mod reader;
 mod utils;
 mod writer;
 
+#[cfg(feature = "async")]
+pub use asynch::on_interrupt;
 pub use peripheral::UartPeripheral;
 pub use pins::*;
 pub use reader::{ReadError, ReadErrorType, Reader};
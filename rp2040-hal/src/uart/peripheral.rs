@@ -7,6 +7,7 @@ use core::{convert::Infallible, fmt};
 use embedded_hal::serial::{Read, Write};
 use fugit::HertzU32;
 use nb::Error::{Other, WouldBlock};
+use rp_hal_common::uart::calculate_baudrate_dividers;
 
 use crate::{
     pac::{self, uart0::uartlcr_h::W as UART_LCR_H_Writer, Peripherals, UART0, UART1},
@@ -14,12 +15,12 @@ use crate::{
     uart::*,
 };
 
-#[cfg(feature = "eh1_0_alpha")]
-use eh_nb_1_0_alpha::serial as eh1nb;
+#[cfg(feature = "eh1")]
+use eh_nb_1_0::serial as eh1nb;
 
 /// An UART Peripheral based on an underlying UART device.
 pub struct UartPeripheral<S: State, D: UartDevice, P: ValidUartPinout<D>> {
-    device: D,
+    pub(super) device: D,
     _state: S,
     pins: P,
 }
@@ -109,6 +110,19 @@ impl<D: UartDevice, P: ValidUartPinout<D>> UartPeripheral<Enabled, D, P> {
         self.transition(Disabled)
     }
 
+    /// Change the baudrate, e.g. after the peripheral clock changed.
+    ///
+    /// `frequency` is the frequency of the peripheral clock. Returns the baudrate actually
+    /// achieved. Data being transferred while the baudrate changes is corrupted, so the TX FIFO
+    /// should be flushed first.
+    pub fn set_baudrate(
+        &mut self,
+        baudrate: HertzU32,
+        frequency: HertzU32,
+    ) -> Result<HertzU32, Error> {
+        configure_baudrate(&mut self.device, baudrate, frequency)
+    }
+
     /// Enable/disable the rx/tx FIFO
     ///
     /// Unfortunately, it's not possible to enable/disable rx/tx
@@ -247,30 +261,6 @@ impl<P: ValidUartPinout<UART1>> UartPeripheral<Enabled, UART1, P> {
     }
 }
 
-/// The PL011 (PrimeCell UART) supports a fractional baud rate divider
-/// From the wanted baudrate, we calculate the divider's two parts: integer and fractional parts.
-/// Code inspired from the C SDK.
-fn calculate_baudrate_dividers(
-    wanted_baudrate: HertzU32,
-    frequency: HertzU32,
-) -> Result<(u16, u16), Error> {
-    // See Chapter 4, Section 2 §7.1 from the datasheet for an explanation of how baudrate is
-    // calculated
-    let baudrate_div = frequency
-        .to_Hz()
-        .checked_mul(8)
-        .and_then(|r| r.checked_div(wanted_baudrate.to_Hz()))
-        .ok_or(Error::BadArgument)?;
-
-    Ok(match (baudrate_div >> 7, ((baudrate_div & 0x7F) + 1) / 2) {
-        (0, _) => (1, 0),
-
-        (int_part, _) if int_part >= 65535 => (65535, 0),
-
-        (int_part, frac_part) => (int_part as u16, frac_part as u16),
-    })
-}
-
 /// Baudrate configuration. Code loosely inspired from the C SDK.
 #[allow(unknown_lints)]
 #[allow(clippy::needless_pass_by_ref_mut)]
@@ -279,7 +269,9 @@ fn configure_baudrate<U: UartDevice>(
     wanted_baudrate: HertzU32,
     frequency: HertzU32,
 ) -> Result<HertzU32, Error> {
-    let (baud_div_int, baud_div_frac) = calculate_baudrate_dividers(wanted_baudrate, frequency)?;
+    let (baud_div_int, baud_div_frac) =
+        calculate_baudrate_dividers(wanted_baudrate.to_Hz(), frequency.to_Hz())
+            .ok_or(Error::BadArgument)?;
 
     // First we load the integer part of the divider.
     device.uartibrd.write(|w| unsafe {
@@ -355,12 +347,12 @@ impl<D: UartDevice, P: ValidUartPinout<D>> Read<u8> for UartPeripheral<Enabled,
     }
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::ErrorType for UartPeripheral<Enabled, D, P> {
     type Error = ReadErrorType;
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::Read<u8> for UartPeripheral<Enabled, D, P> {
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
         let byte: &mut [u8] = &mut [0; 1];
@@ -391,7 +383,7 @@ impl<D: UartDevice, P: ValidUartPinout<D>> Write<u8> for UartPeripheral<Enabled,
     }
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::Write<u8> for UartPeripheral<Enabled, D, P> {
     fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
         if self.write_raw(&[word]).is_err() {
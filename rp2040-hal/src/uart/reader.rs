@@ -8,8 +8,8 @@ use crate::pac::uart0::RegisterBlock;
 use embedded_hal::serial::Read;
 use nb::Error::*;
 
-#[cfg(feature = "eh1_0_alpha")]
-use eh_nb_1_0_alpha::serial as eh1nb;
+#[cfg(feature = "eh1")]
+use eh_nb_1_0::serial as eh1nb;
 
 /// When there's a read error.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -39,7 +39,7 @@ pub enum ReadErrorType {
     Framing,
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl eh1nb::Error for ReadErrorType {
     fn kind(&self) -> eh1nb::ErrorKind {
         match self {
@@ -257,12 +257,12 @@ unsafe impl<D: UartDevice, P: ValidUartPinout<D>> ReadTarget for Reader<D, P> {
 
 impl<D: UartDevice, P: ValidUartPinout<D>> EndlessReadTarget for Reader<D, P> {}
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::ErrorType for Reader<D, P> {
     type Error = ReadErrorType;
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::Read<u8> for Reader<D, P> {
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
         let byte: &mut [u8] = &mut [0; 1];
@@ -69,6 +69,8 @@ impl State for Disabled {}
 impl Sealed for Disabled {}
 
 /// Data bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DataBits {
     /// 5 bits
     Five,
@@ -81,6 +83,8 @@ pub enum DataBits {
 }
 
 /// Stop bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum StopBits {
     /// 1 bit
     One,
@@ -90,6 +94,8 @@ pub enum StopBits {
 
 /// Parity
 /// The "none" state of parity is represented with the Option type (None).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Parity {
     /// Odd parity
     Odd,
@@ -110,6 +116,8 @@ pub enum Parity {
 ///}
 /// ```
 #[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UartConfig {
     /// The baudrate the uart will run at.
     pub baudrate: HertzU32,
@@ -150,6 +158,8 @@ impl UartConfig {
 ///     uart0.set_rx_watermark(hal::uart::FifoWatermark::Bytes8);
 ///     uart0.enable_rx_interrupt();
 ///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FifoWatermark {
     /// Trigger when 4 bytes are (Rx: filled / Tx: available)
     Bytes4,
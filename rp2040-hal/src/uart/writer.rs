@@ -7,8 +7,8 @@ use crate::dma::{EndlessWriteTarget, WriteTarget};
 use crate::pac::uart0::RegisterBlock;
 use core::fmt;
 use core::{convert::Infallible, marker::PhantomData};
-#[cfg(feature = "eh1_0_alpha")]
-use eh_nb_1_0_alpha::serial as eh1nb;
+#[cfg(feature = "eh1")]
+use eh_nb_1_0::serial as eh1nb;
 use embedded_hal::serial::Write;
 use nb::Error::*;
 
@@ -84,7 +84,7 @@ pub(crate) fn write_full_blocking(rb: &RegisterBlock, data: &[u8]) {
         temp = match write_raw(rb, temp) {
             Ok(remaining) => remaining,
             Err(WouldBlock) => continue,
-            Err(_) => unreachable!(),
+            Err(Other(e)) => match e {},
         }
     }
 }
@@ -202,12 +202,12 @@ unsafe impl<D: UartDevice, P: ValidUartPinout<D>> WriteTarget for Writer<D, P> {
 
 impl<D: UartDevice, P: ValidUartPinout<D>> EndlessWriteTarget for Writer<D, P> {}
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::ErrorType for Writer<D, P> {
     type Error = core::convert::Infallible;
 }
 
-#[cfg(feature = "eh1_0_alpha")]
+#[cfg(feature = "eh1")]
 impl<D: UartDevice, P: ValidUartPinout<D>> eh1nb::Write<u8> for Writer<D, P> {
     fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
         if self.write_raw(&[word]).is_err() {
@@ -666,19 +666,16 @@ impl UsbBusTrait for UsbBus {
                 .buff_status
                 .write(|w| unsafe { w.bits(0x5555_5555) });
 
-            for i in 0..32u32 {
-                if buff_status == 0 {
-                    break;
-                } else if (buff_status & 1) == 1 {
-                    let is_in = (i & 1) == 0;
-                    let ep_idx = i / 2;
-                    if is_in {
-                        ep_in_complete |= 1 << ep_idx;
-                    } else {
-                        ep_out |= 1 << ep_idx;
-                    }
+            while buff_status != 0 {
+                let i = crate::rom_data::bits::ctz32(buff_status);
+                let is_in = (i & 1) == 0;
+                let ep_idx = i / 2;
+                if is_in {
+                    ep_in_complete |= 1 << ep_idx;
+                } else {
+                    ep_out |= 1 << ep_idx;
                 }
-                buff_status >>= 1;
+                buff_status &= buff_status - 1;
             }
 
             // check for setup request
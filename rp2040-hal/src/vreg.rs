@@ -0,0 +1,389 @@
+//! Core voltage regulator (VREG) and brown-out detector (BOD)
+//!
+//! The on-chip regulator supplies the digital core, from 0.85 V to 1.30 V in steps of 50 mV.
+//! Lowering the voltage saves power at low clock frequencies, and raising it is needed to run
+//! the chip above its rated frequency.
+//!
+//! The brown-out detector resets the chip when the core supply drops below a threshold. It is
+//! enabled at 0.86 V after power-on.
+//!
+//! See [Chapter 2 Section 10](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) of the datasheet for more details
+//!
+//! ## Usage
+//! ```no_run
+//! use fugit::RateExtU32;
+//! use rp2040_hal::{pac, vreg::{BodThreshold, ChipResetReason, Voltage, Vreg}};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut vreg = Vreg::new(pac.VREG_AND_CHIP_RESET);
+//!
+//! // Raise the voltage before overclocking the system clock to 200 MHz.
+//! vreg.set_voltage(Voltage::V1_15, 200.MHz()).unwrap();
+//!
+//! // Reset the chip earlier when the supply drops.
+//! vreg.enable_brown_out_detection(BodThreshold::from_millivolts(950).unwrap());
+//! if vreg.reset_reason() == Some(ChipResetReason::PowerOnOrBrownOut) {
+//!     // ...
+//! }
+//! ```
+//!
+//! The voltage needed for a frequency can also be checked at compile time:
+//! ```
+//! use fugit::HertzU32;
+//! use rp2040_hal::vreg::Voltage;
+//! const VOLTAGE: Voltage = match Voltage::for_frequency(HertzU32::MHz(250)) {
+//!     Some(voltage) => voltage,
+//!     None => panic!("frequency too high"),
+//! };
+//! ```
+
+use fugit::HertzU32;
+
+use crate::pac::{vreg_and_chip_reset, VREG_AND_CHIP_RESET};
+
+/// Errors for the voltage regulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The voltage is too low for the system clock frequency.
+    VoltageTooLow,
+}
+
+/// Output voltage of the regulator.
+///
+/// The discriminant is the value of the `VSEL` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Voltage {
+    /// 0.85 V
+    V0_85 = 0b0110,
+    /// 0.90 V
+    V0_90 = 0b0111,
+    /// 0.95 V
+    V0_95 = 0b1000,
+    /// 1.00 V
+    V1_00 = 0b1001,
+    /// 1.05 V
+    V1_05 = 0b1010,
+    /// 1.10 V, the default.
+    V1_10 = 0b1011,
+    /// 1.15 V
+    V1_15 = 0b1100,
+    /// 1.20 V
+    V1_20 = 0b1101,
+    /// 1.25 V
+    V1_25 = 0b1110,
+    /// 1.30 V
+    V1_30 = 0b1111,
+}
+
+/// Every voltage, from lowest to highest.
+const VOLTAGES: [Voltage; 10] = [
+    Voltage::V0_85,
+    Voltage::V0_90,
+    Voltage::V0_95,
+    Voltage::V1_00,
+    Voltage::V1_05,
+    Voltage::V1_10,
+    Voltage::V1_15,
+    Voltage::V1_20,
+    Voltage::V1_25,
+    Voltage::V1_30,
+];
+
+impl Voltage {
+    /// The voltage in millivolts.
+    pub const fn millivolts(self) -> u32 {
+        850 + (self as u32 - Voltage::V0_85 as u32) * 50
+    }
+
+    /// The voltage for a value in millivolts, if it is one of the supported steps.
+    pub const fn from_millivolts(millivolts: u32) -> Option<Self> {
+        let mut i = 0;
+        while i < VOLTAGES.len() {
+            if VOLTAGES[i].millivolts() == millivolts {
+                return Some(VOLTAGES[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// The voltage for a value of the `VSEL` field.
+    ///
+    /// Returns `None` for the values below 0.85 V.
+    pub const fn from_vsel(vsel: u8) -> Option<Self> {
+        match vsel {
+            0b0110..=0b1111 => Some(VOLTAGES[(vsel - Voltage::V0_85 as u8) as usize]),
+            _ => None,
+        }
+    }
+
+    /// Highest system clock frequency accepted at this voltage.
+    ///
+    /// The datasheet rates the RP2040 up to 133 MHz at the default 1.10 V, and gives no
+    /// frequency for the lower voltages. These are not accepted for a running system clock,
+    /// so that undervolting is left to [`Vreg::set_voltage_unchecked`].
+    ///
+    /// The frequencies above 1.10 V are overclocking margins, which the datasheet does not rate
+    /// and which are not guaranteed to work on every chip.
+    pub const fn max_frequency(self) -> HertzU32 {
+        HertzU32::MHz(match self {
+            Voltage::V0_85 | Voltage::V0_90 | Voltage::V0_95 | Voltage::V1_00 | Voltage::V1_05 => 0,
+            Voltage::V1_10 => 133,
+            // Overclocking, not rated by the datasheet.
+            Voltage::V1_15 => 200,
+            Voltage::V1_20 => 240,
+            Voltage::V1_25 => 270,
+            Voltage::V1_30 => 300,
+        })
+    }
+
+    /// Lowest voltage accepted for a system clock frequency, see [`Voltage::max_frequency`].
+    ///
+    /// This is never below the default 1.10 V. Returns `None` if the frequency is too high for
+    /// any voltage.
+    pub const fn for_frequency(frequency: HertzU32) -> Option<Self> {
+        let mut i = 0;
+        while i < VOLTAGES.len() {
+            if frequency.to_Hz() <= VOLTAGES[i].max_frequency().to_Hz() {
+                return Some(VOLTAGES[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// Threshold of the brown-out detector, from 0.473 V to 1.118 V in steps of 43 mV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BodThreshold(u8);
+
+impl BodThreshold {
+    /// The threshold after power-on, 0.86 V.
+    pub const DEFAULT: Self = Self(0b1001);
+
+    /// The threshold for a value of the `VSEL` field of the `BOD` register.
+    ///
+    /// Returns `None` if the value does not fit the 4-bit field.
+    pub const fn from_vsel(vsel: u8) -> Option<Self> {
+        if vsel <= 0b1111 {
+            Some(Self(vsel))
+        } else {
+            None
+        }
+    }
+
+    /// The lowest threshold at or above `millivolts`.
+    ///
+    /// Returns `None` if `millivolts` is above the highest threshold.
+    pub const fn from_millivolts(millivolts: u32) -> Option<Self> {
+        let above_min = millivolts.saturating_sub(BOD_MIN_MILLIVOLTS);
+        let vsel = (above_min + BOD_STEP_MILLIVOLTS - 1) / BOD_STEP_MILLIVOLTS;
+        if vsel <= 0b1111 {
+            Some(Self(vsel as u8))
+        } else {
+            None
+        }
+    }
+
+    /// The threshold in millivolts.
+    pub const fn millivolts(self) -> u32 {
+        BOD_MIN_MILLIVOLTS + self.0 as u32 * BOD_STEP_MILLIVOLTS
+    }
+
+    /// The value of the `VSEL` field of the `BOD` register.
+    pub const fn vsel(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for BodThreshold {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Lowest threshold of the brown-out detector.
+const BOD_MIN_MILLIVOLTS: u32 = 473;
+
+/// Step between the thresholds of the brown-out detector.
+const BOD_STEP_MILLIVOLTS: u32 = 43;
+
+/// Source of the last chip-level reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChipResetReason {
+    /// The power-on reset or the brown-out detector. The hardware does not tell them apart.
+    PowerOnOrBrownOut,
+    /// The RUN pin.
+    RunPin,
+    /// A restart requested from the debug port.
+    DebugPort,
+}
+
+/// Decode the chip-level reset flags, shared with `Watchdog::reset_reason`.
+pub(crate) fn chip_reset_reason(
+    device: &vreg_and_chip_reset::RegisterBlock,
+) -> Option<ChipResetReason> {
+    let chip_reset = device.chip_reset.read();
+    if chip_reset.had_por().bit_is_set() {
+        Some(ChipResetReason::PowerOnOrBrownOut)
+    } else if chip_reset.had_run().bit_is_set() {
+        Some(ChipResetReason::RunPin)
+    } else if chip_reset.had_psm_restart().bit_is_set() {
+        Some(ChipResetReason::DebugPort)
+    } else {
+        None
+    }
+}
+
+/// The core voltage regulator and brown-out detector.
+pub struct Vreg {
+    device: VREG_AND_CHIP_RESET,
+}
+
+impl Vreg {
+    /// Create a new [`Vreg`]
+    pub fn new(device: VREG_AND_CHIP_RESET) -> Self {
+        Self { device }
+    }
+
+    /// The current output voltage.
+    ///
+    /// Returns `None` if set below 0.85 V.
+    pub fn voltage(&self) -> Option<Voltage> {
+        Voltage::from_vsel(self.device.vreg.read().vsel().bits())
+    }
+
+    /// Whether the regulator output is in regulation.
+    ///
+    /// This is false for a short time after raising the voltage.
+    pub fn is_regulating(&self) -> bool {
+        self.device.vreg.read().rok().bit_is_set()
+    }
+
+    /// Set the output voltage, checking it is high enough for `sys_freq`.
+    ///
+    /// `sys_freq` is the system clock frequency the chip runs at with this voltage. When
+    /// raising the frequency, set the voltage first; when lowering it, change the clocks first.
+    /// This waits for the regulator output to be in regulation.
+    pub fn set_voltage(&mut self, voltage: Voltage, sys_freq: HertzU32) -> Result<(), Error> {
+        if sys_freq > voltage.max_frequency() {
+            return Err(Error::VoltageTooLow);
+        }
+        // Safety: the voltage is high enough for the frequency, within the limits above.
+        unsafe { self.set_voltage_unchecked(voltage) };
+        Ok(())
+    }
+
+    /// Set the output voltage without checking the system clock frequency.
+    ///
+    /// This waits for the regulator output to be in regulation.
+    ///
+    /// # Safety
+    ///
+    /// The voltage must be high enough for the clocks the chip runs at, or the chip may behave
+    /// erratically.
+    pub unsafe fn set_voltage_unchecked(&mut self, voltage: Voltage) {
+        self.device
+            .vreg
+            .modify(|_, w| unsafe { w.vsel().bits(voltage as u8) });
+        while !self.is_regulating() {}
+    }
+
+    /// Enable the brown-out detector with the given threshold.
+    pub fn enable_brown_out_detection(&mut self, threshold: BodThreshold) {
+        self.device
+            .bod
+            .write(|w| unsafe { w.vsel().bits(threshold.vsel()).en().set_bit() });
+    }
+
+    /// Disable the brown-out detector.
+    ///
+    /// The chip then keeps running, possibly erratically, while its supply is too low.
+    pub fn disable_brown_out_detection(&mut self) {
+        self.device.bod.modify(|_, w| w.en().clear_bit());
+    }
+
+    /// The threshold of the brown-out detector, or `None` if it is disabled.
+    pub fn brown_out_detection(&self) -> Option<BodThreshold> {
+        let bod = self.device.bod.read();
+        if bod.en().bit_is_set() {
+            Some(BodThreshold(bod.vsel().bits()))
+        } else {
+            None
+        }
+    }
+
+    /// Source of the last chip-level reset.
+    ///
+    /// Returns `None` if the chip was not reset by one of these sources since power-on, e.g.
+    /// if it was only reset by the watchdog.
+    pub fn reset_reason(&self) -> Option<ChipResetReason> {
+        chip_reset_reason(&self.device)
+    }
+
+    /// Releases the underlying device.
+    pub fn free(self) -> VREG_AND_CHIP_RESET {
+        self.device
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voltage_steps() {
+        assert_eq!(Voltage::V0_85.millivolts(), 850);
+        assert_eq!(Voltage::V1_10.millivolts(), 1100);
+        assert_eq!(Voltage::V1_30.millivolts(), 1300);
+        assert_eq!(Voltage::from_millivolts(1150), Some(Voltage::V1_15));
+        assert_eq!(Voltage::from_millivolts(1120), None);
+        assert_eq!(Voltage::from_vsel(0b1011), Some(Voltage::V1_10));
+        assert_eq!(Voltage::from_vsel(0b0101), None);
+    }
+
+    #[test]
+    fn voltage_for_frequency() {
+        // Never below the rated 1.10 V
+        assert_eq!(
+            Voltage::for_frequency(HertzU32::MHz(48)),
+            Some(Voltage::V1_10)
+        );
+        assert_eq!(
+            Voltage::for_frequency(HertzU32::MHz(133)),
+            Some(Voltage::V1_10)
+        );
+        assert_eq!(
+            Voltage::for_frequency(HertzU32::MHz(200)),
+            Some(Voltage::V1_15)
+        );
+        assert_eq!(Voltage::for_frequency(HertzU32::MHz(500)), None);
+    }
+
+    #[test]
+    fn bod_threshold() {
+        assert_eq!(BodThreshold::DEFAULT.millivolts(), 860);
+        assert_eq!(
+            BodThreshold::from_millivolts(860),
+            Some(BodThreshold::DEFAULT)
+        );
+        assert_eq!(
+            BodThreshold::from_millivolts(0).map(|t| t.millivolts()),
+            Some(473)
+        );
+        assert_eq!(
+            BodThreshold::from_millivolts(900).map(|t| t.millivolts()),
+            Some(903)
+        );
+        assert_eq!(
+            BodThreshold::from_millivolts(1118).map(|t| t.vsel()),
+            Some(0b1111)
+        );
+        assert_eq!(BodThreshold::from_millivolts(1119), None);
+        assert_eq!(BodThreshold::from_vsel(0b10000), None);
+    }
+}
@@ -32,22 +32,48 @@
 //! // Stop feeding, now we'll reset
 //! loop {}
 //! ```
+//!
+//! The scratch registers keep their value across watchdog resets, e.g. to count the reboots:
+//! ```no_run
+//! use rp2040_hal::{pac, watchdog::{ScratchRegister, Watchdog}};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut watchdog = Watchdog::new(pac.WATCHDOG);
+//! let boot_count = watchdog.read_scratch(ScratchRegister::Scratch0);
+//! watchdog.write_scratch(ScratchRegister::Scratch0, boot_count.wrapping_add(1));
+//! ```
 //! See [examples/watchdog.rs](https://github.com/rp-rs/rp-hal/tree/main/rp2040-hal/examples/watchdog.rs) for a more complete example
 
 use embedded_hal::watchdog;
 use fugit::MicrosDurationU32;
 
 use crate::pac::{self, WATCHDOG};
+use crate::power::PowerDomain;
+use crate::vreg::{chip_reset_reason, ChipResetReason};
+
+/// Every domain of the PSM.
+const ALL_DOMAINS: u32 = 0x0001ffff;
+
+/// The domains reset by default: everything except the oscillators, which keep running.
+const DEFAULT_RESET_DOMAINS: u32 =
+    ALL_DOMAINS & !PowerDomain::Rosc.mask() & !PowerDomain::Xosc.mask();
 
 /// Watchdog peripheral
 pub struct Watchdog {
     watchdog: WATCHDOG,
     load_value: u32, // decremented by 2 per tick (µs)
+    reset_domains: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(missing_docs)]
 /// Scratch registers of the watchdog peripheral
+///
+/// They keep their value across watchdog and soft resets, but not across a power cycle or a
+/// reset through the RUN pin. The bootrom checks `Scratch4` to `Scratch7` after a watchdog
+/// reset, to boot into a given entry point or into BOOTSEL mode, so only `Scratch0` to
+/// `Scratch3` are free for the application. [`panic_persist`](crate::panic_persist) uses
+/// `Scratch3` to mark its record, leaving `Scratch0` to `Scratch2` when it is used.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ScratchRegister {
     Scratch0,
     Scratch1,
@@ -59,12 +85,26 @@ pub enum ScratchRegister {
     Scratch7,
 }
 
+/// Cause of the last reset of the chip, see [`Watchdog::reset_reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResetReason {
+    /// A chip-level reset, see [`Vreg::reset_reason`](crate::vreg::Vreg::reset_reason).
+    Chip(ChipResetReason),
+    /// The watchdog was not fed in time.
+    WatchdogTimeout,
+    /// The watchdog was triggered by software, e.g. by [`crate::reset`] or when rebooting into
+    /// BOOTSEL mode.
+    WatchdogForced,
+}
+
 impl Watchdog {
     /// Create a new [`Watchdog`]
     pub fn new(watchdog: WATCHDOG) -> Self {
         Self {
             watchdog,
             load_value: 0,
+            reset_domains: DEFAULT_RESET_DOMAINS,
         }
     }
 
@@ -99,6 +139,23 @@ impl Watchdog {
         })
     }
 
+    /// The cause of the last reset, or `None` if it is unknown.
+    ///
+    /// The watchdog resets take precedence, as the chip level reset flags of
+    /// [`Vreg::reset_reason`](crate::vreg::Vreg::reset_reason) are kept across them.
+    pub fn reset_reason(&self) -> Option<ResetReason> {
+        let reason = self.watchdog.reason.read();
+        if reason.timer().bit_is_set() {
+            return Some(ResetReason::WatchdogTimeout);
+        }
+        if reason.force().bit_is_set() {
+            return Some(ResetReason::WatchdogForced);
+        }
+        // Safety: only reads the reset flags
+        let chip_reset = unsafe { &*pac::VREG_AND_CHIP_RESET::ptr() };
+        chip_reset_reason(chip_reset).map(ResetReason::Chip)
+    }
+
     fn load_counter(&self, counter: u32) {
         self.watchdog.load.write(|w| unsafe { w.bits(counter) });
     }
@@ -135,6 +192,51 @@ impl Watchdog {
         }
     }
 
+    /// Choose the power domains reset by the watchdog.
+    ///
+    /// By default, everything but the oscillators is reset. Domains left out keep running
+    /// and keep their configuration across a watchdog reset, e.g. [`PowerDomain::Clocks`] to
+    /// keep the clock tree, and including [`PowerDomain::Rosc`] and [`PowerDomain::Xosc`]
+    /// resets the chip as fully as a power-on. Leaving out a domain needed to boot, like a
+    /// processor or the bootrom, prevents the chip from restarting.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{pac, power::PowerDomain, watchdog::Watchdog};
+    /// let mut pac = pac::Peripherals::take().unwrap();
+    /// let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    /// // Reset everything, as for a power-on
+    /// watchdog.set_reset_domains([
+    ///     PowerDomain::Rosc, PowerDomain::Xosc, PowerDomain::Clocks, PowerDomain::Resets,
+    ///     PowerDomain::Busfabric, PowerDomain::Rom, PowerDomain::Sram0, PowerDomain::Sram1,
+    ///     PowerDomain::Sram2, PowerDomain::Sram3, PowerDomain::Sram4, PowerDomain::Sram5,
+    ///     PowerDomain::Xip, PowerDomain::VregAndChipReset, PowerDomain::Sio,
+    ///     PowerDomain::Proc0, PowerDomain::Proc1,
+    /// ]);
+    /// ```
+    ///
+    /// This takes effect immediately, and is kept by
+    /// [`start`](embedded_hal::watchdog::WatchdogEnable::start).
+    pub fn set_reset_domains(&mut self, domains: impl IntoIterator<Item = PowerDomain>) {
+        self.reset_domains = domains.into_iter().fold(0, |bits, d| bits | d.mask());
+        // Safety: nothing else writes to psm.wdsel
+        unsafe { self.configure_wdog_reset_triggers() }
+    }
+
+    /// Reset the chip right away through the watchdog.
+    ///
+    /// Unlike [`crate::reset`], which resets every block, only the domains selected with
+    /// [`set_reset_domains`](Self::set_reset_domains) are reset. [`reset_reason`](Self::reset_reason)
+    /// then reports [`ResetReason::WatchdogForced`]. To reboot into BOOTSEL mode instead, see
+    /// [`rom_data::reboot_to_usb_boot`](crate::rom_data::reboot_to_usb_boot).
+    pub fn reboot(&mut self) -> ! {
+        cortex_m::interrupt::disable();
+        // Safety: nothing else writes to psm.wdsel
+        unsafe { self.configure_wdog_reset_triggers() };
+        self.watchdog.ctrl.write(|w| w.trigger().set_bit());
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
+
     /// Configure which hardware will be reset by the watchdog
     /// the default is everything except ROSC, XOSC
     ///
@@ -142,12 +244,7 @@ impl Watchdog {
     /// This is easy at the moment, since nothing else uses PSM
     unsafe fn configure_wdog_reset_triggers(&self) {
         let psm = &*pac::PSM::ptr();
-        psm.wdsel.write_with_zero(|w| {
-            w.bits(0x0001ffff);
-            w.xosc().clear_bit();
-            w.rosc().clear_bit();
-            w
-        });
+        psm.wdsel.write_with_zero(|w| w.bits(self.reset_domains));
     }
 }
 
@@ -0,0 +1,266 @@
+//! Execute-in-place (XIP) cache control
+//!
+//! Code and data in flash are read through a 16 kB cache. [`XipCache`] allows flushing it,
+//! disabling it, reading its hit and access counters for profiling, and using its memory as
+//! additional SRAM while it is disabled.
+//!
+//! The functions of the [`flash`](crate::flash) module already flush the cache after erasing
+//! or programming, so reads through the XIP window return the new contents.
+//!
+//! See [Chapter 2 Section 6.3](https://datasheets.raspberrypi.org/rp2040/rp2040_datasheet.pdf) of the datasheet for more details
+//!
+//! ## Usage
+//! ```no_run
+//! use rp2040_hal::{pac, xip::XipCache};
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let mut cache = XipCache::new(pac.XIP_CTRL);
+//! cache.clear_counters();
+//! // ... run some code ...
+//! let hit_rate = cache.hit_count() as f32 / cache.access_count() as f32;
+//! ```
+//!
+//! ## Streaming
+//!
+//! [`XipCache::stream`] reads a block of flash into the stream FIFO in the background,
+//! bypassing the cache, and returns an [`XipStream`] which can be used as the source of a DMA
+//! transfer. This is suited to large assets such as fonts, audio or images, which would
+//! otherwise evict the program code from the cache.
+//!
+//! ```no_run
+//! use rp2040_hal::{dma::{single_buffer, DMAExt}, pac, xip::XipCache};
+//! static IMAGE: [u32; 1024] = [0; 1024];
+//! let mut pac = pac::Peripherals::take().unwrap();
+//! let dma = pac.DMA.split(&mut pac.RESETS);
+//! let cache = XipCache::new(pac.XIP_CTRL);
+//! let buffer = cortex_m::singleton!(: [u32; 1024] = [0; 1024]).unwrap();
+//! let stream = cache.stream(&IMAGE);
+//! let transfer = single_buffer::Config::new(dma.ch0, stream, buffer).start();
+//! let (_ch, stream, buffer) = transfer.wait();
+//! let cache = stream.free();
+//! ```
+//!
+//! ## Cache as SRAM
+//!
+//! While the cache is disabled, flash is still readable but every access goes to the flash
+//! chip, and the cache memory is available at [`XIP_SRAM_BASE`]. To place statics there, add
+//! a region to `memory.x`:
+//!
+//! ```text
+//! MEMORY {
+//!     ...
+//!     XIP_SRAM : ORIGIN = 0x15000000, LENGTH = 16K
+//! }
+//! SECTIONS {
+//!     .xip_sram (NOLOAD) : { *(.xip_sram .xip_sram.*) } > XIP_SRAM
+//! }
+//! ```
+//!
+//! and mark them `#[link_section = ".xip_sram"]`. Such statics are not initialized by the
+//! runtime and must only be used between [`XipCache::into_sram`] and [`CacheSram::free`].
+
+use crate::{
+    dma::ReadTarget,
+    pac::{dma::ch::ch_ctrl_trig::TREQ_SEL_A, XIP_CTRL},
+};
+
+/// Start of the cache memory, when used as SRAM.
+pub const XIP_SRAM_BASE: usize = 0x1500_0000;
+
+/// Size of the cache memory, in bytes.
+pub const XIP_SRAM_SIZE: usize = 16 * 1024;
+
+/// Start of the XIP address range, including all of its cached and uncached aliases.
+const XIP_BASE: usize = 0x1000_0000;
+
+/// End of the XIP address range.
+const XIP_END: usize = 0x1400_0000;
+
+/// Address of the stream FIFO on the XIP_AUX port, which is not stalled by other XIP traffic.
+const XIP_AUX_BASE: u32 = 0x5040_0000;
+
+/// Largest number of words a single stream can transfer.
+pub const MAX_STREAM_WORDS: usize = (1 << 22) - 1;
+
+/// XIP cache controller
+pub struct XipCache {
+    xip_ctrl: XIP_CTRL,
+}
+
+impl XipCache {
+    /// Create a new [`XipCache`]
+    pub fn new(xip_ctrl: XIP_CTRL) -> Self {
+        Self { xip_ctrl }
+    }
+
+    /// Invalidate the whole cache, waiting for the flush to complete.
+    ///
+    /// This is needed if the flash contents changed behind the cache's back, for example when
+    /// the flash was programmed without going through the [`flash`](crate::flash) module.
+    pub fn flush(&mut self) {
+        self.xip_ctrl.flush.write(|w| w.flush().set_bit());
+        while self.xip_ctrl.stat.read().flush_ready().bit_is_clear() {}
+    }
+
+    /// Enable the cache.
+    ///
+    /// The cache is enabled at boot. The cache must have been flushed since it was last used
+    /// as SRAM, see [`CacheSram::free`].
+    pub fn enable(&mut self) {
+        self.xip_ctrl.ctrl.modify(|_, w| w.en().set_bit());
+    }
+
+    /// Disable the cache, so every XIP access goes to the flash chip.
+    pub fn disable(&mut self) {
+        self.xip_ctrl.ctrl.modify(|_, w| w.en().clear_bit());
+    }
+
+    /// Check whether the cache is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.xip_ctrl.ctrl.read().en().bit_is_set()
+    }
+
+    /// Number of XIP accesses served from the cache since the counters were cleared.
+    ///
+    /// The counter saturates at `u32::MAX`.
+    pub fn hit_count(&self) -> u32 {
+        self.xip_ctrl.ctr_hit.read().bits()
+    }
+
+    /// Number of XIP accesses, whether cached or not, since the counters were cleared.
+    ///
+    /// The counter saturates at `u32::MAX`.
+    pub fn access_count(&self) -> u32 {
+        self.xip_ctrl.ctr_acc.read().bits()
+    }
+
+    /// Reset the hit and access counters to zero.
+    pub fn clear_counters(&mut self) {
+        // Writing any value clears the counters.
+        self.xip_ctrl.ctr_hit.write(|w| unsafe { w.bits(0) });
+        self.xip_ctrl.ctr_acc.write(|w| unsafe { w.bits(0) });
+    }
+
+    /// Disable the cache and use its memory as SRAM.
+    ///
+    /// Flash stays accessible, but uncached.
+    pub fn into_sram(mut self) -> CacheSram {
+        self.xip_ctrl.ctrl.modify(|_, w| w.power_down().clear_bit());
+        self.disable();
+        CacheSram { cache: self }
+    }
+
+    /// Start streaming `data` from flash into the stream FIFO.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` is not located in flash, or is longer than [`MAX_STREAM_WORDS`].
+    pub fn stream(self, data: &'static [u32]) -> XipStream {
+        let start = data.as_ptr() as usize;
+        assert!((XIP_BASE..XIP_END).contains(&start) && start + data.len() * 4 <= XIP_END);
+        assert!(data.len() <= MAX_STREAM_WORDS);
+
+        let mut stream = XipStream {
+            cache: self,
+            words: data.len() as u32,
+        };
+        stream.stop();
+        let xip_ctrl = &stream.cache.xip_ctrl;
+        xip_ctrl
+            .stream_addr
+            .write(|w| unsafe { w.bits(start as u32) });
+        xip_ctrl
+            .stream_ctr
+            .write(|w| unsafe { w.stream_ctr().bits(stream.words) });
+        stream
+    }
+
+    /// Release the underlying device.
+    pub fn free(self) -> XIP_CTRL {
+        self.xip_ctrl
+    }
+}
+
+/// The XIP cache memory, used as SRAM while the cache is disabled.
+pub struct CacheSram {
+    cache: XipCache,
+}
+
+impl CacheSram {
+    /// The whole cache memory, as words.
+    ///
+    /// The initial contents are whatever the cache held when it was disabled.
+    pub fn as_mut_slice(&mut self) -> &mut [u32] {
+        // Safety: the memory is not used by the cache while it is disabled, and `self` is
+        // borrowed for the lifetime of the slice.
+        unsafe { core::slice::from_raw_parts_mut(XIP_SRAM_BASE as *mut u32, XIP_SRAM_SIZE / 4) }
+    }
+
+    /// Flush and re-enable the cache, giving up the memory.
+    pub fn free(self) -> XipCache {
+        let mut cache = self.cache;
+        cache.flush();
+        cache.enable();
+        cache
+    }
+}
+
+/// A block of flash being streamed into the stream FIFO.
+pub struct XipStream {
+    cache: XipCache,
+    words: u32,
+}
+
+impl XipStream {
+    /// Check whether all words have been read from flash into the FIFO.
+    ///
+    /// Words may still be waiting in the FIFO.
+    pub fn is_done(&self) -> bool {
+        self.cache.xip_ctrl.stream_ctr.read().stream_ctr().bits() == 0
+    }
+
+    /// Read one word from the FIFO, if available.
+    pub fn read(&mut self) -> Option<u32> {
+        let xip_ctrl = &self.cache.xip_ctrl;
+        if xip_ctrl.stat.read().fifo_empty().bit_is_set() {
+            None
+        } else {
+            Some(xip_ctrl.stream_fifo.read().bits())
+        }
+    }
+
+    /// Abort the stream and discard any words left in the FIFO.
+    fn stop(&mut self) {
+        let xip_ctrl = &self.cache.xip_ctrl;
+        xip_ctrl
+            .stream_ctr
+            .write(|w| unsafe { w.stream_ctr().bits(0) });
+        while xip_ctrl.stat.read().fifo_empty().bit_is_clear() {
+            xip_ctrl.stream_fifo.read();
+        }
+    }
+
+    /// Abort the stream if it is still running, and release the cache controller.
+    pub fn free(mut self) -> XipCache {
+        self.stop();
+        self.cache
+    }
+}
+
+// Safety: This only reads from the stream FIFO, so it doesn't
+// interact with rust-managed memory.
+unsafe impl ReadTarget for XipStream {
+    type ReceivedWord = u32;
+
+    fn rx_treq() -> Option<u8> {
+        Some(TREQ_SEL_A::XIP_STREAM.into())
+    }
+
+    fn rx_address_count(&self) -> (u32, u32) {
+        // The counter in STREAM_CTR counts words read from flash, not from the FIFO.
+        (XIP_AUX_BASE, self.words)
+    }
+
+    fn rx_increment(&self) -> bool {
+        false
+    }
+}
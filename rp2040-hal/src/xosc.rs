@@ -7,7 +7,12 @@ use core::{convert::Infallible, ops::RangeInclusive};
 use fugit::HertzU32;
 use nb::Error::WouldBlock;
 
-use crate::{pac::XOSC, typelevel::Sealed};
+use crate::{
+    clocks::ClocksManager,
+    pac::XOSC,
+    power::{self, DormantOscillator, WakeSources},
+    typelevel::Sealed,
+};
 
 /// State of the Crystal Oscillator (typestate trait)
 pub trait State: Sealed {}
@@ -91,33 +96,58 @@ impl CrystalOscillator<Disabled> {
     }
 
     /// Initializes the XOSC : frequency range is set, startup delay is calculated and set.
+    ///
+    /// The startup delay is 1 ms, which is enough for most crystals.
     pub fn initialize(self, frequency: HertzU32) -> Result<CrystalOscillator<Initialized>, Error> {
+        self.initialize_with_delay_us(frequency, 1000)
+    }
+
+    /// Initializes the XOSC with a startup delay of at least `delay_us` microseconds.
+    ///
+    /// The delay is rounded up to a multiple of 256 XOSC cycles. Fast-starting crystals can use
+    /// a delay shorter than the 1 ms of [`initialize`](Self::initialize).
+    pub fn initialize_with_delay_us(
+        self,
+        frequency: HertzU32,
+        delay_us: u32,
+    ) -> Result<CrystalOscillator<Initialized>, Error> {
+        //startup_delay = (freq_hz * delay_s) / 256
+        //See Chapter 2, Section 16, §3)
+        let cycles = u64::from(frequency.to_Hz()) * u64::from(delay_us);
+        let startup_delay = (cycles + 256 * 1_000_000 - 1) / (256 * 1_000_000);
+        let startup_delay: u16 = startup_delay.try_into().map_err(|_| Error::BadArgument)?;
+        self.initialize_with_startup_cycles(frequency, startup_delay, false)
+    }
+
+    /// Initializes the XOSC with a raw STARTUP register value.
+    ///
+    /// The startup delay is `delay` multiples of 256 XOSC cycles, themselves multiplied by 4 if
+    /// `x4` is set. `delay` is 14 bits wide, larger values return [`Error::BadArgument`].
+    pub fn initialize_with_startup_cycles(
+        self,
+        frequency: HertzU32,
+        delay: u16,
+        x4: bool,
+    ) -> Result<CrystalOscillator<Initialized>, Error> {
         const ALLOWED_FREQUENCY_RANGE: RangeInclusive<HertzU32> =
             HertzU32::MHz(1)..=HertzU32::MHz(15);
-        //1 ms = 10e-3 sec and Freq = 1/T where T is in seconds so 1ms converts to 1000Hz
-        const STABLE_DELAY_AS_HZ: HertzU32 = HertzU32::Hz(1000);
-        const DIVIDER: u32 = 256;
+        const MAX_DELAY: u16 = (1 << 14) - 1;
 
         if !ALLOWED_FREQUENCY_RANGE.contains(&frequency) {
             return Err(Error::FrequencyOutOfRange);
         }
+        if delay > MAX_DELAY {
+            return Err(Error::BadArgument);
+        }
 
         self.device.ctrl.write(|w| {
             w.freq_range()._1_15mhz();
             w
         });
 
-        //startup_delay = ((freq_hz * STABLE_DELAY) / 256) = ((freq_hz / delay_to_hz) / 256)
-        //              = freq_hz / (delay_to_hz * 256)
-        //See Chapter 2, Section 16, §3)
-        //We do the calculation first.
-        let startup_delay = frequency.to_Hz() / (STABLE_DELAY_AS_HZ.to_Hz() * DIVIDER);
-
-        //Then we check if it fits into an u16.
-        let startup_delay: u16 = startup_delay.try_into().map_err(|_| Error::BadArgument)?;
-
         self.device.startup.write(|w| unsafe {
-            w.delay().bits(startup_delay);
+            w.delay().bits(delay);
+            w.x4().bit(x4);
             w
         });
 
@@ -158,6 +188,30 @@ impl CrystalOscillator<Stable> {
         self.state.freq_hz
     }
 
+    /// Busy wait for `cycles` cycles of the XOSC, using its COUNT register.
+    ///
+    /// This does not need any clock to be set up, which makes it usable very early in the boot.
+    pub fn delay_cycles(&self, cycles: u8) {
+        // The COUNT register at offset 0x1c is missing from the PAC. It counts down to zero at
+        // the XOSC frequency.
+        let count = unsafe { (XOSC::ptr() as *mut u8).add(0x1c).cast::<u32>() };
+        unsafe {
+            count.write_volatile(cycles.into());
+            while count.read_volatile() != 0 {}
+        }
+    }
+
+    /// Busy wait for at least `us` microseconds, using the COUNT register of the XOSC.
+    pub fn delay_us(&self, us: u32) {
+        let hz = u64::from(self.state.freq_hz.to_Hz());
+        let mut cycles = (u64::from(us) * hz + 999_999) / 1_000_000;
+        while cycles > 0 {
+            let n = cycles.min(u64::from(u8::MAX));
+            self.delay_cycles(n as u8);
+            cycles -= n;
+        }
+    }
+
     /// Disables the XOSC
     pub fn disable(self) -> CrystalOscillator<Disabled> {
         self.device.ctrl.modify(|_r, w| {
@@ -168,8 +222,34 @@ impl CrystalOscillator<Stable> {
         self.transition(Disabled)
     }
 
+    /// Enter DORMANT mode until one of the wake sources triggers, with the chip running from
+    /// the XOSC.
+    ///
+    /// The PLLs are stopped, the wake sources armed, and the XOSC put in DORMANT state. Once woken
+    /// up, the XOSC is stable again and the clock tree is restored. See
+    /// [`power::dormant`](crate::power::dormant) for the details.
+    ///
+    /// ```no_run
+    /// use rp2040_hal::{gpio::Interrupt, power::WakeSources};
+    /// # let pins: rp2040_hal::gpio::Pins = todo!();
+    /// let button = pins.gpio15.into_pull_up_input();
+    /// # let mut clocks: rp2040_hal::clocks::ClocksManager = todo!();
+    /// # let mut xosc: rp2040_hal::xosc::CrystalOscillator<rp2040_hal::xosc::Stable> = todo!();
+    /// let wake = WakeSources::new().gpio(button.id(), Interrupt::EdgeLow);
+    /// xosc.dormant_until(&mut clocks, &wake).unwrap();
+    /// ```
+    pub fn dormant_until(
+        &mut self,
+        clocks: &mut ClocksManager,
+        wake: &WakeSources,
+    ) -> Result<(), power::Error> {
+        power::dormant(clocks, DormantOscillator::Xosc, wake)
+    }
+
     /// Put the XOSC in DORMANT state.
     ///
+    /// Prefer [`dormant_until`](Self::dormant_until), which takes care of the clocks.
+    ///
     /// # Safety
     /// This method is marked unsafe because prior to switch the XOSC into DORMANT state,
     /// PLLs must be stopped and IRQs have to be properly configured.